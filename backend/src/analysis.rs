@@ -0,0 +1,312 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Languages with a dedicated analyzer. Anything we cannot classify falls
+/// back to `Unknown`, which only lowercases and drops stop words of every
+/// supported language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    English,
+    Hungarian,
+    Unknown,
+}
+
+impl Language {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Hungarian => "hu",
+            Language::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Self {
+        match code.to_lowercase().as_str() {
+            "en" | "english" => Language::English,
+            "hu" | "hungarian" | "magyar" => Language::Hungarian,
+            _ => Language::Unknown,
+        }
+    }
+}
+
+const ENGLISH_STOP_WORDS: &[&str] = &[
+    "what", "do", "does", "did", "is", "are", "was", "were", "be", "been", "being",
+    "have", "has", "had", "having", "will", "would", "could", "should", "can", "may",
+    "might", "must", "shall", "the", "a", "an", "and", "or", "but", "in", "on", "at",
+    "to", "for", "of", "with", "by", "from", "up", "about", "into", "through", "during",
+    "before", "after", "above", "below", "between", "among", "this", "that", "these",
+    "those", "i", "you", "he", "she", "it", "we", "they", "me", "him", "her", "us",
+    "them", "my", "your", "his", "its", "our", "their", "mine", "yours", "hers",
+    "ours", "theirs", "who", "whom", "whose", "which", "where", "when", "why", "how",
+    "all", "any", "both", "each", "few", "more", "most", "other", "some", "such", "no",
+    "nor", "not", "only", "own", "same", "so", "than", "too", "very", "just", "now",
+];
+
+const HUNGARIAN_STOP_WORDS: &[&str] = &[
+    "a", "az", "egy", "és", "is", "nem", "hogy", "de", "meg", "már", "még", "csak",
+    "mint", "ha", "vagy", "sem", "pedig", "van", "volt", "lesz", "lett", "vannak",
+    "voltak", "nincs", "nincsenek", "ez", "azt", "ezt", "ezek", "azok", "ezért",
+    "azért", "itt", "ott", "mi", "mit", "mik", "ki", "kik", "kit", "hol", "hova",
+    "honnan", "mikor", "miért", "hogyan", "milyen", "melyik", "mennyi", "amely",
+    "amelyek", "ami", "amit", "aki", "akik", "ahol", "amikor", "én", "te", "ő", "mi",
+    "ti", "ők", "engem", "téged", "őt", "minket", "titeket", "őket", "nekem", "neked",
+    "neki", "nekünk", "nektek", "nekik", "az", "el", "fel", "le", "ki", "be", "rá",
+    "össze", "vissza", "után", "előtt", "alatt", "felett", "között", "mellett",
+    "szerint", "miatt", "nagyon", "minden", "mindig", "soha", "sok", "kevés",
+    "lehet", "kell", "kellene", "tud", "tudja", "lehetne", "illetve", "valamint",
+    "továbbá", "azonban", "viszont", "tehát", "akkor", "aztán", "majd", "mert",
+];
+
+// Case endings, longest first so that e.g. "-ből" wins over "-t".
+const HUNGARIAN_CASE_SUFFIXES: &[&str] = &[
+    "ként", "ból", "ből", "ról", "ről", "tól", "től", "ban", "ben", "nak", "nek",
+    "val", "vel", "hoz", "hez", "höz", "nál", "nél", "kor", "ért", "ig", "ra", "re",
+    "ba", "be", "on", "en", "ön", "at", "et", "ot", "öt", "t",
+];
+
+// Plural and possessive markers, stripped after the case ending.
+const HUNGARIAN_NUMBER_SUFFIXES: &[&str] = &[
+    "jai", "jei", "ok", "ek", "ök", "ak", "ai", "ei", "ja", "je", "uk", "ük", "k",
+];
+
+const ENGLISH_SUFFIXES: &[&str] = &[
+    "ational", "ization", "fulness", "ousness", "iveness", "ations", "ements",
+    "ments", "ation", "ement", "ness", "ment", "ings", "ing", "ies", "ied", "edly",
+    "ed", "ly", "es", "s",
+];
+
+/// Guess the dominant language of `text` from stop-word hits and
+/// Hungarian-specific letters. Short or ambiguous text is `Unknown`.
+pub fn detect_language(text: &str) -> Language {
+    let english: HashSet<&str> = ENGLISH_STOP_WORDS.iter().copied().collect();
+    let hungarian: HashSet<&str> = HUNGARIAN_STOP_WORDS.iter().copied().collect();
+
+    let mut english_hits = 0usize;
+    let mut hungarian_hits = 0usize;
+    let mut total = 0usize;
+
+    for token in tokenize(text).iter().take(2000) {
+        total += 1;
+        if english.contains(token.as_str()) {
+            english_hits += 1;
+        }
+        if hungarian.contains(token.as_str()) {
+            hungarian_hits += 1;
+        }
+        // ő and ű only occur in Hungarian among the languages we care about
+        if token.contains(['ő', 'ű']) {
+            hungarian_hits += 2;
+        }
+    }
+
+    if total == 0 || english_hits + hungarian_hits == 0 {
+        return Language::Unknown;
+    }
+
+    if hungarian_hits > english_hits {
+        Language::Hungarian
+    } else if english_hits > hungarian_hits {
+        Language::English
+    } else {
+        Language::Unknown
+    }
+}
+
+/// Map accented Latin letters to their unaccented base letter so that
+/// `é` matches `e`, `ő` matches `o` and so on.
+pub fn fold_accents(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ä' | 'ã' | 'å' => 'a',
+            'Á' | 'À' | 'Â' | 'Ä' | 'Ã' | 'Å' => 'A',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'É' | 'È' | 'Ê' | 'Ë' => 'E',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'Í' | 'Ì' | 'Î' | 'Ï' => 'I',
+            'ó' | 'ò' | 'ô' | 'ö' | 'õ' | 'ő' => 'o',
+            'Ó' | 'Ò' | 'Ô' | 'Ö' | 'Õ' | 'Ő' => 'O',
+            'ú' | 'ù' | 'û' | 'ü' | 'ű' => 'u',
+            'Ú' | 'Ù' | 'Û' | 'Ü' | 'Ű' => 'U',
+            'ç' => 'c',
+            'Ç' => 'C',
+            'ñ' => 'n',
+            'Ñ' => 'N',
+            other => other,
+        })
+        .collect()
+}
+
+/// Split text into lowercase alphanumeric words.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// A language-specific pipeline: tokenize, drop stop words, stem and
+/// optionally fold accents.
+#[derive(Debug, Clone, Copy)]
+pub struct Analyzer {
+    language: Language,
+    fold_accents: bool,
+}
+
+impl Analyzer {
+    pub fn new(language: Language, fold_accents: bool) -> Self {
+        Self { language, fold_accents }
+    }
+
+    /// Analyze `text` into index terms.
+    pub fn analyze(&self, text: &str) -> Vec<String> {
+        tokenize(text)
+            .into_iter()
+            .filter(|token| !self.is_stop_word(token))
+            .map(|token| self.normalize(&token))
+            .filter(|term| !term.is_empty())
+            .collect()
+    }
+
+    /// Stem and (optionally) fold a single already-lowercased token.
+    pub fn normalize(&self, token: &str) -> String {
+        let stemmed = self.stem(token);
+        if self.fold_accents {
+            fold_accents(&stemmed)
+        } else {
+            stemmed
+        }
+    }
+
+    pub fn is_stop_word(&self, token: &str) -> bool {
+        match self.language {
+            Language::English => ENGLISH_STOP_WORDS.contains(&token),
+            Language::Hungarian => HUNGARIAN_STOP_WORDS.contains(&token),
+            Language::Unknown => {
+                ENGLISH_STOP_WORDS.contains(&token) || HUNGARIAN_STOP_WORDS.contains(&token)
+            }
+        }
+    }
+
+    pub fn stem(&self, token: &str) -> String {
+        match self.language {
+            Language::English => stem_with(token, ENGLISH_SUFFIXES, 3),
+            Language::Hungarian => stem_hungarian(token),
+            Language::Unknown => token.to_string(),
+        }
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'á' | 'e' | 'é' | 'i' | 'í' | 'o' | 'ó' | 'ö' | 'ő' | 'u' | 'ú' | 'ü' | 'ű')
+}
+
+fn strip_suffix(
+    token: &str,
+    suffixes: &[&str],
+    min_stem_chars: usize,
+    vowel_before_lone_consonant: bool,
+) -> Option<String> {
+    // Numbers are kept verbatim
+    if token.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    for suffix in suffixes {
+        if let Some(stem) = token.strip_suffix(suffix) {
+            if stem.chars().count() < min_stem_chars {
+                continue;
+            }
+            // A lone consonant ending ("-t", "-k") only counts after a vowel,
+            // otherwise "projekt" would lose its last letter
+            if vowel_before_lone_consonant
+                && suffix.chars().count() == 1
+                && !stem.chars().last().is_some_and(is_vowel)
+            {
+                continue;
+            }
+            return Some(stem.to_string());
+        }
+    }
+
+    None
+}
+
+fn stem_with(token: &str, suffixes: &[&str], min_stem_chars: usize) -> String {
+    strip_suffix(token, suffixes, min_stem_chars, false).unwrap_or_else(|| token.to_string())
+}
+
+fn stem_hungarian(token: &str) -> String {
+    let mut stem = token.to_string();
+    if let Some(stripped) = strip_suffix(&stem, HUNGARIAN_CASE_SUFFIXES, 3, true) {
+        stem = stripped;
+    }
+    if let Some(stripped) = strip_suffix(&stem, HUNGARIAN_NUMBER_SUFFIXES, 3, true) {
+        stem = stripped;
+    }
+
+    // Suffixes lengthen a final vowel ("alma" -> "almát"), so shorten it back
+    if stem != token && stem.chars().count() > 3 {
+        if let Some(last) = stem.pop() {
+            stem.push(match last {
+                'á' => 'a',
+                'é' => 'e',
+                other => other,
+            });
+        }
+    }
+    stem
+}
+
+/// Analyze `text` with every supported language and return the union of
+/// terms. Used for short inputs such as queries where detection is unreliable,
+/// so stop words of any language are dropped.
+pub fn analyze_multilingual(text: &str, fold_accents: bool) -> Vec<String> {
+    let any_language = Analyzer::new(Language::Unknown, fold_accents);
+    let analyzers = [
+        Analyzer::new(Language::Hungarian, fold_accents),
+        Analyzer::new(Language::English, fold_accents),
+    ];
+
+    let mut seen = HashSet::new();
+    let mut terms = Vec::new();
+    for token in tokenize(text) {
+        if any_language.is_stop_word(&token) {
+            continue;
+        }
+        for analyzer in &analyzers {
+            let term = analyzer.normalize(&token);
+            if !term.is_empty() && seen.insert(term.clone()) {
+                terms.push(term);
+            }
+        }
+    }
+    terms
+}
+
+/// Optimal string alignment distance: Levenshtein plus adjacent
+/// transpositions, which covers the most common typos.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (n, m) = (a.len(), b.len());
+    let mut dp = vec![vec![0usize; m + 1]; n + 1];
+    for (i, row) in dp.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in dp[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            dp[i][j] = (dp[i - 1][j] + 1)
+                .min(dp[i][j - 1] + 1)
+                .min(dp[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                dp[i][j] = dp[i][j].min(dp[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    dp[n][m]
+}
@@ -0,0 +1,99 @@
+//! Offline relevance evaluation.
+//!
+//! Indexes a corpus into an in-memory database, runs a golden query set
+//! through keyword search and the Q&A retriever, and compares the metrics
+//! with a stored baseline:
+//!
+//!     relevance_eval <corpus_dir> <golden.json> [--baseline <file>] [--write-baseline]
+//!
+//! Exits with status 1 if any metric regressed beyond the baseline tolerance.
+
+use anyhow::Result;
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::evaluation::{evaluate, Baseline, GoldenSet, Metrics};
+use knowledge_base_backend::search::SearchEngine;
+use std::path::PathBuf;
+
+struct Args {
+    corpus: PathBuf,
+    golden: PathBuf,
+    baseline: Option<PathBuf>,
+    write_baseline: bool,
+}
+
+fn parse_args() -> Result<Args> {
+    let usage = "usage: relevance_eval <corpus_dir> <golden.json> [--baseline <file>] [--write-baseline]";
+    let mut positional = Vec::new();
+    let mut baseline = None;
+    let mut write_baseline = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--baseline" => baseline = Some(PathBuf::from(args.next().ok_or_else(|| anyhow::anyhow!(usage))?)),
+            "--write-baseline" => write_baseline = true,
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+
+    // There is nowhere to write a baseline without a file
+    if write_baseline && baseline.is_none() {
+        return Err(anyhow::anyhow!(usage));
+    }
+
+    match <[PathBuf; 2]>::try_from(positional) {
+        Ok([corpus, golden]) => Ok(Args { corpus, golden, baseline, write_baseline }),
+        Err(_) => Err(anyhow::anyhow!(usage)),
+    }
+}
+
+fn print_metrics(label: &str, metrics: &Metrics) {
+    println!(
+        "{:<8} P@k {:.3}  R@k {:.3}  MRR {:.3}  nDCG@k {:.3}",
+        label, metrics.precision_at_k, metrics.recall_at_k, metrics.mrr, metrics.ndcg_at_k
+    );
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = parse_args()?;
+
+    let db = Database::new("sqlite::memory:").await?;
+    db.migrate().await?;
+    CorpusManager::new(db.clone(), vec![]).index_folder(&args.corpus, None).await?;
+
+    let golden = GoldenSet::load(&args.golden)?;
+    let report = evaluate(&SearchEngine::new(db), &golden).await?;
+
+    println!("{} queries, k = {}", report.queries.len(), report.k);
+    print_metrics("search", &report.search);
+    print_metrics("qa", &report.qa);
+
+    let Some(baseline_path) = args.baseline else {
+        return Ok(());
+    };
+
+    if args.write_baseline {
+        let baseline = serde_json::to_string_pretty(&Baseline::from_report(&report))?;
+        std::fs::write(&baseline_path, baseline + "\n")?;
+        println!("Baseline written to {}", baseline_path.display());
+        return Ok(());
+    }
+
+    let changes = Baseline::load(&baseline_path)?.compare(&report);
+    for change in &changes {
+        println!(
+            "{} {} {}: {:.3} -> {:.3}",
+            if change.is_regression() { "REGRESSED" } else { "improved" },
+            change.retriever,
+            change.metric,
+            change.baseline,
+            change.current
+        );
+    }
+    if changes.iter().any(|c| c.is_regression()) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
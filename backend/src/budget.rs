@@ -0,0 +1,240 @@
+use crate::llm::LlmClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Context window assumed when neither the config nor Ollama names one.
+/// Ollama itself runs models with this window unless told otherwise.
+pub const DEFAULT_CONTEXT_WINDOW: usize = 4096;
+
+/// Chunks trimmed below this many tokens are dropped instead; a few words
+/// of a passage are more likely to mislead than to help.
+const MIN_TRIMMED_CHUNK_TOKENS: usize = 48;
+
+/// Appended to text cut to fit the budget.
+const TRIM_MARKER: &str = " …";
+
+fn default_answer_reserve() -> usize {
+    1024
+}
+
+/// `[context]` section of the config: context window per model and the
+/// room kept free for the generated text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextConfig {
+    /// Context window in tokens by model name, overriding what Ollama reports
+    #[serde(default)]
+    pub windows: HashMap<String, usize>,
+    /// Tokens kept free for the answer or summary
+    #[serde(default = "default_answer_reserve")]
+    pub answer_reserve: usize,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            windows: HashMap::new(),
+            answer_reserve: default_answer_reserve(),
+        }
+    }
+}
+
+/// Rough token count of `text`: about four characters per token, and at
+/// least one per word.
+pub fn estimate_tokens(text: &str) -> usize {
+    let chars = text.chars().count();
+    let words = text.split_whitespace().count();
+    chars.div_ceil(4).max(words)
+}
+
+/// Cut `text` to about `max_tokens` tokens at a word boundary. Returns the
+/// text unchanged if it already fits.
+pub fn trim_to_tokens(text: &str, max_tokens: usize) -> (String, bool) {
+    if estimate_tokens(text) <= max_tokens {
+        return (text.to_string(), false);
+    }
+
+    // Longest prefix within the budget, found by binary search over char boundaries
+    let budget = max_tokens.saturating_sub(estimate_tokens(TRIM_MARKER));
+    let boundaries: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+    let fits = boundaries.partition_point(|&end| estimate_tokens(&text[..end]) <= budget);
+    let mut end = boundaries[fits.saturating_sub(1)];
+    // Prefer ending on a word boundary
+    if let Some(space) = text[..end].rfind(char::is_whitespace).filter(|space| *space > end / 2) {
+        end = space;
+    }
+    (format!("{}{}", text[..end].trim_end(), TRIM_MARKER), true)
+}
+
+/// The context window of the model and where it was found: `config`,
+/// the provider (`ollama` or `openai`) or `default`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextWindow {
+    pub tokens: usize,
+    pub source: String,
+}
+
+/// How a prompt was fitted into the context window.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetReport {
+    pub context_window: usize,
+    pub window_source: String,
+    pub answer_reserve: usize,
+    /// Estimated tokens of the whole prompt
+    pub prompt_tokens: usize,
+    /// Estimated tokens of the retrieved context or content within it
+    pub context_tokens: usize,
+    pub truncated: bool,
+    /// Chunks left out for lack of room
+    pub dropped_chunks: usize,
+    /// Chunks cut short to fit
+    pub trimmed_chunks: usize,
+}
+
+/// Resolves the context window of a generation model: a configured
+/// window, else the one the provider reports, else [`DEFAULT_CONTEXT_WINDOW`].
+/// The window found for a model is cached, so the provider is asked once.
+#[derive(Clone)]
+pub struct ContextBudgeter {
+    llm: LlmClient,
+    config: ContextConfig,
+    discovered: Arc<Mutex<HashMap<String, ContextWindow>>>,
+}
+
+impl ContextBudgeter {
+    pub fn new(llm: LlmClient, config: ContextConfig) -> Self {
+        Self {
+            llm,
+            config,
+            discovered: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn answer_reserve(&self) -> usize {
+        self.config.answer_reserve
+    }
+
+    pub async fn context_window(&self, model: &str) -> ContextWindow {
+        if let Some(tokens) = self.config.windows.get(model) {
+            return ContextWindow { tokens: *tokens, source: "config".to_string() };
+        }
+        if let Some(window) = self.discovered.lock().unwrap().get(model) {
+            return window.clone();
+        }
+
+        let window = match self.llm.context_window(model).await {
+            Ok(Some(tokens)) => ContextWindow { tokens, source: self.llm.provider_name().to_string() },
+            Ok(None) => ContextWindow { tokens: DEFAULT_CONTEXT_WINDOW, source: "default".to_string() },
+            Err(e) => {
+                // Not cached, so the provider is asked again once it is back
+                tracing::debug!("Could not read the model's context window: {}", e);
+                return ContextWindow { tokens: DEFAULT_CONTEXT_WINDOW, source: "default".to_string() };
+            }
+        };
+        self.discovered.lock().unwrap().insert(model.to_string(), window.clone());
+        window
+    }
+
+    /// A report for a prompt to `model` of `overhead` tokens around its
+    /// context, with `available` set to the tokens left for the context itself.
+    pub async fn plan(&self, model: &str, overhead: usize) -> (BudgetReport, usize) {
+        let window = self.context_window(model).await;
+        let available = window.tokens.saturating_sub(self.config.answer_reserve).saturating_sub(overhead);
+        let report = BudgetReport {
+            context_window: window.tokens,
+            window_source: window.source,
+            answer_reserve: self.config.answer_reserve,
+            prompt_tokens: overhead,
+            ..Default::default()
+        };
+        (report, available)
+    }
+}
+
+/// Pick chunks in rank order until `available` tokens are used up. Each
+/// chunk comes with the tokens its label and separator take in the prompt.
+/// A chunk that does not fit whole is trimmed if enough room is left,
+/// otherwise skipped in favour of shorter ones further down. Returns the
+/// indices of the kept chunks with their (possibly trimmed) text.
+pub fn fit_chunks(chunks: &[(usize, &str)], available: usize, report: &mut BudgetReport) -> Vec<(usize, String)> {
+    let mut kept = Vec::new();
+    let mut remaining = available;
+
+    for (i, (overhead, text)) in chunks.iter().enumerate() {
+        let cost = overhead + estimate_tokens(text);
+        if cost <= remaining {
+            remaining -= cost;
+            report.context_tokens += cost;
+            kept.push((i, text.to_string()));
+            continue;
+        }
+
+        let room = remaining.saturating_sub(*overhead);
+        if room >= MIN_TRIMMED_CHUNK_TOKENS || (kept.is_empty() && room > 0) {
+            let (trimmed, _) = trim_to_tokens(text, room);
+            let cost = overhead + estimate_tokens(&trimmed);
+            remaining = remaining.saturating_sub(cost);
+            report.context_tokens += cost;
+            report.trimmed_chunks += 1;
+            kept.push((i, trimmed));
+        } else {
+            report.dropped_chunks += 1;
+        }
+    }
+
+    report.truncated = report.trimmed_chunks > 0 || report.dropped_chunks > 0;
+    report.prompt_tokens += report.context_tokens;
+    kept
+}
+
+/// Split `text` into sections of at most `max_tokens` tokens, breaking
+/// between paragraphs where possible and between words otherwise.
+pub fn split_to_budget(text: &str, max_tokens: usize) -> Vec<String> {
+    let max_tokens = max_tokens.max(1);
+    let mut sections = Vec::new();
+    let mut current = String::new();
+
+    let push = |piece: &str, current: &mut String, sections: &mut Vec<String>| {
+        let candidate = if current.is_empty() { piece.to_string() } else { format!("{}\n\n{}", current, piece) };
+        if estimate_tokens(&candidate) <= max_tokens {
+            *current = candidate;
+        } else {
+            if !current.is_empty() {
+                sections.push(std::mem::take(current));
+            }
+            *current = piece.to_string();
+        }
+    };
+
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if estimate_tokens(paragraph) <= max_tokens {
+            push(paragraph, &mut current, &mut sections);
+            continue;
+        }
+        // A paragraph longer than a section is cut between words, counting
+        // as `estimate_tokens` does without rescanning the piece
+        let mut start = None;
+        let (mut chars, mut words) = (0, 0);
+        for (offset, word) in paragraph.split_whitespace().map(|w| (w.as_ptr() as usize - paragraph.as_ptr() as usize, w)) {
+            let word_chars = word.chars().count() + usize::from(words > 0);
+            if words > 0 && (chars + word_chars).div_ceil(4).max(words + 1) > max_tokens {
+                let piece = &paragraph[start.unwrap_or(0)..offset];
+                push(piece.trim_end(), &mut current, &mut sections);
+                start = Some(offset);
+                chars = word.chars().count();
+                words = 1;
+            } else {
+                start.get_or_insert(offset);
+                chars += word_chars;
+                words += 1;
+            }
+        }
+        if let Some(start) = start {
+            push(&paragraph[start..], &mut current, &mut sections);
+        }
+    }
+    if !current.is_empty() {
+        sections.push(current);
+    }
+    sections
+}
@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Sentences shorter than this many words (headings, "In short:") are not
+/// expected to cite anything.
+const MIN_CITED_SENTENCE_WORDS: usize = 4;
+
+/// Outcome of checking an answer's `[n]` markers against the numbered
+/// context chunks it was generated from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CitationCheck {
+    /// Chunk numbers (1-based) the answer cites
+    pub used: Vec<usize>,
+    /// Markers that point at no chunk; they are removed from the answer
+    pub invalid_markers: Vec<usize>,
+    /// Sentences of the answer that cite no chunk
+    pub uncited_sentences: Vec<String>,
+    /// The answer cites no chunk at all
+    pub uncited: bool,
+}
+
+/// Strip markers pointing outside `1..=chunk_count` from `answer` and report
+/// which chunks are cited and which sentences cite none. Code blocks, inline
+/// code and Markdown links are left alone.
+pub fn verify_citations(answer: &str, chunk_count: usize) -> (String, CitationCheck) {
+    let mut used = BTreeSet::new();
+    let mut invalid = BTreeSet::new();
+    let mut cleaned = String::with_capacity(answer.len());
+    let mut in_fence = false;
+
+    for line in answer.split_inclusive('\n') {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            cleaned.push_str(line);
+            continue;
+        }
+        if in_fence {
+            cleaned.push_str(line);
+            continue;
+        }
+        clean_line(line, chunk_count, &mut cleaned, &mut used, &mut invalid);
+    }
+
+    let uncited_sentences = uncited_sentences(&cleaned);
+    let check = CitationCheck {
+        uncited: used.is_empty(),
+        used: used.into_iter().collect(),
+        invalid_markers: invalid.into_iter().collect(),
+        uncited_sentences,
+    };
+    (cleaned, check)
+}
+
+fn clean_line(line: &str, chunk_count: usize, cleaned: &mut String, used: &mut BTreeSet<usize>, invalid: &mut BTreeSet<usize>) {
+    let mut in_code = false;
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        if c == '`' {
+            in_code = !in_code;
+        } else if c == '[' && !in_code {
+            if let Some((numbers, len)) = parse_marker(rest) {
+                let valid: Vec<usize> = numbers.iter().copied().filter(|n| (1..=chunk_count).contains(n)).collect();
+                invalid.extend(numbers.iter().copied().filter(|n| !(1..=chunk_count).contains(n)));
+                used.extend(valid.iter().copied());
+
+                if valid.is_empty() {
+                    // Drop the space that separated the marker from its sentence
+                    if cleaned.ends_with(' ') {
+                        cleaned.pop();
+                    }
+                } else if valid.len() == numbers.len() {
+                    cleaned.push_str(&rest[..len]);
+                } else {
+                    let valid: Vec<String> = valid.iter().map(|n| n.to_string()).collect();
+                    cleaned.push_str(&format!("[{}]", valid.join(", ")));
+                }
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        cleaned.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+}
+
+/// Parse a `[1]` or `[1, 2]` marker at the start of `text`, returning the
+/// numbers and the marker's length. Links (`[1](...)`) are not markers.
+fn parse_marker(text: &str) -> Option<(Vec<usize>, usize)> {
+    let end = text.find(']')?;
+    let inner = text.get(1..end)?;
+    if text[end + 1..].starts_with('(') {
+        return None;
+    }
+    let numbers = inner
+        .split(',')
+        .map(|part| {
+            let part = part.trim();
+            if part.is_empty() || part.len() > 4 || !part.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            part.parse().ok()
+        })
+        .collect::<Option<Vec<usize>>>()?;
+    Some((numbers, end + 1))
+}
+
+/// Sentences outside code blocks, headings and tables without a marker.
+fn uncited_sentences(text: &str) -> Vec<String> {
+    let mut uncited = Vec::new();
+    let mut in_fence = false;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || line.is_empty() || line.starts_with('#') || line.starts_with('|') {
+            continue;
+        }
+        let line = line.trim_start_matches(|c: char| c == '-' || c == '*' || c == '>' || c.is_whitespace());
+
+        for sentence in split_sentences(line) {
+            let cited = has_marker(sentence);
+            let trimmed = sentence.trim();
+            if !cited && !trimmed.ends_with(':') && trimmed.split_whitespace().count() >= MIN_CITED_SENTENCE_WORDS {
+                uncited.push(trimmed.to_string());
+            }
+        }
+    }
+    uncited
+}
+
+/// Whether `text` holds a marker outside inline code.
+fn has_marker(text: &str) -> bool {
+    let mut in_code = false;
+    text.char_indices().any(|(i, c)| {
+        if c == '`' {
+            in_code = !in_code;
+        }
+        !in_code && c == '[' && parse_marker(&text[i..]).is_some()
+    })
+}
+
+/// Split a line into sentences; a marker right after the closing
+/// punctuation ("Monday. [1]") belongs to the sentence before it.
+fn split_sentences(line: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if !matches!(c, '.' | '!' | '?') {
+            continue;
+        }
+        let after = i + c.len_utf8();
+        if !line[after..].is_empty() && !line[after..].starts_with(char::is_whitespace) && !line[after..].starts_with('[') {
+            continue;
+        }
+
+        let mut end = after;
+        loop {
+            let rest = &line[end..];
+            let skipped = rest.len() - rest.trim_start().len();
+            match parse_marker(rest.trim_start()) {
+                Some((_, len)) if rest.trim_start().starts_with('[') => end += skipped + len,
+                _ => break,
+            }
+        }
+        sentences.push(&line[start..end]);
+        start = end;
+        while chars.peek().is_some_and(|(j, _)| *j < end) {
+            chars.next();
+        }
+    }
+    if !line[start..].trim().is_empty() {
+        sentences.push(&line[start..]);
+    }
+    sentences
+}
@@ -1,4 +1,7 @@
 use anyhow::Result;
+use crate::budget::ContextConfig;
+use crate::llm::LlmConfig;
+use crate::prompts::PromptConfig;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -13,6 +16,29 @@ pub struct Config {
     pub local_first: bool,
     pub logging_enabled: bool,
     pub log_retention_days: u32,
+    /// Match accented and unaccented letters alike in search (`é` ≈ `e`)
+    #[serde(default = "default_fold_accents")]
+    pub fold_accents: bool,
+    /// Ollama model used to embed chunks for semantic search
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+    /// Prompt templates and answer language
+    #[serde(default)]
+    pub prompts: PromptConfig,
+    /// Context window per model and room reserved for answers
+    #[serde(default)]
+    pub context: ContextConfig,
+    /// Model provider: Ollama or an OpenAI-compatible server
+    #[serde(default)]
+    pub llm: LlmConfig,
+}
+
+fn default_embedding_model() -> String {
+    crate::ollama::DEFAULT_EMBEDDING_MODEL.to_string()
+}
+
+fn default_fold_accents() -> bool {
+    true
 }
 
 impl Default for Config {
@@ -35,6 +61,11 @@ impl Default for Config {
             local_first: true,
             logging_enabled: false,
             log_retention_days: 7,
+            fold_accents: default_fold_accents(),
+            embedding_model: default_embedding_model(),
+            prompts: PromptConfig::default(),
+            context: ContextConfig::default(),
+            llm: LlmConfig::default(),
         }
     }
 }
@@ -1,18 +1,24 @@
 use anyhow::Result;
 use chrono::Utc;
-use crate::database::{Database, Document, IndexEntry};
-use md5;
+use crate::analysis::{analyze_multilingual, detect_language, fold_accents, tokenize, Analyzer, Language};
+use crate::database::{ChunkEmbedding, Database, Document, IndexEntry, SuggestionEntry, TermEntry, VocabularyEntry};
+use crate::llm::LlmClient;
 use regex::Regex;
 use std::fs;
 use std::path::Path;
 use uuid::Uuid;
 use walkdir::WalkDir;
 
+/// Most frequent words of a document offered as autocomplete candidates
+const MAX_SUGGESTION_TERMS: usize = 25;
+
+/// Longer titles and headings are not useful completions
+const MAX_SUGGESTION_LENGTH: usize = 120;
+
 fn convert_docx_to_markdown(path: &Path) -> Result<String> {
     // Prefer pandoc if available
-    let pandoc = which::which("pandoc");
-    if pandoc.is_ok() {
-        let output = std::process::Command::new(pandoc.unwrap())
+    if let Ok(pandoc) = which::which("pandoc") {
+        let output = std::process::Command::new(pandoc)
             .arg(path.to_string_lossy().to_string())
             .arg("-t")
             .arg("gfm")
@@ -54,11 +60,18 @@ fn convert_docx_to_markdown(path: &Path) -> Result<String> {
 pub struct CorpusManager {
     db: Database,
     exclusions: Vec<String>,
+    embedder: Option<LlmClient>,
 }
 
 impl CorpusManager {
     pub fn new(db: Database, exclusions: Vec<String>) -> Self {
-        Self { db, exclusions }
+        Self { db, exclusions, embedder: None }
+    }
+
+    /// Compute chunk embeddings with the given client after each indexing run.
+    pub fn with_embeddings(mut self, client: impl Into<LlmClient>) -> Self {
+        self.embedder = Some(client.into());
+        self
     }
 
     pub async fn index_folder(&self, folder_path: &Path, project_id: Option<&Uuid>) -> Result<IndexingResult> {
@@ -97,9 +110,46 @@ impl CorpusManager {
             }
         }
 
+        // Embeddings are best effort: keyword search works without them
+        if let Err(e) = self.embed_missing_chunks().await {
+            tracing::warn!("Failed to compute embeddings: {}", e);
+        }
+
         Ok(result)
     }
 
+    /// Embed every chunk of the latest document versions that has no vector
+    /// for the configured model. Chunks whose text is unchanged since a
+    /// previous version reuse the stored vector instead of calling the model.
+    pub async fn embed_missing_chunks(&self) -> Result<u32> {
+        let Some(client) = &self.embedder else {
+            return Ok(0);
+        };
+        let model = client.embedding_model().to_string();
+        let mut embedded = 0;
+
+        for entry in self.db.get_chunks_missing_embeddings(&model).await? {
+            let chunk_hash = self.compute_hash(&entry.chunk_text);
+            let vector = match self.db.find_embedding_by_hash(&chunk_hash, &model).await? {
+                Some(vector) => vector,
+                None => client.embed(&entry.chunk_text).await?,
+            };
+
+            self.db
+                .insert_chunk_embedding(&ChunkEmbedding {
+                    document_id: entry.document_id,
+                    chunk_id: entry.chunk_id,
+                    model: model.clone(),
+                    chunk_hash,
+                    vector,
+                })
+                .await?;
+            embedded += 1;
+        }
+
+        Ok(embedded)
+    }
+
     async fn index_file(&self, path: &Path, project_id: Option<&Uuid>) -> Result<()> {
         let metadata = fs::metadata(path)?;
         let modified_at = metadata.modified()?.into();
@@ -121,11 +171,16 @@ impl CorpusManager {
         let content_hash = self.compute_hash(&content);
 
         // Check if file has changed or if project assignment has changed
-        if let Some(existing_doc) = self.db.get_latest_document_version(&path.to_path_buf()).await? {
+        if let Some(existing_doc) = self.db.get_latest_document_version(path).await? {
             let content_unchanged = existing_doc.content_hash == content_hash;
             let project_unchanged = existing_doc.project_id == project_id.cloned();
             
             if content_unchanged && project_unchanged {
+                // Backfill autocomplete candidates for documents indexed before they existed
+                if !self.db.has_suggestions(&existing_doc.id).await? {
+                    let vocabulary = self.create_vocabulary(&existing_doc, &content);
+                    self.db.replace_suggestions(&existing_doc, &self.create_suggestions(&existing_doc, &vocabulary)).await?;
+                }
                 return Ok(()); // File and project assignment haven't changed
             }
         }
@@ -133,10 +188,11 @@ impl CorpusManager {
         // Extract metadata
         let (title, tags, headings) = self.extract_metadata(&content, path);
         let content_excerpt = self.create_excerpt(&content);
+        let language = detect_language(&content);
 
         // Get next version number and mark previous versions as not latest
-        let version = self.db.get_next_version_number(&path.to_path_buf()).await?;
-        self.db.mark_previous_versions_not_latest(&path.to_path_buf()).await?;
+        let version = self.db.get_next_version_number(path).await?;
+        self.db.mark_previous_versions_not_latest(path).await?;
 
         // Create document
         let document = Document {
@@ -158,6 +214,7 @@ impl CorpusManager {
             version,
             is_latest: true,
             project_id: project_id.cloned(),
+            language: Some(language.code().to_string()),
         };
 
         // Insert document
@@ -170,6 +227,18 @@ impl CorpusManager {
         let index_entries = self.create_index_entries(&document, &content);
         self.db.insert_index_entries(&index_entries).await?;
 
+        // Store analyzed terms for language-aware search
+        let term_entries = self.create_term_entries(&index_entries, language);
+        self.db.insert_term_entries(&term_entries).await?;
+
+        // Store surface vocabulary for typo correction
+        let vocabulary = self.create_vocabulary(&document, &content);
+        self.db.insert_vocabulary(&document.id, &vocabulary).await?;
+
+        // Autocomplete candidates replace those of the previous version
+        let suggestions = self.create_suggestions(&document, &vocabulary);
+        self.db.replace_suggestions(&document, &suggestions).await?;
+
         Ok(())
     }
 
@@ -226,8 +295,8 @@ impl CorpusManager {
 
         // Extract title from first H1 heading or filename
         if let Some(first_line) = content.lines().next() {
-            if first_line.starts_with("# ") {
-                title = Some(first_line[2..].trim().to_string());
+            if let Some(heading) = first_line.strip_prefix("# ") {
+                title = Some(heading.trim().to_string());
             } else {
                 title = path.file_stem()
                     .unwrap_or_default()
@@ -242,8 +311,8 @@ impl CorpusManager {
             if let Some(end_pos) = content.find("\n---\n") {
                 let frontmatter = &content[4..end_pos];
                 for line in frontmatter.lines() {
-                    if line.starts_with("tags:") {
-                        let tag_line = &line[5..].trim();
+                    if let Some(tag_line) = line.strip_prefix("tags:") {
+                        let tag_line = tag_line.trim();
                         if tag_line.starts_with('[') && tag_line.ends_with(']') {
                             let tags_str = &tag_line[1..tag_line.len()-1];
                             tags = tags_str.split(',')
@@ -257,12 +326,12 @@ impl CorpusManager {
 
         // Extract headings
         for line in content.lines() {
-            if line.starts_with("# ") {
-                headings.push(line[2..].trim().to_string());
-            } else if line.starts_with("## ") {
-                headings.push(line[3..].trim().to_string());
-            } else if line.starts_with("### ") {
-                headings.push(line[4..].trim().to_string());
+            if let Some(heading) = line.strip_prefix("# ") {
+                headings.push(heading.trim().to_string());
+            } else if let Some(heading) = line.strip_prefix("## ") {
+                headings.push(heading.trim().to_string());
+            } else if let Some(heading) = line.strip_prefix("### ") {
+                headings.push(heading.trim().to_string());
             }
         }
 
@@ -290,6 +359,9 @@ impl CorpusManager {
 
         let mut start = 0;
         let mut chunk_id = 0;
+        // Character offset of `start`, counted incrementally
+        let mut offset_start = 0;
+        let mut offset_chars = 0;
 
         while start < content.len() {
             // Ensure start is on a UTF-8 boundary
@@ -333,12 +405,20 @@ impl CorpusManager {
             // Find positions of important terms (simple word-based indexing)
             let positions = self.find_word_positions(&chunk_text);
 
+            if start < offset_start {
+                offset_start = 0;
+                offset_chars = 0;
+            }
+            offset_chars += content[offset_start..start].chars().count();
+            offset_start = start;
+
             entries.push(IndexEntry {
                 id: Uuid::new_v4(),
                 document_id: document.id,
                 chunk_id,
                 chunk_text,
                 positions,
+                start_offset: offset_chars,
             });
 
             start = if final_end < content.len() {
@@ -356,6 +436,91 @@ impl CorpusManager {
         entries
     }
 
+    fn create_term_entries(&self, index_entries: &[IndexEntry], language: Language) -> Vec<TermEntry> {
+        let analyzer = Analyzer::new(language, false);
+        let mut entries = Vec::new();
+
+        for index_entry in index_entries {
+            let mut frequencies: std::collections::HashMap<String, u32> = std::collections::HashMap::new();
+            let terms = match language {
+                // Without a detected language, index every language's variant
+                // of each word, the way queries are expanded
+                Language::Unknown => tokenize(&index_entry.chunk_text)
+                    .iter()
+                    .flat_map(|token| analyze_multilingual(token, false))
+                    .collect(),
+                _ => analyzer.analyze(&index_entry.chunk_text),
+            };
+            for term in terms {
+                *frequencies.entry(term).or_insert(0) += 1;
+            }
+
+            for (term, frequency) in frequencies {
+                entries.push(TermEntry {
+                    document_id: index_entry.document_id,
+                    chunk_id: index_entry.chunk_id,
+                    folded_term: fold_accents(&term),
+                    term,
+                    frequency,
+                });
+            }
+        }
+
+        entries
+    }
+
+    fn create_vocabulary(&self, document: &Document, content: &str) -> Vec<VocabularyEntry> {
+        let stop_words = Analyzer::new(Language::Unknown, false);
+        let mut frequencies: std::collections::HashMap<String, u32> = std::collections::HashMap::new();
+
+        let title = document.title.clone().unwrap_or_default();
+        let sources = [content, title.as_str(), &document.tags.join(" ")].map(tokenize);
+        for token in sources.into_iter().flatten() {
+            if token.chars().count() < 3
+                || token.chars().any(|c| c.is_ascii_digit())
+                || stop_words.is_stop_word(&token)
+            {
+                continue;
+            }
+            *frequencies.entry(token).or_insert(0) += 1;
+        }
+
+        frequencies
+            .into_iter()
+            .map(|(term, frequency)| VocabularyEntry { term, frequency })
+            .collect()
+    }
+
+    /// Title, headings, tags and the most frequent words of a document as
+    /// autocomplete candidates.
+    fn create_suggestions(&self, document: &Document, vocabulary: &[VocabularyEntry]) -> Vec<SuggestionEntry> {
+        let mut suggestions = Vec::new();
+        let mut add = |text: &str, kind: &str| {
+            let text = text.trim();
+            if !text.is_empty() && text.chars().count() <= MAX_SUGGESTION_LENGTH {
+                suggestions.push(SuggestionEntry { text: text.to_string(), kind: kind.to_string() });
+            }
+        };
+
+        if let Some(title) = &document.title {
+            add(title, "title");
+        }
+        for heading in &document.headings {
+            add(heading, "heading");
+        }
+        for tag in &document.tags {
+            add(tag, "tag");
+        }
+
+        let mut terms: Vec<&VocabularyEntry> = vocabulary.iter().collect();
+        terms.sort_by(|a, b| b.frequency.cmp(&a.frequency).then_with(|| a.term.cmp(&b.term)));
+        for entry in terms.into_iter().take(MAX_SUGGESTION_TERMS) {
+            add(&entry.term, "term");
+        }
+
+        suggestions
+    }
+
     fn find_word_positions(&self, text: &str) -> Vec<u32> {
         let mut positions = Vec::new();
         let words: Vec<&str> = text.split_whitespace().collect();
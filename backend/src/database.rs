@@ -2,7 +2,7 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{SqlitePool, Row};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +22,8 @@ pub struct Document {
     pub version: u32,
     pub is_latest: bool,
     pub project_id: Option<Uuid>,
+    #[serde(default)]
+    pub language: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +33,148 @@ pub struct IndexEntry {
     pub chunk_id: u32,
     pub chunk_text: String,
     pub positions: Vec<u32>,
+    /// Character offset of the chunk within the indexed document content
+    #[serde(default)]
+    pub start_offset: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TermEntry {
+    pub document_id: Uuid,
+    pub chunk_id: u32,
+    pub term: String,
+    pub folded_term: String,
+    pub frequency: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkEmbedding {
+    pub document_id: Uuid,
+    pub chunk_id: u32,
+    pub model: String,
+    pub chunk_hash: String,
+    pub vector: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyEntry {
+    pub term: String,
+    pub frequency: u32,
+}
+
+/// Autocomplete candidate taken from a document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestionEntry {
+    pub text: String,
+    /// `title`, `heading`, `tag` or `term`
+    pub kind: String,
+}
+
+/// Autocomplete candidate with the number of latest documents it occurs in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub text: String,
+    pub kind: String,
+    pub document_frequency: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Terms treated as equivalent when searching, e.g. `k8s` and `kubernetes`.
+/// Entries without a project apply everywhere.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Synonym {
+    pub id: Uuid,
+    pub project_id: Option<Uuid>,
+    pub term: String,
+    pub synonyms: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A prompt template overriding the configured one, for one project or
+/// (without a project) for all of them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub project_id: Option<Uuid>,
+    /// `answer` or `summary`
+    pub kind: String,
+    pub template: String,
+    pub updated_at: String,
+}
+
+/// A named query with filters that can be rerun like a virtual folder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: Uuid,
+    pub name: String,
+    pub query: String,
+    /// Serialized `SearchFilters`
+    pub filters: Option<serde_json::Value>,
+    /// `keyword` or `semantic`
+    pub mode: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub last_run_at: Option<String>,
+}
+
+/// A generated summary, reused while the document content and the prompt
+/// it was generated with stay the same.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedSummary {
+    pub document_id: Uuid,
+    /// `short`, `medium` or `long`
+    pub length: String,
+    pub model: String,
+    pub content_hash: String,
+    /// Hash of the template and language the summary was written with
+    pub prompt_hash: String,
+    pub summary: String,
+    /// Sections the content was summarized in (1 unless map-reduced)
+    pub sections: u32,
+    pub created_at: String,
+}
+
+/// A multi-turn Q&A session. Follow-up questions are answered within the
+/// projects the conversation was started in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: Uuid,
+    pub title: Option<String>,
+    pub project_ids: Vec<Uuid>,
+    pub turn_count: u32,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// One question and answer of a conversation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationTurn {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    /// 1-based position within the conversation
+    pub position: u32,
+    pub question: String,
+    /// The question rewritten to stand on its own, as used for retrieval
+    pub standalone_question: String,
+    pub answer: String,
+    pub citations: serde_json::Value,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedFolder {
+    pub path: String,
+    pub file_count: u32,
+    pub last_indexed: Option<String>,
+    pub project_id: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +186,51 @@ pub struct ExclusionPattern {
     pub created_at: String,
 }
 
+/// Which versions of each document a query sees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Versions {
+    /// The current version of each path
+    Latest,
+    /// Every version ever indexed
+    All,
+    /// Per path, the highest version indexed at or before this moment
+    AsOf(DateTime<Utc>),
+}
+
+impl Versions {
+    /// `as_of` takes precedence over `include_historical`.
+    pub fn new(include_historical: bool, as_of: Option<DateTime<Utc>>) -> Self {
+        match as_of {
+            Some(as_of) => Versions::AsOf(as_of),
+            None if include_historical => Versions::All,
+            None => Versions::Latest,
+        }
+    }
+
+    /// Condition on the documents table aliased `d`, if any.
+    fn condition(&self) -> Option<&'static str> {
+        match self {
+            Versions::Latest => Some("d.is_latest = 1"),
+            Versions::All => None,
+            Versions::AsOf(_) => Some(
+                "d.indexed_at <= ? AND NOT EXISTS (SELECT 1 FROM documents newer \
+                 WHERE newer.path = d.path AND newer.version > d.version AND newer.indexed_at <= ?)",
+            ),
+        }
+    }
+
+    /// Bind the parameters of [`condition`](Self::condition).
+    fn bind<'q>(
+        &self,
+        query: sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>>,
+    ) -> sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>> {
+        match self {
+            Versions::AsOf(as_of) => query.bind(as_of.to_rfc3339()).bind(as_of.to_rfc3339()),
+            _ => query,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Database {
     pool: SqlitePool,
@@ -199,6 +388,243 @@ impl Database {
             .execute(&self.pool)
             .await?;
 
+        // Detected document language (ISO code) used to pick an analyzer
+        sqlx::query("ALTER TABLE documents ADD COLUMN language TEXT")
+            .execute(&self.pool)
+            .await
+            .ok(); // Ignore error if column already exists
+
+        // Chunk position within the document, for document-relative snippet
+        // offsets. Chunks indexed before this column existed report 0.
+        sqlx::query("ALTER TABLE index_entries ADD COLUMN start_offset INTEGER NOT NULL DEFAULT 0")
+            .execute(&self.pool)
+            .await
+            .ok(); // Ignore error if column already exists
+
+        // Analyzed (stemmed) terms per chunk, with an accent-folded variant
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS term_index (
+                document_id TEXT NOT NULL,
+                chunk_id INTEGER NOT NULL,
+                term TEXT NOT NULL,
+                folded_term TEXT NOT NULL,
+                frequency INTEGER NOT NULL DEFAULT 1,
+                FOREIGN KEY (document_id) REFERENCES documents (id)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_term_index_term ON term_index (term)")
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_term_index_folded_term ON term_index (folded_term)")
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_term_index_document_id ON term_index (document_id)")
+            .execute(&self.pool)
+            .await?;
+
+        // Surface-form vocabulary (chunk words, titles, tags) for typo correction
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS vocabulary (
+                document_id TEXT NOT NULL,
+                term TEXT NOT NULL,
+                folded_term TEXT NOT NULL,
+                frequency INTEGER NOT NULL DEFAULT 1,
+                FOREIGN KEY (document_id) REFERENCES documents (id)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_vocabulary_term ON vocabulary (term)")
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_vocabulary_folded_term ON vocabulary (folded_term)")
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_vocabulary_document_id ON vocabulary (document_id)")
+            .execute(&self.pool)
+            .await?;
+
+        // Chunk embeddings for semantic search, keyed by chunk and model.
+        // chunk_hash lets unchanged chunks of a new version reuse a vector.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS chunk_embeddings (
+                document_id TEXT NOT NULL,
+                chunk_id INTEGER NOT NULL,
+                model TEXT NOT NULL,
+                chunk_hash TEXT NOT NULL,
+                dimensions INTEGER NOT NULL,
+                vector BLOB NOT NULL,
+                PRIMARY KEY (document_id, chunk_id, model),
+                FOREIGN KEY (document_id) REFERENCES documents (id)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_chunk_embeddings_hash ON chunk_embeddings (chunk_hash, model)")
+            .execute(&self.pool)
+            .await?;
+
+        // Autocomplete candidates of the latest version of each path. The
+        // normalized (lowercased, accent-folded) text is range-scanned by prefix.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS suggestions (
+                document_id TEXT NOT NULL,
+                path TEXT NOT NULL,
+                text TEXT NOT NULL,
+                normalized TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                PRIMARY KEY (document_id, normalized, kind),
+                FOREIGN KEY (document_id) REFERENCES documents (id)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_suggestions_normalized ON suggestions (normalized)")
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_suggestions_path ON suggestions (path)")
+            .execute(&self.pool)
+            .await?;
+
+        // Saved searches (smart collections)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS saved_searches (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                query TEXT NOT NULL,
+                filters TEXT,
+                mode TEXT NOT NULL DEFAULT 'keyword',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                last_run_at TEXT
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Synonym and abbreviation dictionary, per project or global
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS synonyms (
+                id TEXT PRIMARY KEY,
+                project_id TEXT,
+                term TEXT NOT NULL,
+                synonyms TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_synonyms_project ON synonyms (project_id)")
+            .execute(&self.pool)
+            .await?;
+
+        // Generated summaries by document, length and model
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS summaries (
+                document_id TEXT NOT NULL,
+                length TEXT NOT NULL,
+                model TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                prompt_hash TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                sections INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (document_id, length, model)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Conversational Q&A sessions and their turns
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS conversations (
+                id TEXT PRIMARY KEY,
+                title TEXT,
+                project_ids TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS conversation_turns (
+                id TEXT PRIMARY KEY,
+                conversation_id TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                question TEXT NOT NULL,
+                standalone_question TEXT NOT NULL,
+                answer TEXT NOT NULL,
+                citations TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (conversation_id) REFERENCES conversations (id)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_conversation_turns_conversation ON conversation_turns (conversation_id, position)")
+            .execute(&self.pool)
+            .await?;
+
+        // Prompt template overrides, per project or global
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS prompt_templates (
+                project_id TEXT,
+                kind TEXT NOT NULL,
+                template TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // One override per kind and project; global ones have no project.
+        // Keep the newest of any duplicates stored before the index existed.
+        sqlx::query(
+            "DELETE FROM prompt_templates WHERE rowid NOT IN (SELECT MAX(rowid) FROM prompt_templates GROUP BY kind, IFNULL(project_id, ''))",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_prompt_templates_kind_project ON prompt_templates (kind, IFNULL(project_id, ''))")
+            .execute(&self.pool)
+            .await?;
+
         Ok(())
     }
 
@@ -270,6 +696,23 @@ impl Database {
         Ok(out)
     }
 
+    pub async fn get_indexed_folders(&self) -> Result<Vec<IndexedFolder>> {
+        let rows = sqlx::query("SELECT path, file_count, last_indexed, project_id FROM indexed_folders ORDER BY path")
+            .fetch_all(&self.pool)
+            .await?;
+        let mut out = Vec::new();
+        for row in rows {
+            let project_id: Option<String> = row.get("project_id");
+            out.push(IndexedFolder {
+                path: row.get("path"),
+                file_count: row.get::<i64, _>("file_count") as u32,
+                last_indexed: row.get("last_indexed"),
+                project_id: project_id.and_then(|s| Uuid::parse_str(&s).ok()),
+            });
+        }
+        Ok(out)
+    }
+
     pub async fn remove_indexed_folder(&self, path: &str) -> Result<()> {
         sqlx::query("DELETE FROM indexed_folders WHERE path = ?")
             .bind(path)
@@ -314,24 +757,30 @@ impl Database {
     }
 
     // Project management methods
-    pub async fn list_projects(&self) -> Result<Vec<serde_json::Value>> {
+    pub async fn list_projects(&self) -> Result<Vec<Project>> {
         let rows = sqlx::query("SELECT id, name, description, created_at, updated_at FROM projects ORDER BY name")
             .fetch_all(&self.pool)
             .await?;
         let mut out = Vec::new();
         for row in rows {
-            out.push(serde_json::json!({
-                "id": row.get::<String, _>("id"),
-                "name": row.get::<String, _>("name"),
-                "description": row.get::<Option<String>, _>("description"),
-                "created_at": row.get::<String, _>("created_at"),
-                "updated_at": row.get::<String, _>("updated_at")
-            }));
+            out.push(project_from_row(&row)?);
         }
         Ok(out)
     }
 
-    pub async fn create_project(&self, name: &str, description: Option<&str>) -> Result<serde_json::Value> {
+    pub async fn get_project(&self, id: &Uuid) -> Result<Option<Project>> {
+        let row = sqlx::query("SELECT id, name, description, created_at, updated_at FROM projects WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => Ok(Some(project_from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn create_project(&self, name: &str, description: Option<&str>) -> Result<Project> {
         let id = Uuid::new_v4();
         let now = Utc::now();
         
@@ -349,16 +798,16 @@ impl Database {
         .execute(&self.pool)
         .await?;
 
-        Ok(serde_json::json!({
-            "id": id.to_string(),
-            "name": name,
-            "description": description,
-            "created_at": now.to_rfc3339(),
-            "updated_at": now.to_rfc3339()
-        }))
+        Ok(Project {
+            id,
+            name: name.to_string(),
+            description: description.map(|d| d.to_string()),
+            created_at: now.to_rfc3339(),
+            updated_at: now.to_rfc3339(),
+        })
     }
 
-    pub async fn update_project(&self, id: &Uuid, name: Option<&str>, description: Option<&str>) -> Result<Option<serde_json::Value>> {
+    pub async fn update_project(&self, id: &Uuid, name: Option<&str>, description: Option<&str>) -> Result<Option<Project>> {
         let now = Utc::now();
         
         // Update name if provided
@@ -382,22 +831,414 @@ impl Database {
         }
 
         // Get updated project
-        let row = sqlx::query("SELECT id, name, description, created_at, updated_at FROM projects WHERE id = ?")
-            .bind(id.to_string())
-            .fetch_optional(&self.pool)
+        self.get_project(id).await
+    }
+
+    pub async fn create_saved_search(
+        &self,
+        name: &str,
+        query: &str,
+        filters: Option<&serde_json::Value>,
+        mode: &str,
+    ) -> Result<SavedSearch> {
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO saved_searches (id, name, query, filters, mode, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(id.to_string())
+        .bind(name)
+        .bind(query)
+        .bind(filters.map(|f| f.to_string()))
+        .bind(mode)
+        .bind(now.to_rfc3339())
+        .bind(now.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(SavedSearch {
+            id,
+            name: name.to_string(),
+            query: query.to_string(),
+            filters: filters.cloned(),
+            mode: mode.to_string(),
+            created_at: now.to_rfc3339(),
+            updated_at: now.to_rfc3339(),
+            last_run_at: None,
+        })
+    }
+
+    pub async fn list_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        let rows = sqlx::query(
+            "SELECT id, name, query, filters, mode, created_at, updated_at, last_run_at FROM saved_searches ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(saved_search_from_row).collect()
+    }
+
+    /// Look up a saved search by id or, failing that, by name.
+    pub async fn get_saved_search(&self, id_or_name: &str) -> Result<Option<SavedSearch>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, name, query, filters, mode, created_at, updated_at, last_run_at
+            FROM saved_searches
+            WHERE id = ? OR name = ?
+            ORDER BY id = ? DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(id_or_name)
+        .bind(id_or_name)
+        .bind(id_or_name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(saved_search_from_row).transpose()
+    }
+
+    pub async fn mark_saved_search_run(&self, id: &Uuid, run_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE saved_searches SET last_run_at = ? WHERE id = ?")
+            .bind(run_at.to_rfc3339())
+            .bind(id.to_string())
+            .execute(&self.pool)
             .await?;
 
-        if let Some(row) = row {
-            Ok(Some(serde_json::json!({
-                "id": row.get::<String, _>("id"),
-                "name": row.get::<String, _>("name"),
-                "description": row.get::<Option<String>, _>("description"),
-                "created_at": row.get::<String, _>("created_at"),
-                "updated_at": row.get::<String, _>("updated_at")
-            })))
-        } else {
-            Ok(None)
+        Ok(())
+    }
+
+    pub async fn delete_saved_search(&self, id: &Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM saved_searches WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_cached_summary(&self, document_id: &Uuid, length: &str, model: &str) -> Result<Option<CachedSummary>> {
+        let row = sqlx::query(
+            r#"
+            SELECT document_id, length, model, content_hash, prompt_hash, summary, sections, created_at
+            FROM summaries
+            WHERE document_id = ? AND length = ? AND model = ?
+            "#,
+        )
+        .bind(document_id.to_string())
+        .bind(length)
+        .bind(model)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(cached_summary_from_row).transpose()
+    }
+
+    /// Store a summary, replacing the one cached for the same document,
+    /// length and model.
+    pub async fn store_summary(&self, summary: &CachedSummary) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO summaries (document_id, length, model, content_hash, prompt_hash, summary, sections, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(summary.document_id.to_string())
+        .bind(&summary.length)
+        .bind(&summary.model)
+        .bind(&summary.content_hash)
+        .bind(&summary.prompt_hash)
+        .bind(&summary.summary)
+        .bind(summary.sections as i64)
+        .bind(&summary.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn create_conversation(&self, title: Option<&str>, project_ids: &[Uuid]) -> Result<Conversation> {
+        let id = Uuid::new_v4();
+        let now = Utc::now().to_rfc3339();
+
+        sqlx::query("INSERT INTO conversations (id, title, project_ids, created_at, updated_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(id.to_string())
+            .bind(title)
+            .bind(serde_json::to_string(project_ids)?)
+            .bind(&now)
+            .bind(&now)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(Conversation {
+            id,
+            title: title.map(str::to_string),
+            project_ids: project_ids.to_vec(),
+            turn_count: 0,
+            created_at: now.clone(),
+            updated_at: now,
+        })
+    }
+
+    /// Conversations with the most recently active first.
+    pub async fn list_conversations(&self, limit: u32) -> Result<Vec<Conversation>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT c.id, c.title, c.project_ids, c.created_at, c.updated_at,
+                   (SELECT COUNT(*) FROM conversation_turns t WHERE t.conversation_id = c.id) AS turn_count
+            FROM conversations c
+            ORDER BY c.updated_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(conversation_from_row).collect()
+    }
+
+    pub async fn get_conversation(&self, id: &Uuid) -> Result<Option<Conversation>> {
+        let row = sqlx::query(
+            r#"
+            SELECT c.id, c.title, c.project_ids, c.created_at, c.updated_at,
+                   (SELECT COUNT(*) FROM conversation_turns t WHERE t.conversation_id = c.id) AS turn_count
+            FROM conversations c
+            WHERE c.id = ?
+            "#,
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(conversation_from_row).transpose()
+    }
+
+    /// The turns of a conversation in order. With `last`, only the most
+    /// recent `last` turns.
+    pub async fn get_conversation_turns(&self, conversation_id: &Uuid, last: Option<u32>) -> Result<Vec<ConversationTurn>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, conversation_id, position, question, standalone_question, answer, citations, created_at
+            FROM conversation_turns
+            WHERE conversation_id = ?
+            ORDER BY position DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(conversation_id.to_string())
+        .bind(last.map(i64::from).unwrap_or(-1))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut turns = rows.iter().map(conversation_turn_from_row).collect::<Result<Vec<_>>>()?;
+        turns.reverse();
+        Ok(turns)
+    }
+
+    /// Append a turn to a conversation and mark the conversation active.
+    pub async fn add_conversation_turn(
+        &self,
+        conversation_id: &Uuid,
+        question: &str,
+        standalone_question: &str,
+        answer: &str,
+        citations: &serde_json::Value,
+    ) -> Result<ConversationTurn> {
+        let id = Uuid::new_v4();
+        let now = Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+
+        let position: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(MAX(position), 0) + 1 FROM conversation_turns WHERE conversation_id = ?",
+        )
+        .bind(conversation_id.to_string())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO conversation_turns (id, conversation_id, position, question, standalone_question, answer, citations, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(id.to_string())
+        .bind(conversation_id.to_string())
+        .bind(position)
+        .bind(question)
+        .bind(standalone_question)
+        .bind(answer)
+        .bind(citations.to_string())
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE conversations SET updated_at = ? WHERE id = ?")
+            .bind(&now)
+            .bind(conversation_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(ConversationTurn {
+            id,
+            conversation_id: *conversation_id,
+            position: position as u32,
+            question: question.to_string(),
+            standalone_question: standalone_question.to_string(),
+            answer: answer.to_string(),
+            citations: citations.clone(),
+            created_at: now,
+        })
+    }
+
+    /// Delete a conversation with all of its turns.
+    pub async fn delete_conversation(&self, id: &Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM conversation_turns WHERE conversation_id = ?")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        let result = sqlx::query("DELETE FROM conversations WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Store the synonyms of `term` in a project's dictionary (or the global
+    /// one), replacing any existing entry for the same term.
+    pub async fn upsert_synonym(&self, project_id: Option<&Uuid>, term: &str, synonyms: &[String]) -> Result<Synonym> {
+        let now = Utc::now().to_rfc3339();
+        let project = project_id.map(|id| id.to_string());
+        let existing = sqlx::query(
+            "SELECT id, created_at FROM synonyms WHERE project_id IS ? AND lower(term) = lower(?)",
+        )
+        .bind(&project)
+        .bind(term)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let (id, created_at) = match existing {
+            Some(row) => (Uuid::parse_str(&row.get::<String, _>("id"))?, row.get("created_at")),
+            None => (Uuid::new_v4(), now.clone()),
+        };
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO synonyms (id, project_id, term, synonyms, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(id.to_string())
+        .bind(&project)
+        .bind(term)
+        .bind(serde_json::to_string(synonyms)?)
+        .bind(&created_at)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(Synonym {
+            id,
+            project_id: project_id.copied(),
+            term: term.to_string(),
+            synonyms: synonyms.to_vec(),
+            created_at,
+            updated_at: now,
+        })
+    }
+
+    /// Dictionary entries that apply to a search in `project_ids`: the global
+    /// ones plus those of the given projects, or every entry when unscoped.
+    pub async fn get_synonyms(&self, project_ids: Option<&[Uuid]>) -> Result<Vec<Synonym>> {
+        let mut sql = "SELECT id, project_id, term, synonyms, created_at, updated_at FROM synonyms".to_string();
+        let ids: Vec<String> = project_ids.unwrap_or_default().iter().map(|id| id.to_string()).collect();
+        if project_ids.is_some() {
+            sql.push_str(&format!(
+                " WHERE project_id IS NULL OR project_id IN ({})",
+                vec!["?"; ids.len()].join(",")
+            ));
         }
+        sql.push_str(" ORDER BY project_id IS NOT NULL, lower(term)");
+
+        let mut query = sqlx::query(&sql);
+        for id in &ids {
+            query = query.bind(id);
+        }
+        let rows = query.fetch_all(&self.pool).await?;
+
+        rows.iter().map(synonym_from_row).collect()
+    }
+
+    pub async fn delete_synonym(&self, id: &Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM synonyms WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Store a prompt template override, replacing the previous one for the
+    /// same project (or the global one) and kind.
+    pub async fn set_prompt_template(&self, project_id: Option<&Uuid>, kind: &str, template: &str) -> Result<PromptTemplate> {
+        let project = project_id.map(|id| id.to_string());
+        let now = Utc::now().to_rfc3339();
+        sqlx::query("INSERT OR REPLACE INTO prompt_templates (project_id, kind, template, updated_at) VALUES (?, ?, ?, ?)")
+            .bind(&project)
+            .bind(kind)
+            .bind(template)
+            .bind(&now)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(PromptTemplate {
+            project_id: project_id.copied(),
+            kind: kind.to_string(),
+            template: template.to_string(),
+            updated_at: now,
+        })
+    }
+
+    pub async fn get_prompt_template(&self, project_id: Option<&Uuid>, kind: &str) -> Result<Option<PromptTemplate>> {
+        let row = sqlx::query(
+            "SELECT project_id, kind, template, updated_at FROM prompt_templates WHERE project_id IS ? AND kind = ?",
+        )
+        .bind(project_id.map(|id| id.to_string()))
+        .bind(kind)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(prompt_template_from_row).transpose()
+    }
+
+    pub async fn list_prompt_templates(&self) -> Result<Vec<PromptTemplate>> {
+        let rows = sqlx::query(
+            "SELECT project_id, kind, template, updated_at FROM prompt_templates ORDER BY project_id IS NOT NULL, project_id, kind",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(prompt_template_from_row).collect()
+    }
+
+    pub async fn delete_prompt_template(&self, project_id: Option<&Uuid>, kind: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM prompt_templates WHERE project_id IS ? AND kind = ?")
+            .bind(project_id.map(|id| id.to_string()))
+            .bind(kind)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
     }
 
     pub async fn delete_project(&self, id: &Uuid) -> Result<bool> {
@@ -421,6 +1262,15 @@ impl Database {
             .execute(&self.pool)
             .await?;
 
+        sqlx::query("DELETE FROM synonyms WHERE project_id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("DELETE FROM prompt_templates WHERE project_id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
         Ok(result.rows_affected() > 0)
     }
 
@@ -440,6 +1290,27 @@ impl Database {
                 .bind(&id_str)
                 .execute(&self.pool)
                 .await?;
+            // Delete analyzed terms and vocabulary
+            sqlx::query("DELETE FROM term_index WHERE document_id = ?")
+                .bind(&id_str)
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("DELETE FROM vocabulary WHERE document_id = ?")
+                .bind(&id_str)
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("DELETE FROM suggestions WHERE document_id = ?")
+                .bind(&id_str)
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("DELETE FROM chunk_embeddings WHERE document_id = ?")
+                .bind(&id_str)
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("DELETE FROM summaries WHERE document_id = ?")
+                .bind(&id_str)
+                .execute(&self.pool)
+                .await?;
             // Delete document snapshots
             sqlx::query("DELETE FROM document_snapshots WHERE document_id = ?")
                 .bind(&id_str)
@@ -462,8 +1333,8 @@ impl Database {
         sqlx::query(
             r#"
             INSERT INTO documents 
-            (id, path, filename, extension, size, modified_at, title, tags, headings, content_excerpt, content_hash, indexed_at, version, is_latest, project_id)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            (id, path, filename, extension, size, modified_at, title, tags, headings, content_excerpt, content_hash, indexed_at, version, is_latest, project_id, language)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(document.id.to_string())
@@ -481,243 +1352,891 @@ impl Database {
         .bind(document.version as i64)
         .bind(document.is_latest)
         .bind(document.project_id.map(|id| id.to_string()))
+        .bind(&document.language)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
-    pub async fn insert_index_entries(&self, entries: &[IndexEntry]) -> Result<()> {
+    pub async fn insert_index_entries(&self, entries: &[IndexEntry]) -> Result<()> {
+        for entry in entries {
+            let positions_json = serde_json::to_string(&entry.positions)?;
+
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO index_entries 
+                (id, document_id, chunk_id, chunk_text, positions, start_offset)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(entry.id.to_string())
+            .bind(entry.document_id.to_string())
+            .bind(entry.chunk_id as i64)
+            .bind(&entry.chunk_text)
+            .bind(positions_json)
+            .bind(entry.start_offset as i64)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Latest document versions ordered by modification time (newest first),
+    /// starting after `after` = (modified_at, id) when given.
+    pub async fn list_documents_page(&self, limit: u32, offset: u32, after: Option<(&str, &Uuid)>) -> Result<Vec<Document>> {
+        let where_clause = if after.is_some() {
+            "is_latest = 1 AND (modified_at < ? OR (modified_at = ? AND id > ?))"
+        } else {
+            "is_latest = 1"
+        };
+        let query_str = format!(
+            r#"
+            SELECT id, path, filename, extension, size, modified_at, title, tags, headings, content_excerpt, content_hash, indexed_at, version, is_latest, project_id, language
+            FROM documents
+            WHERE {}
+            ORDER BY modified_at DESC, id ASC
+            LIMIT ? OFFSET ?
+            "#,
+            where_clause
+        );
+
+        let mut query_builder = sqlx::query(&query_str);
+        if let Some((modified_at, id)) = after {
+            query_builder = query_builder
+                .bind(modified_at.to_string())
+                .bind(modified_at.to_string())
+                .bind(id.to_string());
+        }
+        let rows = query_builder
+            .bind(limit as i64)
+            .bind(offset as i64)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut documents = Vec::new();
+        for row in rows {
+            documents.push(document_from_row(&row)?);
+        }
+        Ok(documents)
+    }
+
+    /// Latest document versions in a project, under a folder and/or carrying
+    /// a tag (case-insensitive), newest first. Unset criteria match everything.
+    pub async fn list_collection_documents(
+        &self,
+        project_id: Option<&Uuid>,
+        folder: Option<&str>,
+        tag: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<Document>> {
+        let mut conditions = vec!["is_latest = 1"];
+        if project_id.is_some() {
+            conditions.push("project_id = ?");
+        }
+        if folder.is_some() {
+            conditions.push("substr(path, 1, length(?)) = ?");
+        }
+        if tag.is_some() {
+            conditions.push("EXISTS (SELECT 1 FROM json_each(documents.tags) WHERE lower(json_each.value) = lower(?))");
+        }
+        let query_str = format!(
+            r#"
+            SELECT id, path, filename, extension, size, modified_at, title, tags, headings, content_excerpt, content_hash, indexed_at, version, is_latest, project_id, language
+            FROM documents
+            WHERE {}
+            ORDER BY modified_at DESC, id ASC
+            LIMIT ?
+            "#,
+            conditions.join(" AND ")
+        );
+
+        let mut query_builder = sqlx::query(&query_str);
+        if let Some(project_id) = project_id {
+            query_builder = query_builder.bind(project_id.to_string());
+        }
+        if let Some(folder) = folder {
+            // A folder matches the paths inside it, not its siblings sharing a prefix
+            let prefix = format!("{}/", folder.trim_end_matches('/'));
+            query_builder = query_builder.bind(prefix.clone()).bind(prefix);
+        }
+        if let Some(tag) = tag {
+            query_builder = query_builder.bind(tag.to_string());
+        }
+        let rows = query_builder.bind(limit as i64).fetch_all(&self.pool).await?;
+
+        let mut documents = Vec::new();
+        for row in rows {
+            documents.push(document_from_row(&row)?);
+        }
+        Ok(documents)
+    }
+
+    pub async fn count_latest_documents(&self) -> Result<u64> {
+        let row = sqlx::query("SELECT COUNT(*) as count FROM documents WHERE is_latest = 1")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.get::<i64, _>("count") as u64)
+    }
+
+    pub async fn search_documents(
+        &self,
+        query: &str,
+        limit: u32,
+        offset: u32,
+        include_historical: bool,
+    ) -> Result<Vec<Document>> {
+        self.search_documents_with_filters(query, limit, offset, Versions::new(include_historical, None), None).await
+    }
+
+    pub async fn search_documents_with_filters(
+        &self,
+        query: &str,
+        limit: u32,
+        offset: u32,
+        versions: Versions,
+        project_ids: Option<&[Uuid]>,
+    ) -> Result<Vec<Document>> {
+        let mut base_where_clause =
+            "(d.filename LIKE ? OR d.content_excerpt LIKE ? OR d.title LIKE ? OR ie.chunk_text LIKE ?)".to_string();
+        if let Some(condition) = versions.condition() {
+            base_where_clause.push_str(&format!(" AND {}", condition));
+        }
+
+        // Add project filtering if specified
+        let final_where_clause = if let Some(project_ids) = project_ids {
+            if !project_ids.is_empty() {
+                format!("({}) AND d.project_id IN ({})", 
+                    base_where_clause, 
+                    project_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+                )
+            } else {
+                base_where_clause
+            }
+        } else {
+            base_where_clause
+        };
+
+        let query_str = format!(
+            r#"
+            SELECT DISTINCT d.id, d.path, d.filename, d.extension, d.size, d.modified_at, d.title, d.tags, d.headings, d.content_excerpt, d.content_hash, d.indexed_at, d.version, d.is_latest, d.project_id, d.language
+            FROM documents d
+            LEFT JOIN index_entries ie ON d.id = ie.document_id
+            WHERE {}
+            ORDER BY d.modified_at DESC
+            LIMIT ? OFFSET ?
+            "#,
+            final_where_clause
+        );
+
+        let mut query_builder = sqlx::query(&query_str);
+
+        // Bind the search query parameters
+        query_builder = query_builder
+            .bind(format!("%{}%", query))
+            .bind(format!("%{}%", query))
+            .bind(format!("%{}%", query))
+            .bind(format!("%{}%", query));
+        query_builder = versions.bind(query_builder);
+
+        // Bind project IDs if specified
+        if let Some(project_ids) = project_ids {
+            if !project_ids.is_empty() {
+                for project_id in project_ids {
+                    query_builder = query_builder.bind(project_id.to_string());
+                }
+            }
+        }
+
+        // Bind limit and offset
+        query_builder = query_builder
+            .bind(limit as i64)
+            .bind(offset as i64);
+
+        let documents = query_builder.fetch_all(&self.pool).await?;
+
+        let mut results = Vec::new();
+        for row in documents {
+            results.push(document_from_row(&row)?);
+        }
+
+        Ok(results)
+    }
+
+    pub async fn get_document_by_id(&self, id: &Uuid) -> Result<Option<Document>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, path, filename, extension, size, modified_at, title, tags, headings, content_excerpt, content_hash, indexed_at, version, is_latest, project_id, language
+            FROM documents
+            WHERE id = ?
+            "#,
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(row) = row {
+            Ok(Some(document_from_row(&row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn get_document_by_path(&self, path: &Path) -> Result<Option<Document>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, path, filename, extension, size, modified_at, title, tags, headings, content_excerpt, content_hash, indexed_at, version, is_latest, project_id, language
+            FROM documents
+            WHERE path = ?
+            "#,
+        )
+        .bind(path.to_string_lossy())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(row) = row {
+            Ok(Some(document_from_row(&row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn get_index_entries_for_document(&self, document_id: &Uuid) -> Result<Vec<IndexEntry>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, document_id, chunk_id, chunk_text, positions, start_offset
+            FROM index_entries
+            WHERE document_id = ?
+            ORDER BY chunk_id
+            "#,
+        )
+        .bind(document_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(index_entry_from_row(&row)?);
+        }
+
+        Ok(results)
+    }
+
+    /// Chunks containing any of the analyzed `terms`, compared like in
+    /// [`search_documents_by_terms`](Self::search_documents_by_terms).
+    pub async fn get_index_entries_matching_terms(
+        &self,
+        terms: &[String],
+        accent_insensitive: bool,
+        versions: Versions,
+        project_ids: Option<&[Uuid]>,
+    ) -> Result<Vec<IndexEntry>> {
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let term_column = if accent_insensitive { "t.folded_term" } else { "t.term" };
+        let mut where_clause = format!(
+            "{} IN ({})",
+            term_column,
+            terms.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+        );
+        if let Some(condition) = versions.condition() {
+            where_clause.push_str(&format!(" AND {}", condition));
+        }
+        if let Some(project_ids) = project_ids {
+            if !project_ids.is_empty() {
+                where_clause.push_str(&format!(
+                    " AND d.project_id IN ({})",
+                    project_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+                ));
+            }
+        }
+
+        let query_str = format!(
+            r#"
+            SELECT DISTINCT ie.id, ie.document_id, ie.chunk_id, ie.chunk_text, ie.positions, ie.start_offset
+            FROM term_index t
+            JOIN documents d ON d.id = t.document_id
+            JOIN index_entries ie ON ie.document_id = t.document_id AND ie.chunk_id = t.chunk_id
+            WHERE {}
+            ORDER BY ie.document_id, ie.chunk_id
+            "#,
+            where_clause
+        );
+
+        let mut query_builder = sqlx::query(&query_str);
+        for term in terms {
+            query_builder = query_builder.bind(term);
+        }
+        query_builder = versions.bind(query_builder);
+        if let Some(project_ids) = project_ids {
+            for project_id in project_ids {
+                query_builder = query_builder.bind(project_id.to_string());
+            }
+        }
+
+        let rows = query_builder.fetch_all(&self.pool).await?;
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(index_entry_from_row(&row)?);
+        }
+
+        Ok(results)
+    }
+
+    pub async fn insert_term_entries(&self, entries: &[TermEntry]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for entry in entries {
+            sqlx::query(
+                r#"
+                INSERT INTO term_index (document_id, chunk_id, term, folded_term, frequency)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(entry.document_id.to_string())
+            .bind(entry.chunk_id as i64)
+            .bind(&entry.term)
+            .bind(&entry.folded_term)
+            .bind(entry.frequency as i64)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Index entries of latest document versions that have no embedding
+    /// for `model` yet (new or changed chunks, or a newly configured model).
+    pub async fn get_chunks_missing_embeddings(&self, model: &str) -> Result<Vec<IndexEntry>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT ie.id, ie.document_id, ie.chunk_id, ie.chunk_text, ie.positions, ie.start_offset
+            FROM index_entries ie
+            JOIN documents d ON d.id = ie.document_id
+            LEFT JOIN chunk_embeddings ce
+              ON ce.document_id = ie.document_id AND ce.chunk_id = ie.chunk_id AND ce.model = ?
+            WHERE d.is_latest = 1 AND ce.document_id IS NULL
+            ORDER BY ie.document_id, ie.chunk_id
+            "#,
+        )
+        .bind(model)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(index_entry_from_row(&row)?);
+        }
+        Ok(results)
+    }
+
+    pub async fn find_embedding_by_hash(&self, chunk_hash: &str, model: &str) -> Result<Option<Vec<f32>>> {
+        let row = sqlx::query("SELECT vector FROM chunk_embeddings WHERE chunk_hash = ? AND model = ? LIMIT 1")
+            .bind(chunk_hash)
+            .bind(model)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|row| decode_vector(&row.get::<Vec<u8>, _>("vector"))))
+    }
+
+    pub async fn insert_chunk_embedding(&self, embedding: &ChunkEmbedding) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO chunk_embeddings (document_id, chunk_id, model, chunk_hash, dimensions, vector)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(embedding.document_id.to_string())
+        .bind(embedding.chunk_id as i64)
+        .bind(&embedding.model)
+        .bind(&embedding.chunk_hash)
+        .bind(embedding.vector.len() as i64)
+        .bind(encode_vector(&embedding.vector))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// All chunk embeddings for `model`, restricted like the keyword search.
+    pub async fn get_chunk_embeddings(
+        &self,
+        model: &str,
+        versions: Versions,
+        project_ids: Option<&[Uuid]>,
+    ) -> Result<Vec<ChunkEmbedding>> {
+        let mut where_clause = "ce.model = ?".to_string();
+        if let Some(condition) = versions.condition() {
+            where_clause.push_str(&format!(" AND {}", condition));
+        }
+        if let Some(project_ids) = project_ids {
+            if !project_ids.is_empty() {
+                where_clause.push_str(&format!(
+                    " AND d.project_id IN ({})",
+                    project_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+                ));
+            }
+        }
+
+        let query_str = format!(
+            r#"
+            SELECT ce.document_id, ce.chunk_id, ce.model, ce.chunk_hash, ce.vector
+            FROM chunk_embeddings ce
+            JOIN documents d ON d.id = ce.document_id
+            WHERE {}
+            "#,
+            where_clause
+        );
+
+        let mut query_builder = versions.bind(sqlx::query(&query_str).bind(model));
+        if let Some(project_ids) = project_ids {
+            for project_id in project_ids {
+                query_builder = query_builder.bind(project_id.to_string());
+            }
+        }
+
+        let rows = query_builder.fetch_all(&self.pool).await?;
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(ChunkEmbedding {
+                document_id: Uuid::parse_str(&row.get::<String, _>("document_id"))?,
+                chunk_id: row.get::<i64, _>("chunk_id") as u32,
+                model: row.get("model"),
+                chunk_hash: row.get("chunk_hash"),
+                vector: decode_vector(&row.get::<Vec<u8>, _>("vector")),
+            });
+        }
+        Ok(results)
+    }
+
+    pub async fn get_index_entry(&self, document_id: &Uuid, chunk_id: u32) -> Result<Option<IndexEntry>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, document_id, chunk_id, chunk_text, positions, start_offset
+            FROM index_entries
+            WHERE document_id = ? AND chunk_id = ?
+            "#,
+        )
+        .bind(document_id.to_string())
+        .bind(chunk_id as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(index_entry_from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn insert_vocabulary(&self, document_id: &Uuid, entries: &[VocabularyEntry]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for entry in entries {
+            sqlx::query(
+                r#"
+                INSERT INTO vocabulary (document_id, term, folded_term, frequency)
+                VALUES (?, ?, ?, ?)
+                "#,
+            )
+            .bind(document_id.to_string())
+            .bind(&entry.term)
+            .bind(crate::analysis::fold_accents(&entry.term))
+            .bind(entry.frequency as i64)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Replace the autocomplete candidates of `document`'s path with `entries`,
+    /// dropping those of earlier versions.
+    pub async fn replace_suggestions(&self, document: &Document, entries: &[SuggestionEntry]) -> Result<()> {
+        let path = document.path.to_string_lossy();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM suggestions WHERE path = ?")
+            .bind(path.as_ref())
+            .execute(&mut *tx)
+            .await?;
         for entry in entries {
-            let positions_json = serde_json::to_string(&entry.positions)?;
-
             sqlx::query(
                 r#"
-                INSERT OR REPLACE INTO index_entries 
-                (id, document_id, chunk_id, chunk_text, positions)
+                INSERT OR IGNORE INTO suggestions (document_id, path, text, normalized, kind)
                 VALUES (?, ?, ?, ?, ?)
                 "#,
             )
-            .bind(entry.id.to_string())
-            .bind(entry.document_id.to_string())
-            .bind(entry.chunk_id as i64)
-            .bind(&entry.chunk_text)
-            .bind(positions_json)
-            .execute(&self.pool)
+            .bind(document.id.to_string())
+            .bind(path.as_ref())
+            .bind(&entry.text)
+            .bind(normalize_suggestion(&entry.text))
+            .bind(&entry.kind)
+            .execute(&mut *tx)
             .await?;
         }
+        tx.commit().await?;
 
         Ok(())
     }
 
-    pub async fn search_documents(
-        &self,
-        query: &str,
-        limit: u32,
-        offset: u32,
-        include_historical: bool,
-    ) -> Result<Vec<Document>> {
-        self.search_documents_with_filters(query, limit, offset, include_historical, None).await
+    pub async fn has_suggestions(&self, document_id: &Uuid) -> Result<bool> {
+        let row = sqlx::query("SELECT 1 FROM suggestions WHERE document_id = ? LIMIT 1")
+            .bind(document_id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.is_some())
     }
 
-    pub async fn search_documents_with_filters(
+    /// Candidates starting with `prefix` (case and accent insensitive), most
+    /// widespread first. Titles win over headings, tags and terms on ties.
+    pub async fn suggest(&self, prefix: &str, limit: u32, project_ids: Option<&[Uuid]>) -> Result<Vec<Suggestion>> {
+        let normalized = normalize_suggestion(prefix);
+        if normalized.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (project_join, project_params) = match project_ids {
+            Some(ids) if !ids.is_empty() => (
+                format!(
+                    "JOIN documents d ON d.id = s.document_id AND d.project_id IN ({})",
+                    vec!["?"; ids.len()].join(",")
+                ),
+                ids.iter().map(|id| id.to_string()).collect(),
+            ),
+            _ => (String::new(), Vec::new()),
+        };
+        let sql = format!(
+            r#"
+            SELECT MIN(s.text) AS text,
+                   MIN(CASE s.kind WHEN 'title' THEN 0 WHEN 'heading' THEN 1 WHEN 'tag' THEN 2 ELSE 3 END) AS priority,
+                   COUNT(DISTINCT s.document_id) AS document_frequency
+            FROM suggestions s
+            {}
+            WHERE s.normalized >= ? AND s.normalized < ?
+            GROUP BY s.normalized
+            ORDER BY document_frequency DESC, priority, s.normalized
+            LIMIT ?
+            "#,
+            project_join
+        );
+
+        let mut query = sqlx::query(&sql);
+        for id in &project_params {
+            query = query.bind(id);
+        }
+        // Every string with the prefix sorts below prefix + U+10FFFF
+        let upper_bound = format!("{}{}", normalized, char::MAX);
+        let rows = query
+            .bind(&normalized)
+            .bind(upper_bound)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Suggestion {
+                text: row.get("text"),
+                kind: match row.get::<i64, _>("priority") {
+                    0 => "title",
+                    1 => "heading",
+                    2 => "tag",
+                    _ => "term",
+                }
+                .to_string(),
+                document_frequency: row.get::<i64, _>("document_frequency") as u32,
+            })
+            .collect())
+    }
+
+    /// Analyzed terms of a document with their total frequency across chunks.
+    pub async fn get_document_term_frequencies(&self, document_id: &Uuid) -> Result<Vec<(String, u32)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT term, SUM(frequency) as frequency
+            FROM term_index
+            WHERE document_id = ?
+            GROUP BY term
+            ORDER BY frequency DESC, term ASC
+            "#,
+        )
+        .bind(document_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("term"), row.get::<i64, _>("frequency") as u32))
+            .collect())
+    }
+
+    /// Number of latest documents containing each of `terms`.
+    pub async fn get_term_document_counts(&self, terms: &[String]) -> Result<std::collections::HashMap<String, u32>> {
+        if terms.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+
+        let query_str = format!(
+            r#"
+            SELECT t.term, COUNT(DISTINCT t.document_id) as count
+            FROM term_index t
+            JOIN documents d ON d.id = t.document_id
+            WHERE d.is_latest = 1 AND t.term IN ({})
+            GROUP BY t.term
+            "#,
+            terms.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+        );
+        let mut query_builder = sqlx::query(&query_str);
+        for term in terms {
+            query_builder = query_builder.bind(term);
+        }
+
+        let rows = query_builder.fetch_all(&self.pool).await?;
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("term"), row.get::<i64, _>("count") as u32))
+            .collect())
+    }
+
+    /// (document, term) pairs of latest documents other than `exclude` that
+    /// contain any of `terms`.
+    pub async fn find_documents_sharing_terms(
         &self,
-        query: &str,
-        limit: u32,
-        offset: u32,
-        include_historical: bool,
+        terms: &[String],
+        exclude: &Uuid,
         project_ids: Option<&[Uuid]>,
-    ) -> Result<Vec<Document>> {
-        let base_where_clause = if include_historical {
-            "d.filename LIKE ? OR d.content_excerpt LIKE ? OR d.title LIKE ? OR ie.chunk_text LIKE ?"
-        } else {
-            "d.is_latest = 1 AND (d.filename LIKE ? OR d.content_excerpt LIKE ? OR d.title LIKE ? OR ie.chunk_text LIKE ?)"
-        };
+    ) -> Result<Vec<(Uuid, String)>> {
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
 
-        // Add project filtering if specified
-        let final_where_clause = if let Some(project_ids) = project_ids {
+        let mut where_clause = format!(
+            "d.is_latest = 1 AND t.document_id != ? AND t.term IN ({})",
+            terms.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+        );
+        if let Some(project_ids) = project_ids {
             if !project_ids.is_empty() {
-                format!("({}) AND d.project_id IN ({})", 
-                    base_where_clause, 
+                where_clause.push_str(&format!(
+                    " AND d.project_id IN ({})",
                     project_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",")
-                )
-            } else {
-                base_where_clause.to_string()
+                ));
             }
-        } else {
-            base_where_clause.to_string()
-        };
+        }
 
         let query_str = format!(
             r#"
-            SELECT DISTINCT d.id, d.path, d.filename, d.extension, d.size, d.modified_at, d.title, d.tags, d.headings, d.content_excerpt, d.content_hash, d.indexed_at, d.version, d.is_latest, d.project_id
-            FROM documents d
-            LEFT JOIN index_entries ie ON d.id = ie.document_id
+            SELECT DISTINCT t.document_id, t.term
+            FROM term_index t
+            JOIN documents d ON d.id = t.document_id
             WHERE {}
-            ORDER BY d.modified_at DESC
-            LIMIT ? OFFSET ?
             "#,
-            final_where_clause
+            where_clause
         );
+        let mut query_builder = sqlx::query(&query_str).bind(exclude.to_string());
+        for term in terms {
+            query_builder = query_builder.bind(term);
+        }
+        if let Some(project_ids) = project_ids {
+            for project_id in project_ids {
+                query_builder = query_builder.bind(project_id.to_string());
+            }
+        }
 
-        let mut query_builder = sqlx::query(&query_str);
+        let rows = query_builder.fetch_all(&self.pool).await?;
+        let mut results = Vec::new();
+        for row in rows {
+            results.push((Uuid::parse_str(&row.get::<String, _>("document_id"))?, row.get("term")));
+        }
+        Ok(results)
+    }
 
-        // Bind the search query parameters
-        query_builder = query_builder
-            .bind(format!("%{}%", query))
-            .bind(format!("%{}%", query))
-            .bind(format!("%{}%", query))
-            .bind(format!("%{}%", query));
+    /// Latest documents other than `exclude` that are among `ids`, carry one
+    /// of `tags`, or whose name or content mentions one of `mentions`.
+    pub async fn find_related_documents(
+        &self,
+        exclude: &Uuid,
+        ids: &[Uuid],
+        tags: &[String],
+        mentions: &[String],
+        project_ids: Option<&[Uuid]>,
+    ) -> Result<Vec<Document>> {
+        let placeholders = |count: usize| vec!["?"; count].join(",");
+        let mut related = Vec::new();
+        if !ids.is_empty() {
+            related.push(format!("d.id IN ({})", placeholders(ids.len())));
+        }
+        if !tags.is_empty() {
+            related.push(format!(
+                "EXISTS (SELECT 1 FROM json_each(d.tags) WHERE lower(json_each.value) IN ({}))",
+                placeholders(tags.len())
+            ));
+        }
+        for _ in mentions {
+            related.push(
+                "lower(d.filename) = ? OR lower(d.filename) LIKE ? OR COALESCE(s.content, d.content_excerpt) LIKE ?".to_string(),
+            );
+        }
+        if related.is_empty() {
+            return Ok(Vec::new());
+        }
 
-        // Bind project IDs if specified
+        let mut where_clause = format!("d.is_latest = 1 AND d.id != ? AND ({})", related.join(" OR "));
         if let Some(project_ids) = project_ids {
             if !project_ids.is_empty() {
-                for project_id in project_ids {
-                    query_builder = query_builder.bind(project_id.to_string());
-                }
+                where_clause.push_str(&format!(" AND d.project_id IN ({})", placeholders(project_ids.len())));
             }
         }
 
-        // Bind limit and offset
-        query_builder = query_builder
-            .bind(limit as i64)
-            .bind(offset as i64);
-
-        let documents = query_builder.fetch_all(&self.pool).await?;
+        let query_str = format!(
+            r#"
+            SELECT d.id, d.path, d.filename, d.extension, d.size, d.modified_at, d.title, d.tags, d.headings, d.content_excerpt, d.content_hash, d.indexed_at, d.version, d.is_latest, d.project_id, d.language
+            FROM documents d
+            LEFT JOIN document_snapshots s ON s.document_id = d.id
+            WHERE {}
+            "#,
+            where_clause
+        );
+        let mut query_builder = sqlx::query(&query_str).bind(exclude.to_string());
+        for id in ids {
+            query_builder = query_builder.bind(id.to_string());
+        }
+        for tag in tags {
+            query_builder = query_builder.bind(tag.to_lowercase());
+        }
+        for mention in mentions {
+            let mention = mention.to_lowercase();
+            query_builder = query_builder
+                .bind(mention.clone())
+                .bind(format!("{}.%", mention))
+                .bind(format!("%{}%", mention));
+        }
+        if let Some(project_ids) = project_ids {
+            for project_id in project_ids {
+                query_builder = query_builder.bind(project_id.to_string());
+            }
+        }
 
+        let rows = query_builder.fetch_all(&self.pool).await?;
         let mut results = Vec::new();
-        for row in documents {
-            let tags: String = row.get("tags");
-            let headings: String = row.get("headings");
-            let project_id_str: Option<String> = row.get("project_id");
-            let project_id = project_id_str.and_then(|s| Uuid::parse_str(&s).ok());
-            
-            results.push(Document {
-                id: Uuid::parse_str(&row.get::<String, _>("id"))?,
-                path: PathBuf::from(row.get::<String, _>("path")),
-                filename: row.get("filename"),
-                extension: row.get("extension"),
-                size: row.get::<i64, _>("size") as u64,
-                modified_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("modified_at"))?.into(),
-                title: row.get("title"),
-                tags: serde_json::from_str(&tags).unwrap_or_default(),
-                headings: serde_json::from_str(&headings).unwrap_or_default(),
-                content_excerpt: row.get("content_excerpt"),
-                content_hash: row.get("content_hash"),
-                indexed_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("indexed_at"))?.into(),
-                version: row.get::<i64, _>("version") as u32,
-                is_latest: row.get::<i64, _>("is_latest") != 0,
-                project_id,
-            });
+        for row in rows {
+            results.push(document_from_row(&row)?);
         }
-
         Ok(results)
     }
 
-    pub async fn get_document_by_id(&self, id: &Uuid) -> Result<Option<Document>> {
-        let row = sqlx::query(
-            r#"
-            SELECT id, path, filename, extension, size, modified_at, title, tags, headings, content_excerpt, content_hash, indexed_at, version, is_latest, project_id
-            FROM documents
-            WHERE id = ?
-            "#,
-        )
-        .bind(id.to_string())
-        .fetch_optional(&self.pool)
-        .await?;
-
-        if let Some(row) = row {
-            let tags: String = row.get("tags");
-            let headings: String = row.get("headings");
-            let project_id_str: Option<String> = row.get("project_id");
-            let project_id = project_id_str.and_then(|s| Uuid::parse_str(&s).ok());
-            
-            Ok(Some(Document {
-                id: Uuid::parse_str(&row.get::<String, _>("id"))?,
-                path: PathBuf::from(row.get::<String, _>("path")),
-                filename: row.get("filename"),
-                extension: row.get("extension"),
-                size: row.get::<i64, _>("size") as u64,
-                modified_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("modified_at"))?.into(),
-                title: row.get("title"),
-                tags: serde_json::from_str(&tags).unwrap_or_default(),
-                headings: serde_json::from_str(&headings).unwrap_or_default(),
-                content_excerpt: row.get("content_excerpt"),
-                content_hash: row.get("content_hash"),
-                indexed_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("indexed_at"))?.into(),
-                version: row.get::<i64, _>("version") as u32,
-                is_latest: row.get::<i64, _>("is_latest") != 0,
-                project_id,
-            }))
+    pub async fn vocabulary_contains(&self, term: &str, accent_insensitive: bool) -> Result<bool> {
+        let query_str = if accent_insensitive {
+            "SELECT 1 FROM vocabulary WHERE folded_term = ? LIMIT 1"
         } else {
-            Ok(None)
-        }
+            "SELECT 1 FROM vocabulary WHERE term = ? LIMIT 1"
+        };
+        let term = if accent_insensitive { crate::analysis::fold_accents(term) } else { term.to_string() };
+        let row = sqlx::query(query_str)
+            .bind(term)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.is_some())
     }
 
-    pub async fn get_document_by_path(&self, path: &PathBuf) -> Result<Option<Document>> {
-        let row = sqlx::query(
+    /// Vocabulary of the latest document versions whose accent-folded form
+    /// starts with `first` and whose length is within `min_len..=max_len`
+    /// characters, with corpus-wide frequencies. The first letter is looked
+    /// up as a range of the folded term index rather than scanning every term.
+    pub async fn get_vocabulary_candidates(&self, first: char, min_len: usize, max_len: usize) -> Result<Vec<VocabularyEntry>> {
+        let first = crate::analysis::fold_accents(&first.to_string());
+        let Some(next) = first.chars().next().and_then(|c| char::from_u32(c as u32 + 1)) else {
+            return Ok(Vec::new());
+        };
+        let rows = sqlx::query(
             r#"
-            SELECT id, path, filename, extension, size, modified_at, title, tags, headings, content_excerpt, content_hash, indexed_at, version, is_latest, project_id
-            FROM documents
-            WHERE path = ?
+            SELECT v.term, SUM(v.frequency) AS frequency
+            FROM vocabulary v
+            JOIN documents d ON d.id = v.document_id
+            WHERE v.folded_term >= ? AND v.folded_term < ? AND d.is_latest = 1 AND length(v.term) BETWEEN ? AND ?
+            GROUP BY v.term
             "#,
         )
-        .bind(path.to_string_lossy())
-        .fetch_optional(&self.pool)
+        .bind(&first)
+        .bind(next.to_string())
+        .bind(min_len as i64)
+        .bind(max_len as i64)
+        .fetch_all(&self.pool)
         .await?;
 
-        if let Some(row) = row {
-            let tags: String = row.get("tags");
-            let headings: String = row.get("headings");
-            let project_id_str: Option<String> = row.get("project_id");
-            let project_id = project_id_str.and_then(|s| Uuid::parse_str(&s).ok());
-            
-            Ok(Some(Document {
-                id: Uuid::parse_str(&row.get::<String, _>("id"))?,
-                path: PathBuf::from(row.get::<String, _>("path")),
-                filename: row.get("filename"),
-                extension: row.get("extension"),
-                size: row.get::<i64, _>("size") as u64,
-                modified_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("modified_at"))?.into(),
-                title: row.get("title"),
-                tags: serde_json::from_str(&tags).unwrap_or_default(),
-                headings: serde_json::from_str(&headings).unwrap_or_default(),
-                content_excerpt: row.get("content_excerpt"),
-                content_hash: row.get("content_hash"),
-                indexed_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("indexed_at"))?.into(),
-                version: row.get::<i64, _>("version") as u32,
-                is_latest: row.get::<i64, _>("is_latest") != 0,
-                project_id,
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(rows
+            .into_iter()
+            .map(|row| VocabularyEntry {
+                term: row.get("term"),
+                frequency: row.get::<i64, _>("frequency") as u32,
+            })
+            .collect())
     }
 
-    pub async fn get_index_entries_for_document(&self, document_id: &Uuid) -> Result<Vec<IndexEntry>> {
-        let rows = sqlx::query(
+    /// Find documents containing any of the analyzed `terms`. When
+    /// `accent_insensitive` is set the terms are compared against the folded
+    /// column, so they must already be folded by the caller.
+    pub async fn search_documents_by_terms(
+        &self,
+        terms: &[String],
+        accent_insensitive: bool,
+        limit: u32,
+        offset: u32,
+        versions: Versions,
+        project_ids: Option<&[Uuid]>,
+    ) -> Result<Vec<Document>> {
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let term_column = if accent_insensitive { "t.folded_term" } else { "t.term" };
+        let mut where_clause = format!(
+            "{} IN ({})",
+            term_column,
+            terms.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+        );
+        if let Some(condition) = versions.condition() {
+            where_clause.push_str(&format!(" AND {}", condition));
+        }
+        if let Some(project_ids) = project_ids {
+            if !project_ids.is_empty() {
+                where_clause.push_str(&format!(
+                    " AND d.project_id IN ({})",
+                    project_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+                ));
+            }
+        }
+
+        let query_str = format!(
             r#"
-            SELECT id, document_id, chunk_id, chunk_text, positions
-            FROM index_entries
-            WHERE document_id = ?
-            ORDER BY chunk_id
+            SELECT DISTINCT d.id, d.path, d.filename, d.extension, d.size, d.modified_at, d.title, d.tags, d.headings, d.content_excerpt, d.content_hash, d.indexed_at, d.version, d.is_latest, d.project_id, d.language
+            FROM documents d
+            JOIN term_index t ON d.id = t.document_id
+            WHERE {}
+            ORDER BY d.modified_at DESC
+            LIMIT ? OFFSET ?
             "#,
-        )
-        .bind(document_id.to_string())
-        .fetch_all(&self.pool)
-        .await?;
+            where_clause
+        );
+
+        let mut query_builder = sqlx::query(&query_str);
+        for term in terms {
+            query_builder = query_builder.bind(term);
+        }
+        query_builder = versions.bind(query_builder);
+        if let Some(project_ids) = project_ids {
+            for project_id in project_ids {
+                query_builder = query_builder.bind(project_id.to_string());
+            }
+        }
+        query_builder = query_builder
+            .bind(limit as i64)
+            .bind(offset as i64);
 
+        let rows = query_builder.fetch_all(&self.pool).await?;
         let mut results = Vec::new();
         for row in rows {
-            let positions: String = row.get("positions");
-            
-            results.push(IndexEntry {
-                id: Uuid::parse_str(&row.get::<String, _>("id"))?,
-                document_id: Uuid::parse_str(&row.get::<String, _>("document_id"))?,
-                chunk_id: row.get::<i64, _>("chunk_id") as u32,
-                chunk_text: row.get("chunk_text"),
-                positions: serde_json::from_str(&positions).unwrap_or_default(),
-            });
+            results.push(document_from_row(&row)?);
         }
 
         Ok(results)
@@ -730,6 +2249,31 @@ impl Database {
             .execute(&self.pool)
             .await?;
 
+        sqlx::query("DELETE FROM term_index WHERE document_id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM vocabulary WHERE document_id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM suggestions WHERE document_id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM chunk_embeddings WHERE document_id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM summaries WHERE document_id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
         // Delete document
         sqlx::query("DELETE FROM documents WHERE id = ?")
             .bind(id.to_string())
@@ -739,10 +2283,10 @@ impl Database {
         Ok(())
     }
 
-    pub async fn get_document_versions(&self, path: &PathBuf) -> Result<Vec<Document>> {
+    pub async fn get_document_versions(&self, path: &Path) -> Result<Vec<Document>> {
         let rows = sqlx::query(
             r#"
-            SELECT id, path, filename, extension, size, modified_at, title, tags, headings, content_excerpt, content_hash, indexed_at, version, is_latest, project_id
+            SELECT id, path, filename, extension, size, modified_at, title, tags, headings, content_excerpt, content_hash, indexed_at, version, is_latest, project_id, language
             FROM documents
             WHERE path = ?
             ORDER BY version DESC
@@ -754,37 +2298,16 @@ impl Database {
 
         let mut results = Vec::new();
         for row in rows {
-            let tags: String = row.get("tags");
-            let headings: String = row.get("headings");
-            let project_id_str: Option<String> = row.get("project_id");
-            let project_id = project_id_str.and_then(|s| Uuid::parse_str(&s).ok());
-            
-            results.push(Document {
-                id: Uuid::parse_str(&row.get::<String, _>("id"))?,
-                path: PathBuf::from(row.get::<String, _>("path")),
-                filename: row.get("filename"),
-                extension: row.get("extension"),
-                size: row.get::<i64, _>("size") as u64,
-                modified_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("modified_at"))?.into(),
-                title: row.get("title"),
-                tags: serde_json::from_str(&tags).unwrap_or_default(),
-                headings: serde_json::from_str(&headings).unwrap_or_default(),
-                content_excerpt: row.get("content_excerpt"),
-                content_hash: row.get("content_hash"),
-                indexed_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("indexed_at"))?.into(),
-                version: row.get::<i64, _>("version") as u32,
-                is_latest: row.get::<i64, _>("is_latest") != 0,
-                project_id,
-            });
+            results.push(document_from_row(&row)?);
         }
 
         Ok(results)
     }
 
-    pub async fn get_latest_document_version(&self, path: &PathBuf) -> Result<Option<Document>> {
+    pub async fn get_latest_document_version(&self, path: &Path) -> Result<Option<Document>> {
         let row = sqlx::query(
             r#"
-            SELECT id, path, filename, extension, size, modified_at, title, tags, headings, content_excerpt, content_hash, indexed_at, version, is_latest, project_id
+            SELECT id, path, filename, extension, size, modified_at, title, tags, headings, content_excerpt, content_hash, indexed_at, version, is_latest, project_id, language
             FROM documents
             WHERE path = ? AND is_latest = 1
             "#,
@@ -794,34 +2317,13 @@ impl Database {
         .await?;
 
         if let Some(row) = row {
-            let tags: String = row.get("tags");
-            let headings: String = row.get("headings");
-            let project_id_str: Option<String> = row.get("project_id");
-            let project_id = project_id_str.and_then(|s| Uuid::parse_str(&s).ok());
-            
-            Ok(Some(Document {
-                id: Uuid::parse_str(&row.get::<String, _>("id"))?,
-                path: PathBuf::from(row.get::<String, _>("path")),
-                filename: row.get("filename"),
-                extension: row.get("extension"),
-                size: row.get::<i64, _>("size") as u64,
-                modified_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("modified_at"))?.into(),
-                title: row.get("title"),
-                tags: serde_json::from_str(&tags).unwrap_or_default(),
-                headings: serde_json::from_str(&headings).unwrap_or_default(),
-                content_excerpt: row.get("content_excerpt"),
-                content_hash: row.get("content_hash"),
-                indexed_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("indexed_at"))?.into(),
-                version: row.get::<i64, _>("version") as u32,
-                is_latest: row.get::<i64, _>("is_latest") != 0,
-                project_id,
-            }))
+            Ok(Some(document_from_row(&row)?))
         } else {
             Ok(None)
         }
     }
 
-    pub async fn mark_previous_versions_not_latest(&self, path: &PathBuf) -> Result<()> {
+    pub async fn mark_previous_versions_not_latest(&self, path: &Path) -> Result<()> {
         sqlx::query("UPDATE documents SET is_latest = 0 WHERE path = ?")
             .bind(path.to_string_lossy())
             .execute(&self.pool)
@@ -830,7 +2332,7 @@ impl Database {
         Ok(())
     }
 
-    pub async fn get_next_version_number(&self, path: &PathBuf) -> Result<u32> {
+    pub async fn get_next_version_number(&self, path: &Path) -> Result<u32> {
         let row = sqlx::query(
             "SELECT MAX(version) as max_version FROM documents WHERE path = ?"
         )
@@ -928,3 +2430,148 @@ impl Database {
         })
     }
 }
+
+fn document_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Document> {
+    let tags: String = row.get("tags");
+    let headings: String = row.get("headings");
+    let project_id_str: Option<String> = row.get("project_id");
+    let project_id = project_id_str.and_then(|s| Uuid::parse_str(&s).ok());
+
+    Ok(Document {
+        id: Uuid::parse_str(&row.get::<String, _>("id"))?,
+        path: PathBuf::from(row.get::<String, _>("path")),
+        filename: row.get("filename"),
+        extension: row.get("extension"),
+        size: row.get::<i64, _>("size") as u64,
+        modified_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("modified_at"))?.into(),
+        title: row.get("title"),
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+        headings: serde_json::from_str(&headings).unwrap_or_default(),
+        content_excerpt: row.get("content_excerpt"),
+        content_hash: row.get("content_hash"),
+        indexed_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("indexed_at"))?.into(),
+        version: row.get::<i64, _>("version") as u32,
+        is_latest: row.get::<i64, _>("is_latest") != 0,
+        project_id,
+        language: row.get("language"),
+    })
+}
+
+fn project_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Project> {
+    Ok(Project {
+        id: Uuid::parse_str(&row.get::<String, _>("id"))?,
+        name: row.get("name"),
+        description: row.get("description"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+fn normalize_suggestion(text: &str) -> String {
+    crate::analysis::fold_accents(&text.trim().to_lowercase())
+}
+
+fn synonym_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Synonym> {
+    let project_id: Option<String> = row.get("project_id");
+    let synonyms: String = row.get("synonyms");
+
+    Ok(Synonym {
+        id: Uuid::parse_str(&row.get::<String, _>("id"))?,
+        project_id: project_id.map(|id| Uuid::parse_str(&id)).transpose()?,
+        term: row.get("term"),
+        synonyms: serde_json::from_str(&synonyms)?,
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+fn prompt_template_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<PromptTemplate> {
+    let project_id: Option<String> = row.get("project_id");
+
+    Ok(PromptTemplate {
+        project_id: project_id.map(|id| Uuid::parse_str(&id)).transpose()?,
+        kind: row.get("kind"),
+        template: row.get("template"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+fn cached_summary_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<CachedSummary> {
+    Ok(CachedSummary {
+        document_id: Uuid::parse_str(&row.get::<String, _>("document_id"))?,
+        length: row.get("length"),
+        model: row.get("model"),
+        content_hash: row.get("content_hash"),
+        prompt_hash: row.get("prompt_hash"),
+        summary: row.get("summary"),
+        sections: row.get::<i64, _>("sections") as u32,
+        created_at: row.get("created_at"),
+    })
+}
+
+fn conversation_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Conversation> {
+    let project_ids: String = row.get("project_ids");
+
+    Ok(Conversation {
+        id: Uuid::parse_str(&row.get::<String, _>("id"))?,
+        title: row.get("title"),
+        project_ids: serde_json::from_str(&project_ids)?,
+        turn_count: row.get::<i64, _>("turn_count") as u32,
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+fn conversation_turn_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<ConversationTurn> {
+    let citations: String = row.get("citations");
+
+    Ok(ConversationTurn {
+        id: Uuid::parse_str(&row.get::<String, _>("id"))?,
+        conversation_id: Uuid::parse_str(&row.get::<String, _>("conversation_id"))?,
+        position: row.get::<i64, _>("position") as u32,
+        question: row.get("question"),
+        standalone_question: row.get("standalone_question"),
+        answer: row.get("answer"),
+        citations: serde_json::from_str(&citations)?,
+        created_at: row.get("created_at"),
+    })
+}
+
+fn saved_search_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<SavedSearch> {
+    let filters: Option<String> = row.get("filters");
+
+    Ok(SavedSearch {
+        id: Uuid::parse_str(&row.get::<String, _>("id"))?,
+        name: row.get("name"),
+        query: row.get("query"),
+        filters: filters.map(|f| serde_json::from_str(&f)).transpose()?,
+        mode: row.get("mode"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        last_run_at: row.get("last_run_at"),
+    })
+}
+
+fn index_entry_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<IndexEntry> {
+    let positions: String = row.get("positions");
+
+    Ok(IndexEntry {
+        id: Uuid::parse_str(&row.get::<String, _>("id"))?,
+        document_id: Uuid::parse_str(&row.get::<String, _>("document_id"))?,
+        chunk_id: row.get::<i64, _>("chunk_id") as u32,
+        chunk_text: row.get("chunk_text"),
+        positions: serde_json::from_str(&positions).unwrap_or_default(),
+        start_offset: row.get::<i64, _>("start_offset") as usize,
+    })
+}
+
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
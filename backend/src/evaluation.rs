@@ -0,0 +1,205 @@
+use anyhow::Result;
+use crate::search::SearchEngine;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+fn default_k() -> usize {
+    5
+}
+
+fn default_tolerance() -> f64 {
+    0.01
+}
+
+/// Queries with the documents a good ranking should return for them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldenSet {
+    /// Cutoff for precision, recall and nDCG
+    #[serde(default = "default_k")]
+    pub k: usize,
+    pub queries: Vec<GoldenQuery>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldenQuery {
+    pub query: String,
+    /// Filenames of the relevant documents
+    pub expected: Vec<String>,
+}
+
+impl GoldenSet {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// Ranking quality averaged over all golden queries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    pub precision_at_k: f64,
+    pub recall_at_k: f64,
+    pub mrr: f64,
+    pub ndcg_at_k: f64,
+}
+
+impl Metrics {
+    /// Metrics of a single ranking of filenames against the relevant set.
+    pub fn for_ranking(ranked: &[String], expected: &[String], k: usize) -> Self {
+        let relevant = |name: &String| expected.contains(name);
+        let top = &ranked[..ranked.len().min(k)];
+        let hits = top.iter().filter(|name| relevant(name)).count() as f64;
+
+        let precision_at_k = if k == 0 { 0.0 } else { hits / k as f64 };
+        let recall_at_k = if expected.is_empty() { 0.0 } else { hits / expected.len() as f64 };
+        let mrr = ranked
+            .iter()
+            .position(relevant)
+            .map(|rank| 1.0 / (rank as f64 + 1.0))
+            .unwrap_or(0.0);
+
+        // Binary relevance: each hit gains 1 / log2(rank + 1)
+        let dcg: f64 = top
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| relevant(name))
+            .map(|(rank, _)| 1.0 / (rank as f64 + 2.0).log2())
+            .sum();
+        let ideal: f64 = (0..expected.len().min(k)).map(|rank| 1.0 / (rank as f64 + 2.0).log2()).sum();
+        let ndcg_at_k = if ideal == 0.0 { 0.0 } else { dcg / ideal };
+
+        Self { precision_at_k, recall_at_k, mrr, ndcg_at_k }
+    }
+
+    fn mean(all: &[Metrics]) -> Self {
+        if all.is_empty() {
+            return Self::default();
+        }
+        let n = all.len() as f64;
+        Self {
+            precision_at_k: all.iter().map(|m| m.precision_at_k).sum::<f64>() / n,
+            recall_at_k: all.iter().map(|m| m.recall_at_k).sum::<f64>() / n,
+            mrr: all.iter().map(|m| m.mrr).sum::<f64>() / n,
+            ndcg_at_k: all.iter().map(|m| m.ndcg_at_k).sum::<f64>() / n,
+        }
+    }
+
+    fn named(&self) -> [(&'static str, f64); 4] {
+        [
+            ("precision@k", self.precision_at_k),
+            ("recall@k", self.recall_at_k),
+            ("mrr", self.mrr),
+            ("ndcg@k", self.ndcg_at_k),
+        ]
+    }
+}
+
+/// Per-query outcome, kept so regressions can be traced to their queries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryReport {
+    pub query: String,
+    pub search_ranking: Vec<String>,
+    pub search: Metrics,
+    pub qa_ranking: Vec<String>,
+    pub qa: Metrics,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvaluationReport {
+    pub k: usize,
+    /// Keyword search (`SearchEngine::search`)
+    pub search: Metrics,
+    /// Documents behind the chunks picked by the Q&A retriever
+    pub qa: Metrics,
+    pub queries: Vec<QueryReport>,
+}
+
+/// Stored metrics that later runs are compared against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub search: Metrics,
+    pub qa: Metrics,
+    /// Largest drop of a metric that is not reported as a regression
+    #[serde(default = "default_tolerance")]
+    pub tolerance: f64,
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn from_report(report: &EvaluationReport) -> Self {
+        Self { search: report.search, qa: report.qa, tolerance: default_tolerance() }
+    }
+
+    /// Every metric of `report` that differs from the baseline by more than
+    /// the tolerance, in either direction.
+    pub fn compare(&self, report: &EvaluationReport) -> Vec<MetricChange> {
+        let mut changes = Vec::new();
+        for (retriever, baseline, current) in [("search", &self.search, &report.search), ("qa", &self.qa, &report.qa)] {
+            for ((metric, before), (_, after)) in baseline.named().into_iter().zip(current.named()) {
+                if (after - before).abs() > self.tolerance {
+                    changes.push(MetricChange {
+                        retriever: retriever.to_string(),
+                        metric: metric.to_string(),
+                        baseline: before,
+                        current: after,
+                    });
+                }
+            }
+        }
+        changes
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricChange {
+    pub retriever: String,
+    pub metric: String,
+    pub baseline: f64,
+    pub current: f64,
+}
+
+impl MetricChange {
+    pub fn is_regression(&self) -> bool {
+        self.current < self.baseline
+    }
+}
+
+/// Run every golden query through keyword search and the Q&A retriever.
+pub async fn evaluate(engine: &SearchEngine, golden: &GoldenSet) -> Result<EvaluationReport> {
+    let k = golden.k;
+    let mut queries = Vec::new();
+
+    for golden_query in &golden.queries {
+        let results = engine.search(&golden_query.query, None, k as u32, 0, false).await?;
+        let search_ranking: Vec<String> = results.into_iter().map(|r| r.document.filename).collect();
+
+        let chunks = engine.get_relevant_chunks_for_qa(&golden_query.query, k as u32).await?;
+        let mut qa_ranking: Vec<String> = Vec::new();
+        for chunk in chunks {
+            if !qa_ranking.contains(&chunk.document.filename) {
+                qa_ranking.push(chunk.document.filename);
+            }
+        }
+
+        queries.push(QueryReport {
+            query: golden_query.query.clone(),
+            search: Metrics::for_ranking(&search_ranking, &golden_query.expected, k),
+            search_ranking,
+            qa: Metrics::for_ranking(&qa_ranking, &golden_query.expected, k),
+            qa_ranking,
+        });
+    }
+
+    let search: Vec<Metrics> = queries.iter().map(|q| q.search).collect();
+    let qa: Vec<Metrics> = queries.iter().map(|q| q.qa).collect();
+    Ok(EvaluationReport {
+        k,
+        search: Metrics::mean(&search),
+        qa: Metrics::mean(&qa),
+        queries,
+    })
+}
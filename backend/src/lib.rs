@@ -1,9 +1,18 @@
+pub mod analysis;
 pub mod database;
 pub mod config;
 pub mod server;
+pub mod llm;
 pub mod ollama;
+pub mod openai;
 pub mod corpus;
 pub mod search;
+pub mod evaluation;
+pub mod pagination;
+pub mod prompts;
+pub mod budget;
+pub mod resilience;
+pub mod citations;
 pub mod mcp;
 
 
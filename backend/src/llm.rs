@@ -0,0 +1,516 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use crate::config::Config;
+use crate::ollama::OllamaClient;
+use crate::openai::OpenAiClient;
+use crate::resilience::{is_retryable, is_transient, CircuitBreaker, CircuitStatus, ModelUnavailable, ResilienceConfig};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Environment variable holding the API key unless `api_key_env` names another.
+pub const DEFAULT_API_KEY_ENV: &str = "KB_LLM_API_KEY";
+
+/// Model and sampling settings of one generation. Providers ignore what
+/// they do not support; `num_ctx` only applies to Ollama, which sizes its
+/// context window per request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GenerationOptions {
+    /// Model to generate with instead of the provider's default
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// Context window to run the model with, in tokens
+    pub num_ctx: Option<usize>,
+}
+
+/// What a model is used for; each task can default to a different model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelTask {
+    Answer,
+    Summary,
+    Embedding,
+}
+
+impl ModelTask {
+    pub const ALL: [ModelTask; 3] = [ModelTask::Answer, ModelTask::Summary, ModelTask::Embedding];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModelTask::Answer => "answer",
+            ModelTask::Summary => "summary",
+            ModelTask::Embedding => "embedding",
+        }
+    }
+}
+
+/// A model installed on the provider. Fields the provider does not report
+/// are left out.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub family: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameter_size: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quantization: Option<String>,
+}
+
+/// Everything the provider tells about one model.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelDetails {
+    #[serde(flatten)]
+    pub info: ModelInfo,
+    /// Context window the model runs with, in tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<usize>,
+    /// Modelfile parameters, one `name value` pair per line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+/// Whether the model configured for a task is installed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelStatus {
+    pub task: ModelTask,
+    pub model: String,
+    pub available: bool,
+}
+
+/// Result of [`LlmClient::check_models`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCheck {
+    pub provider: String,
+    /// The provider answered the model list request
+    pub reachable: bool,
+    pub models: Vec<ModelStatus>,
+    /// Why the model list could not be read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Whether calls are currently failing fast
+    pub circuit: CircuitStatus,
+}
+
+impl ModelCheck {
+    /// The provider is up and has every configured model.
+    pub fn ok(&self) -> bool {
+        self.reachable && self.models.iter().all(|status| status.available)
+    }
+}
+
+/// A model server that generates text and embeddings.
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Short provider name for logs and health reports: `ollama` or `openai`.
+    fn name(&self) -> &'static str;
+
+    /// Model used for generation.
+    fn model(&self) -> &str;
+
+    /// Model used for embeddings.
+    fn embedding_model(&self) -> &str;
+
+    async fn generate(&self, prompt: &str, options: &GenerationOptions) -> Result<String>;
+
+    /// Like [`generate`](Self::generate), handing every token to `on_token`
+    /// as it arrives. Returns the full generated text.
+    async fn generate_stream(
+        &self,
+        prompt: &str,
+        options: &GenerationOptions,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<String>;
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>>;
+
+    /// Whether the server answers at all; errors reaching it count as `false`.
+    async fn health_check(&self) -> Result<bool>;
+
+    /// Models installed on the server.
+    async fn list_models(&self) -> Result<Vec<ModelInfo>>;
+
+    /// Details of `model`, or `None` if the server does not have it.
+    async fn show_model(&self, model: &str) -> Result<Option<ModelDetails>>;
+
+    /// The context window `model` runs with, if the server reports one.
+    async fn context_window(&self, model: &str) -> Result<Option<usize>> {
+        Ok(self.show_model(model).await?.and_then(|details| details.context_window))
+    }
+}
+
+/// Shared handle to the configured [`LlmProvider`], with the default model
+/// of each task and the generation settings used for answers, summaries and
+/// question rewriting. Calls are retried on transient failures and go
+/// through a circuit breaker shared by all clones; when the server stays
+/// down they fail with [`ModelUnavailable`].
+#[derive(Clone)]
+pub struct LlmClient {
+    provider: Arc<dyn LlmProvider>,
+    answer_model: Option<String>,
+    summary_model: Option<String>,
+    resilience: ResilienceConfig,
+    breaker: CircuitBreaker,
+}
+
+impl LlmClient {
+    pub fn new(provider: impl LlmProvider + 'static) -> Self {
+        Self::with_policy(Arc::new(provider), ResilienceConfig::default())
+    }
+
+    fn with_policy(provider: Arc<dyn LlmProvider>, resilience: ResilienceConfig) -> Self {
+        let breaker = CircuitBreaker::new(resilience.failure_threshold, Duration::from_secs(resilience.cooldown_secs));
+        Self { provider, answer_model: None, summary_model: None, resilience, breaker }
+    }
+
+    /// Retry and circuit breaker settings; timeouts are set on the provider.
+    /// The client gets a circuit breaker of its own.
+    pub fn with_resilience(self, resilience: ResilienceConfig) -> Self {
+        Self { answer_model: self.answer_model, summary_model: self.summary_model, ..Self::with_policy(self.provider, resilience) }
+    }
+
+    /// Answer and summarize with these models instead of the provider's
+    /// default model.
+    pub fn with_task_models(mut self, answer_model: Option<String>, summary_model: Option<String>) -> Self {
+        self.answer_model = answer_model;
+        self.summary_model = summary_model;
+        self
+    }
+
+    /// The model `task` uses unless a request names another.
+    pub fn model_for(&self, task: ModelTask) -> &str {
+        match task {
+            ModelTask::Answer => self.answer_model.as_deref().unwrap_or(self.provider.model()),
+            ModelTask::Summary => self.summary_model.as_deref().unwrap_or(self.provider.model()),
+            ModelTask::Embedding => self.provider.embedding_model(),
+        }
+    }
+
+    pub fn provider_name(&self) -> &'static str {
+        self.provider.name()
+    }
+
+    pub fn model(&self) -> &str {
+        self.provider.model()
+    }
+
+    pub fn embedding_model(&self) -> &str {
+        self.provider.embedding_model()
+    }
+
+    pub async fn generate(&self, prompt: &str, options: &GenerationOptions) -> Result<String> {
+        self.call(|| self.provider.generate(prompt, options)).await
+    }
+
+    /// Streams are only retried until the first token has been handed on.
+    pub async fn generate_stream<F: FnMut(&str) + Send>(&self, prompt: &str, options: &GenerationOptions, mut on_token: F) -> Result<String> {
+        self.admit()?;
+        let mut retry = 0;
+        loop {
+            let mut streamed = false;
+            let result = self
+                .provider
+                .generate_stream(prompt, options, &mut |token: &str| {
+                    streamed = true;
+                    on_token(token)
+                })
+                .await;
+            match result {
+                Ok(text) => {
+                    self.breaker.record_success();
+                    return Ok(text);
+                }
+                Err(e) => {
+                    let delay = self.retry_delay(e, retry, !streamed)?;
+                    tokio::time::sleep(delay).await;
+                    retry += 1;
+                }
+            }
+        }
+    }
+
+    /// Run `operation`, retrying transient failures with backoff.
+    async fn call<T, F, Fut>(&self, operation: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.admit()?;
+        let mut retry = 0;
+        loop {
+            match operation().await {
+                Ok(value) => {
+                    self.breaker.record_success();
+                    return Ok(value);
+                }
+                Err(e) => {
+                    let delay = self.retry_delay(e, retry, true)?;
+                    tokio::time::sleep(delay).await;
+                    retry += 1;
+                }
+            }
+        }
+    }
+
+    /// Fail fast while the circuit is open.
+    fn admit(&self) -> Result<()> {
+        self.breaker.check().map_err(|retry_in| {
+            let failures = self.breaker.status().consecutive_failures;
+            let reason = if retry_in.is_zero() {
+                format!("{} consecutive calls failed; another call is testing the server", failures)
+            } else {
+                format!("{} consecutive calls failed; trying again in {} s", failures, retry_in.as_secs_f64().ceil())
+            };
+            ModelUnavailable { provider: self.provider_name().to_string(), reason }.into()
+        })
+    }
+
+    /// The delay before retrying after `error`, or the error to give up
+    /// with. Errors the server answered with, such as a missing model, are
+    /// returned as they are and show the server is up; timeouts give up at
+    /// once.
+    fn retry_delay(&self, error: anyhow::Error, retry: u32, can_retry: bool) -> Result<Duration> {
+        if !is_transient(&error) {
+            self.breaker.record_success();
+            return Err(error);
+        }
+        let can_retry = can_retry && is_retryable(&error);
+        if can_retry && retry < self.resilience.max_retries {
+            tracing::debug!("Retrying {} call after: {}", self.provider_name(), error);
+            return Ok(self.resilience.backoff(retry));
+        }
+        self.breaker.record_failure();
+        let attempts = if can_retry { retry + 1 } else { 1 };
+        Err(ModelUnavailable {
+            provider: self.provider_name().to_string(),
+            reason: format!("{} (after {} attempt{})", error, attempts, if attempts == 1 { "" } else { "s" }),
+        }
+        .into())
+    }
+
+    /// State of the circuit breaker, for health reports.
+    pub fn circuit_status(&self) -> CircuitStatus {
+        self.breaker.status()
+    }
+
+    /// Generate a summary from a rendered summary prompt (see `prompts`)
+    /// with `model`, running it with a `num_ctx` token context window.
+    pub async fn summarize(&self, prompt: &str, model: &str, num_ctx: usize) -> Result<String> {
+        self.generate(prompt, &summary_options(model, num_ctx)).await
+    }
+
+    /// Streaming variant of [`summarize`](Self::summarize).
+    pub async fn summarize_stream<F: FnMut(&str) + Send>(&self, prompt: &str, model: &str, num_ctx: usize, on_token: F) -> Result<String> {
+        self.generate_stream(prompt, &summary_options(model, num_ctx), on_token).await
+    }
+
+    /// Generate an answer from a rendered answer prompt (see `prompts`)
+    /// with `model`, running it with a `num_ctx` token context window.
+    pub async fn answer_question(&self, prompt: &str, model: &str, num_ctx: usize) -> Result<String> {
+        self.generate(prompt, &answer_options(model, num_ctx)).await
+    }
+
+    /// Streaming variant of [`answer_question`](Self::answer_question).
+    pub async fn answer_question_stream<F: FnMut(&str) + Send>(&self, prompt: &str, model: &str, num_ctx: usize, on_token: F) -> Result<String> {
+        self.generate_stream(prompt, &answer_options(model, num_ctx), on_token).await
+    }
+
+    /// Rewrite a follow-up question from a condense prompt (see `prompts`)
+    /// with `model`.
+    pub async fn condense_question(&self, prompt: &str, model: &str) -> Result<String> {
+        self.generate(prompt, &condense_options(model)).await
+    }
+
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.call(|| self.provider.embed(text)).await
+    }
+
+    pub async fn health_check(&self) -> Result<bool> {
+        self.provider.health_check().await
+    }
+
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        self.call(|| self.provider.list_models()).await
+    }
+
+    pub async fn show_model(&self, model: &str) -> Result<Option<ModelDetails>> {
+        self.call(|| self.provider.show_model(model)).await
+    }
+
+    pub async fn context_window(&self, model: &str) -> Result<Option<usize>> {
+        self.call(|| self.provider.context_window(model)).await
+    }
+
+    /// Whether the provider is up and has the model of every task. Asks the
+    /// server once, without retrying or counting towards the circuit breaker,
+    /// so health checks answer quickly and can see the server come back.
+    pub async fn check_models(&self) -> ModelCheck {
+        let (installed, error) = match self.provider.list_models().await {
+            Ok(models) => (Some(models), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let models = ModelTask::ALL
+            .iter()
+            .map(|task| {
+                let model = self.model_for(*task).to_string();
+                let available = installed
+                    .as_ref()
+                    .is_some_and(|installed| installed.iter().any(|info| same_model(&info.name, &model)));
+                ModelStatus { task: *task, model, available }
+            })
+            .collect();
+        ModelCheck {
+            provider: self.provider_name().to_string(),
+            reachable: installed.is_some(),
+            models,
+            error,
+            circuit: self.circuit_status(),
+        }
+    }
+}
+
+/// Whether two model names refer to the same model; Ollama lists models
+/// with an explicit `:latest` tag that configs usually leave out.
+pub fn same_model(a: &str, b: &str) -> bool {
+    let untagged = |name: &str| name.strip_suffix(":latest").unwrap_or(name).to_string();
+    untagged(a) == untagged(b)
+}
+
+impl From<OllamaClient> for LlmClient {
+    fn from(client: OllamaClient) -> Self {
+        Self::new(client)
+    }
+}
+
+impl From<OpenAiClient> for LlmClient {
+    fn from(client: OpenAiClient) -> Self {
+        Self::new(client)
+    }
+}
+
+fn summary_options(model: &str, num_ctx: usize) -> GenerationOptions {
+    GenerationOptions {
+        model: Some(model.to_string()),
+        temperature: Some(0.3),
+        top_p: Some(0.9),
+        max_tokens: Some(1000),
+        num_ctx: Some(num_ctx),
+    }
+}
+
+fn answer_options(model: &str, num_ctx: usize) -> GenerationOptions {
+    GenerationOptions {
+        model: Some(model.to_string()),
+        temperature: Some(0.2),
+        top_p: Some(0.8),
+        max_tokens: Some(1200),
+        num_ctx: Some(num_ctx),
+    }
+}
+
+fn condense_options(model: &str) -> GenerationOptions {
+    GenerationOptions {
+        model: Some(model.to_string()),
+        temperature: Some(0.0),
+        top_p: Some(0.8),
+        max_tokens: Some(200),
+        num_ctx: None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    #[default]
+    Ollama,
+    /// Any server speaking the OpenAI chat completions API (llama.cpp, vLLM, ...)
+    OpenAi,
+}
+
+/// `[llm]` section of the config: which provider serves generation and
+/// embeddings. Unset fields fall back to `ollama_url`, `ollama_model` and
+/// `embedding_model`. The API key itself is never stored here: it is read
+/// from the environment variable named by `api_key_env` or from
+/// `api_key_file`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LlmConfig {
+    #[serde(default)]
+    pub provider: ProviderKind,
+    /// Server URL; for OpenAI-compatible servers including `/v1`
+    pub base_url: Option<String>,
+    /// Default model for generation
+    pub model: Option<String>,
+    /// Model for answers, if not the default one
+    pub answer_model: Option<String>,
+    /// Model for summaries, if not the default one
+    pub summary_model: Option<String>,
+    pub embedding_model: Option<String>,
+    /// Environment variable holding the API key (default `KB_LLM_API_KEY`)
+    pub api_key_env: Option<String>,
+    /// File whose first line is the API key
+    pub api_key_file: Option<PathBuf>,
+    /// Timeouts, retries and circuit breaker
+    #[serde(default)]
+    pub resilience: ResilienceConfig,
+}
+
+impl LlmConfig {
+    /// The API key from `api_key_env` or else `api_key_file`, if any.
+    pub fn api_key(&self) -> Result<Option<String>> {
+        let variable = self.api_key_env.as_deref().unwrap_or(DEFAULT_API_KEY_ENV);
+        if let Some(key) = std::env::var(variable).ok().filter(|key| !key.trim().is_empty()) {
+            return Ok(Some(key.trim().to_string()));
+        }
+        match &self.api_key_file {
+            Some(path) => {
+                let content = std::fs::read_to_string(path)
+                    .map_err(|e| anyhow::anyhow!("Could not read API key file {}: {}", path.display(), e))?;
+                Ok(content.lines().next().map(str::trim).filter(|key| !key.is_empty()).map(str::to_string))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// The provider configured in `config`.
+pub fn client_from_config(config: &Config) -> Result<LlmClient> {
+    let llm = &config.llm;
+    let model = llm.model.clone().unwrap_or_else(|| config.ollama_model.clone());
+    let embedding_model = llm.embedding_model.clone().unwrap_or_else(|| config.embedding_model.clone());
+
+    let client: LlmClient = match llm.provider {
+        ProviderKind::Ollama => {
+            let base_url = llm.base_url.clone().unwrap_or_else(|| config.ollama_url.clone());
+            OllamaClient::new(base_url, model)
+                .with_embedding_model(embedding_model)
+                .with_timeouts(llm.resilience.connect_timeout(), llm.resilience.timeout(), llm.resilience.stream_timeout())
+                .into()
+        }
+        ProviderKind::OpenAi => {
+            let base_url = llm
+                .base_url
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("llm.base_url is required for the openai provider"))?;
+            let mut client = OpenAiClient::new(base_url, model)
+                .with_embedding_model(embedding_model)
+                .with_timeouts(llm.resilience.connect_timeout(), llm.resilience.timeout(), llm.resilience.stream_timeout());
+            if let Some(key) = llm.api_key()? {
+                client = client.with_api_key(key);
+            }
+            client.into()
+        }
+    };
+    Ok(client
+        .with_task_models(llm.answer_model.clone(), llm.summary_model.clone())
+        .with_resilience(llm.resilience.clone()))
+}
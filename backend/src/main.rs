@@ -1,12 +1,24 @@
+// The modules are also built as the library crate; the server binary
+// only uses part of them
+#![allow(dead_code)]
+
 use anyhow::Result;
 use tracing::{info, Level};
-use tracing_subscriber;
 
+mod analysis;
+mod budget;
+mod citations;
 mod config;
 mod corpus;
 mod database;
+mod evaluation;
+mod llm;
 mod mcp;
 mod ollama;
+mod openai;
+mod pagination;
+mod prompts;
+mod resilience;
 mod search;
 mod server;
 
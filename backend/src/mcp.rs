@@ -1,10 +1,43 @@
 use anyhow::Result;
-use crate::database::Database;
-use crate::ollama::{OllamaClient, SummaryLength};
-use crate::search::SearchEngine;
+use chrono::{DateTime, Utc};
+use crate::budget::{self, BudgetReport, ContextBudgeter, ContextConfig};
+use crate::citations::{self, CitationCheck};
+use crate::database::{CachedSummary, ConversationTurn, Database, Document, SavedSearch};
+use crate::llm::{LlmClient, ModelTask};
+use crate::ollama::SummaryLength;
+use crate::pagination::{Cursor, PageStart, MAX_PAGE_SIZE};
+use crate::prompts::{self, PromptConfig, PromptKind, PromptLibrary, PromptVariables, ResolvedTemplate};
+use crate::resilience::is_model_unavailable;
+use crate::search::{retrieval_confidence, HighlightFormat, RetrievedChunk, SearchEngine, SearchMode, SearchOptions, DEFAULT_MAX_CHUNKS_PER_DOCUMENT};
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
 use uuid::Uuid;
 
+/// Largest number of chunks `answer_question` retrieves.
+const MAX_TOP_K: u32 = 20;
+
+/// Largest number of neighbours `find_similar` returns.
+const MAX_SIMILAR: u32 = 50;
+
+/// Largest number of completions `suggest` returns.
+const MAX_SUGGESTIONS: u32 = 50;
+
+/// Earlier turns of a conversation used to rewrite a follow-up question.
+const CONVERSATION_HISTORY_TURNS: u32 = 3;
+
+/// Largest number of conversations `list_conversations` returns.
+const MAX_CONVERSATIONS: u32 = 100;
+
+/// Rounds of merging section summaries before what is left is cut to fit.
+const MAX_REDUCE_LEVELS: usize = 4;
+
+/// Largest number of documents `summarize_collection` covers.
+const MAX_COLLECTION_DOCUMENTS: u32 = 50;
+
+/// Length of the document summaries a collection overview is merged from;
+/// short ones leave room for more documents in the prompt.
+const COLLECTION_DOCUMENT_LENGTH: (SummaryLength, &str) = (SummaryLength::Short, "short");
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MCPTool {
     pub name: String,
@@ -25,23 +58,211 @@ pub struct MCPResponse {
     pub error: Option<String>,
 }
 
+/// One event of a streamed `answer_question`, `summarize_note` or
+/// `summarize_collection` call. The citations come first, then the generated
+/// tokens, then either `done` with the rest of the non-streaming response or
+/// `error`. For answers, `done` also carries the verified answer and
+/// citations, since markers pointing at no chunk have already been streamed
+/// by then. Summaries of long documents report `progress` through their
+/// sections before the tokens, collections through their documents before
+/// the citations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Citations { citations: Vec<serde_json::Value> },
+    /// `stage` is `map` while sections are summarized, `reduce` while
+    /// their summaries are merged and `documents` while the documents of a
+    /// collection are summarized
+    Progress { stage: String, completed: usize, total: usize },
+    Token { text: String },
+    Done { metadata: serde_json::Value },
+    Error { message: String },
+}
+
+impl StreamEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamEvent::Citations { .. } => "citations",
+            StreamEvent::Progress { .. } => "progress",
+            StreamEvent::Token { .. } => "token",
+            StreamEvent::Done { .. } => "done",
+            StreamEvent::Error { .. } => "error",
+        }
+    }
+}
+
+/// Retrieved context for answering a question.
+struct AnswerContext {
+    /// The question used for retrieval and the prompt; within a
+    /// conversation, the follow-up rewritten to stand on its own
+    question: String,
+    /// The question as asked
+    asked: String,
+    conversation_id: Option<Uuid>,
+    chunks: Vec<RetrievedChunk>,
+    citations: Vec<serde_json::Value>,
+    project_ids: Vec<Uuid>,
+    as_of: Option<DateTime<Utc>>,
+}
+
+/// A document to summarize with its full content.
+struct SummarySource {
+    document: Document,
+    content: String,
+    length: SummaryLength,
+    length_name: String,
+}
+
+/// The documents selected for `summarize_collection` and how: `kind` is
+/// `project`, `folder`, `tag` or `saved_search`.
+struct Collection {
+    kind: &'static str,
+    value: String,
+    documents: Vec<Document>,
+}
+
+/// A finished summary and how it was produced.
+struct SummaryOutcome {
+    summary: String,
+    rendered: RenderedPrompt,
+    cached: bool,
+    sections: u32,
+}
+
+/// A prompt ready for the model, with the model, template and language
+/// used and how its context was fitted into the model's context window.
+#[derive(Debug, Clone, Serialize)]
+struct RenderedPrompt {
+    prompt: String,
+    model: String,
+    language: String,
+    template: ResolvedTemplate,
+    budget: BudgetReport,
+}
+
+impl AnswerContext {
+    fn prompt_context(&self) -> String {
+        self.chunks
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| format!("{}{}", chunk_label(i, &chunk.document.filename), chunk.entry.chunk_text))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Keep the chunks that fit in `available` tokens, trimming the last
+    /// one if needed, and renumber the citations to match.
+    fn fit_to_budget(&mut self, available: usize, report: &mut BudgetReport) {
+        let candidates: Vec<(usize, &str)> = self
+            .chunks
+            .iter()
+            .enumerate()
+            // Labels are numbered by rank, which can only shrink as chunks are dropped
+            .map(|(i, chunk)| (budget::estimate_tokens(&chunk_label(i, &chunk.document.filename)) + 1, chunk.entry.chunk_text.as_str()))
+            .collect();
+        let kept = budget::fit_chunks(&candidates, available, report);
+
+        let mut chunks = Vec::with_capacity(kept.len());
+        let mut citations = Vec::with_capacity(kept.len());
+        for (i, text) in kept {
+            let mut chunk = self.chunks[i].clone();
+            let mut citation = self.citations[i].clone();
+            if text != chunk.entry.chunk_text {
+                citation["excerpt"] = serde_json::json!(text);
+                citation["trimmed"] = serde_json::json!(true);
+                chunk.entry.chunk_text = text;
+            }
+            citation["marker"] = serde_json::json!(citations.len() + 1);
+            chunks.push(chunk);
+            citations.push(citation);
+        }
+        self.chunks = chunks;
+        self.citations = citations;
+    }
+
+    /// Verify the `[n]` markers of a generated answer against the numbered
+    /// chunks, flag each citation as used or not and return the answer
+    /// without markers pointing at no chunk.
+    fn check_citations(&mut self, answer: String) -> (String, Option<CitationCheck>) {
+        if self.chunks.is_empty() {
+            return (answer, None);
+        }
+        let (answer, check) = citations::verify_citations(&answer, self.chunks.len());
+        for (i, citation) in self.citations.iter_mut().enumerate() {
+            citation["used"] = serde_json::json!(check.used.contains(&(i + 1)));
+        }
+        (answer, Some(check))
+    }
+
+    fn metadata(&self) -> serde_json::Value {
+        let confidence_score = retrieval_confidence(&self.chunks);
+        let confidence = if confidence_score >= 0.6 { "high" } else if confidence_score >= 0.3 { "medium" } else { "low" };
+        serde_json::json!({
+            "confidence": confidence,
+            "confidence_score": confidence_score,
+            "context_chunks": self.chunks.len(),
+            "as_of": self.as_of
+        })
+    }
+}
+
+/// How chunk `index` is introduced in the answer prompt; answers cite it by
+/// this number.
+fn chunk_label(index: usize, filename: &str) -> String {
+    format!("[{}] ({}) ", index + 1, filename)
+}
+
+/// Answer used when retrieval finds nothing to base an answer on
+const NO_CONTEXT_ANSWER: &str = "I couldn't find any relevant information in the knowledge base to answer your question.";
+
+/// Answer given with the retrieved passages when the model server is down.
+const MODEL_UNAVAILABLE_ANSWER: &str = "The language model is unavailable right now, so no answer could be written. The passages below matched the question.";
+
+/// Overview of a collection without any readable document
+const EMPTY_COLLECTION_OVERVIEW: &str = "The collection does not contain any documents to summarize.";
+
 #[derive(Clone)]
 pub struct MCPServer {
     db: Database,
     search_engine: SearchEngine,
-    ollama_client: OllamaClient,
+    llm: LlmClient,
+    prompts: PromptLibrary,
+    budget: ContextBudgeter,
 }
 
 impl MCPServer {
-    pub fn new(db: Database, ollama_client: OllamaClient) -> Self {
-        let search_engine = SearchEngine::new(db.clone());
+    pub fn new(db: Database, llm: impl Into<LlmClient>) -> Self {
+        let llm = llm.into();
+        let search_engine = SearchEngine::new(db.clone()).with_embeddings(llm.clone());
+        let prompts = PromptLibrary::new(db.clone(), PromptConfig::default());
+        let budget = ContextBudgeter::new(llm.clone(), ContextConfig::default());
         Self {
             db,
             search_engine,
-            ollama_client,
+            llm,
+            prompts,
+            budget,
         }
     }
 
+    /// Use the configured prompt templates and answer language.
+    pub fn with_prompt_config(mut self, config: PromptConfig) -> Self {
+        self.prompts = PromptLibrary::new(self.db.clone(), config);
+        self
+    }
+
+    /// Use the configured context windows and answer reserve.
+    pub fn with_context_config(mut self, config: ContextConfig) -> Self {
+        self.budget = ContextBudgeter::new(self.llm.clone(), config);
+        self
+    }
+
+    /// Enable or disable accent-insensitive matching for search and Q&A.
+    pub fn with_accent_folding(mut self, enabled: bool) -> Self {
+        self.search_engine = self.search_engine.with_accent_folding(enabled);
+        self
+    }
+
     pub fn get_available_tools(&self) -> Vec<MCPTool> {
         vec![
             MCPTool {
@@ -50,8 +271,9 @@ impl MCPServer {
                 input_schema: serde_json::json!({
                     "type": "object",
                     "properties": {
-                        "limit": {"type": "integer", "minimum": 1, "maximum": 100, "default": 20},
-                        "offset": {"type": "integer", "minimum": 0, "default": 0}
+                        "limit": {"type": "integer", "minimum": 1, "maximum": MAX_PAGE_SIZE, "default": 20},
+                        "offset": {"type": "integer", "minimum": 0, "default": 0},
+                        "cursor": {"type": "string", "description": "next_cursor of the previous page; cannot be combined with offset"}
                     }
                 }),
             },
@@ -68,14 +290,20 @@ impl MCPServer {
             },
             MCPTool {
                 name: "search_notes".to_string(),
-                description: "Search notes by keywords with filters and pagination".to_string(),
+                description: "Search notes by keywords or meaning with filters and pagination".to_string(),
                 input_schema: serde_json::json!({
                     "type": "object",
                     "properties": {
                         "query": {"type": "string"},
-                        "limit": {"type": "integer", "minimum": 1, "maximum": 100, "default": 20},
+                        "mode": {"type": "string", "enum": ["keyword", "semantic"], "default": "keyword"},
+                        "limit": {"type": "integer", "minimum": 1, "maximum": MAX_PAGE_SIZE, "default": 20},
                         "offset": {"type": "integer", "minimum": 0, "default": 0},
+                        "cursor": {"type": "string", "description": "next_cursor of the previous page; cannot be combined with offset"},
                         "include_historical": {"type": "boolean", "default": false},
+                        "as_of": {"type": "string", "format": "date-time", "description": "Search the notes as they were at this RFC 3339 timestamp"},
+                        "include_facets": {"type": "boolean", "default": false},
+                        "highlight": {"type": "string", "enum": ["markdown", "html", "raw"], "default": "markdown"},
+                        "explain": {"type": "boolean", "default": false, "description": "Include a score breakdown with every result"},
                         "filters": {
                             "type": "object",
                             "properties": {
@@ -91,16 +319,38 @@ impl MCPServer {
             },
             MCPTool {
                 name: "summarize_note".to_string(),
-                description: "Generate a summary of a note in short, medium, or long format".to_string(),
+                description: "Generate a summary of a note in short, medium, or long format; long notes are summarized section by section and results are cached".to_string(),
                 input_schema: serde_json::json!({
                     "type": "object",
                     "properties": {
                         "id": {"type": "string", "format": "uuid"},
-                        "length": {"type": "string", "enum": ["short", "medium", "long"], "default": "medium"}
+                        "length": {"type": "string", "enum": ["short", "medium", "long"], "default": "medium"},
+                        "language": {"type": "string", "description": "Language name for {language} in the template, or \"auto\" to follow the note"},
+                        "model": {"type": "string", "description": "Model to summarize with instead of the configured summary model"},
+                        "refresh": {"type": "boolean", "default": false, "description": "Regenerate even if a summary of the unchanged note is cached"}
                     },
                     "required": ["id"]
                 }),
             },
+            MCPTool {
+                name: "summarize_collection".to_string(),
+                description: "Summarize a project, folder, tag or saved search: each document is summarized (or its cached summary reused), then the summaries are merged into one overview citing documents by number".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project_id": {"type": "string", "format": "uuid"},
+                        "folder": {"type": "string"},
+                        "tag": {"type": "string"},
+                        "saved_search": {"type": "string", "description": "Saved search id or name"},
+                        "length": {"type": "string", "enum": ["short", "medium", "long"], "default": "medium"},
+                        "limit": {"type": "integer", "minimum": 1, "maximum": MAX_COLLECTION_DOCUMENTS, "default": 20, "description": "Most recently modified documents to include"},
+                        "language": {"type": "string", "description": "Language name, or \"auto\" to follow the documents"},
+                        "model": {"type": "string", "description": "Model to summarize with instead of the configured summary model"},
+                        "refresh": {"type": "boolean", "default": false, "description": "Regenerate the document summaries even if cached"}
+                    },
+                    "description": "Exactly one of project_id, folder, tag or saved_search selects the documents"
+                }),
+            },
             MCPTool {
                 name: "answer_question".to_string(),
                 description: "Answer a question based on the knowledge base with citations".to_string(),
@@ -108,12 +358,259 @@ impl MCPServer {
                     "type": "object",
                     "properties": {
                         "question": {"type": "string"},
-                        "top_k": {"type": "integer", "minimum": 1, "maximum": 20, "default": 5},
-                        "project_ids": {"type": "array", "items": {"type": "string", "format": "uuid"}}
+                        "top_k": {"type": "integer", "minimum": 1, "maximum": MAX_TOP_K, "default": 5},
+                        "max_chunks_per_document": {"type": "integer", "minimum": 1, "maximum": MAX_TOP_K, "default": 2},
+                        "project_ids": {"type": "array", "items": {"type": "string", "format": "uuid"}},
+                        "as_of": {"type": "string", "format": "date-time", "description": "Answer from the notes as they were at this RFC 3339 timestamp"},
+                        "language": {"type": "string", "description": "Language to answer in, or \"auto\" to follow the question; defaults to the configured language"},
+                        "model": {"type": "string", "description": "Model to answer with instead of the configured answer model"},
+                        "conversation_id": {"type": "string", "format": "uuid", "description": "Answer as the next turn of this conversation; follow-ups are rewritten from the recent turns before retrieval"}
                     },
                     "required": ["question"]
                 }),
             },
+            MCPTool {
+                name: "start_conversation".to_string(),
+                description: "Start a conversation for multi-turn Q&A; pass its id as conversation_id to answer_question".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "title": {"type": "string"},
+                        "project_ids": {"type": "array", "items": {"type": "string", "format": "uuid"}, "description": "Projects questions in this conversation are answered from unless a turn names others"}
+                    }
+                }),
+            },
+            MCPTool {
+                name: "list_conversations".to_string(),
+                description: "List conversations, most recently active first".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "limit": {"type": "integer", "minimum": 1, "maximum": MAX_CONVERSATIONS, "default": 20}
+                    }
+                }),
+            },
+            MCPTool {
+                name: "get_conversation".to_string(),
+                description: "Get a conversation with its turns and the citations each answer used".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "string", "format": "uuid"}
+                    },
+                    "required": ["id"]
+                }),
+            },
+            MCPTool {
+                name: "delete_conversation".to_string(),
+                description: "Delete a conversation and its turns".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "string", "format": "uuid"}
+                    },
+                    "required": ["id"]
+                }),
+            },
+            MCPTool {
+                name: "find_similar".to_string(),
+                description: "Find the notes most similar to a given note, with the reasons they are related".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "string", "format": "uuid"},
+                        "limit": {"type": "integer", "minimum": 1, "maximum": MAX_SIMILAR, "default": 10},
+                        "project_ids": {"type": "array", "items": {"type": "string", "format": "uuid"}}
+                    },
+                    "required": ["id"]
+                }),
+            },
+            MCPTool {
+                name: "suggest".to_string(),
+                description: "Complete search box input from indexed titles, headings, tags and frequent terms".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "prefix": {"type": "string"},
+                        "limit": {"type": "integer", "minimum": 1, "maximum": MAX_SUGGESTIONS, "default": 8},
+                        "project_ids": {"type": "array", "items": {"type": "string", "format": "uuid"}}
+                    },
+                    "required": ["prefix"]
+                }),
+            },
+            MCPTool {
+                name: "save_search".to_string(),
+                description: "Save a query and its filters under a name so it can be rerun like a folder".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "query": {"type": "string"},
+                        "mode": {"type": "string", "enum": ["keyword", "semantic"], "default": "keyword"},
+                        "filters": {
+                            "type": "object",
+                            "properties": {
+                                "file_types": {"type": "array", "items": {"type": "string"}},
+                                "folders": {"type": "array", "items": {"type": "string"}},
+                                "tags": {"type": "array", "items": {"type": "string"}},
+                                "project_ids": {"type": "array", "items": {"type": "string", "format": "uuid"}}
+                            }
+                        }
+                    },
+                    "required": ["name", "query"]
+                }),
+            },
+            MCPTool {
+                name: "list_saved_searches".to_string(),
+                description: "List saved searches, optionally with their current number of matches".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "counts": {"type": "boolean", "default": false, "description": "Run every saved search to count its matches"}
+                    }
+                }),
+            },
+            MCPTool {
+                name: "run_saved_search".to_string(),
+                description: "Run a saved search, optionally returning only documents indexed since its last run".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "string", "description": "Saved search id or name"},
+                        "new_only": {"type": "boolean", "default": false},
+                        "since": {"type": "string", "format": "date-time", "description": "Overrides the last run time; needed to page a new_only run by offset"},
+                        "limit": {"type": "integer", "minimum": 1, "maximum": MAX_PAGE_SIZE, "default": 20},
+                        "offset": {"type": "integer", "minimum": 0, "default": 0},
+                        "cursor": {"type": "string"}
+                    },
+                    "required": ["id"]
+                }),
+            },
+            MCPTool {
+                name: "delete_saved_search".to_string(),
+                description: "Delete a saved search".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "string", "description": "Saved search id or name"}
+                    },
+                    "required": ["id"]
+                }),
+            },
+            MCPTool {
+                name: "list_synonyms".to_string(),
+                description: "List synonym and abbreviation dictionary entries, optionally those applying to one project".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project_id": {"type": "string", "format": "uuid", "description": "Global entries plus this project's; omit for all entries"}
+                    }
+                }),
+            },
+            MCPTool {
+                name: "set_synonyms".to_string(),
+                description: "Define the synonyms or expansions of a term, e.g. k8s => kubernetes, for one project or globally".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "term": {"type": "string"},
+                        "synonyms": {"type": "array", "items": {"type": "string"}, "minItems": 1},
+                        "project_id": {"type": "string", "format": "uuid", "description": "Omit for a global entry"}
+                    },
+                    "required": ["term", "synonyms"]
+                }),
+            },
+            MCPTool {
+                name: "delete_synonym".to_string(),
+                description: "Delete a synonym dictionary entry".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "string", "format": "uuid"}
+                    },
+                    "required": ["id"]
+                }),
+            },
+            MCPTool {
+                name: "list_prompt_templates".to_string(),
+                description: "Show the answer and summary prompt templates in effect, with their source, and all stored overrides".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project_id": {"type": "string", "format": "uuid", "description": "Resolve the templates for this project"}
+                    }
+                }),
+            },
+            MCPTool {
+                name: "set_prompt_template".to_string(),
+                description: "Override the answer or summary prompt template, for one project or globally. Templates may use {question}, {context}, {language} and {length}".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "kind": {"type": "string", "enum": ["answer", "summary"]},
+                        "template": {"type": "string"},
+                        "project_id": {"type": "string", "format": "uuid", "description": "Omit for a global override"}
+                    },
+                    "required": ["kind", "template"]
+                }),
+            },
+            MCPTool {
+                name: "reset_prompt_template".to_string(),
+                description: "Remove a prompt template override, falling back to the global, configured or built-in template".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "kind": {"type": "string", "enum": ["answer", "summary"]},
+                        "project_id": {"type": "string", "format": "uuid"}
+                    },
+                    "required": ["kind"]
+                }),
+            },
+            MCPTool {
+                name: "preview_prompt".to_string(),
+                description: "Render the prompt an answer_question or summarize_note call would send to the model, without calling it".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "kind": {"type": "string", "enum": ["answer", "summary"]},
+                        "question": {"type": "string", "description": "For answer prompts"},
+                        "id": {"type": "string", "format": "uuid", "description": "Note to summarize, for summary prompts"},
+                        "length": {"type": "string", "enum": ["short", "medium", "long"], "default": "medium"},
+                        "language": {"type": "string"},
+                        "model": {"type": "string", "description": "Model whose context window the prompt is fitted to"},
+                        "top_k": {"type": "integer", "minimum": 1, "maximum": MAX_TOP_K, "default": 5},
+                        "project_ids": {"type": "array", "items": {"type": "string", "format": "uuid"}}
+                    },
+                    "required": ["kind"]
+                }),
+            },
+            MCPTool {
+                name: "list_models".to_string(),
+                description: "List the models installed on the model provider".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
+            MCPTool {
+                name: "show_model".to_string(),
+                description: "Show details of an installed model: family, size, quantization, context window and parameters".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"}
+                    },
+                    "required": ["name"]
+                }),
+            },
+            MCPTool {
+                name: "check_models".to_string(),
+                description: "Check that the models configured for answers, summaries and embeddings are installed".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
             MCPTool {
                 name: "get_document_versions".to_string(),
                 description: "Get all versions of a document".to_string(),
@@ -177,7 +674,28 @@ impl MCPServer {
             "read_note" => self.handle_read_note(request.arguments).await,
             "search_notes" => self.handle_search_notes(request.arguments).await,
             "summarize_note" => self.handle_summarize_note(request.arguments).await,
+            "summarize_collection" => self.handle_summarize_collection(request.arguments).await,
             "answer_question" => self.handle_answer_question(request.arguments).await,
+            "start_conversation" => self.handle_start_conversation(request.arguments).await,
+            "list_conversations" => self.handle_list_conversations(request.arguments).await,
+            "get_conversation" => self.handle_get_conversation(request.arguments).await,
+            "delete_conversation" => self.handle_delete_conversation(request.arguments).await,
+            "find_similar" => self.handle_find_similar(request.arguments).await,
+            "suggest" => self.handle_suggest(request.arguments).await,
+            "save_search" => self.handle_save_search(request.arguments).await,
+            "list_saved_searches" => self.handle_list_saved_searches(request.arguments).await,
+            "run_saved_search" => self.handle_run_saved_search(request.arguments).await,
+            "delete_saved_search" => self.handle_delete_saved_search(request.arguments).await,
+            "list_synonyms" => self.handle_list_synonyms(request.arguments).await,
+            "set_synonyms" => self.handle_set_synonyms(request.arguments).await,
+            "delete_synonym" => self.handle_delete_synonym(request.arguments).await,
+            "list_prompt_templates" => self.handle_list_prompt_templates(request.arguments).await,
+            "set_prompt_template" => self.handle_set_prompt_template(request.arguments).await,
+            "reset_prompt_template" => self.handle_reset_prompt_template(request.arguments).await,
+            "preview_prompt" => self.handle_preview_prompt(request.arguments).await,
+            "list_models" => self.handle_list_models(request.arguments).await,
+            "show_model" => self.handle_show_model(request.arguments).await,
+            "check_models" => self.handle_check_models(request.arguments).await,
             "get_document_versions" => self.handle_get_document_versions(request.arguments).await,
             "compare_versions" => self.handle_compare_versions(request.arguments).await,
             "get_retention_policy" => self.handle_get_retention_policy(request.arguments).await,
@@ -191,17 +709,896 @@ impl MCPServer {
         }
     }
 
-    async fn handle_list_notes(&self, args: serde_json::Value) -> Result<MCPResponse> {
-        let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(20) as u32;
-        let offset = args.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+    async fn handle_list_notes(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let limit = parse_bounded_u32(&args, "limit", 20, 1, MAX_PAGE_SIZE)?;
+        let start = parse_page_start(&args)?;
+
+        // Fetch one extra document to learn whether another page follows
+        let mut documents = match &start {
+            PageStart::Offset(offset) => self.db.list_documents_page(limit + 1, *offset, None).await?,
+            PageStart::Cursor(Cursor::List { modified_at, id }) => {
+                self.db.list_documents_page(limit + 1, 0, Some((modified_at.as_str(), id))).await?
+            }
+            PageStart::Cursor(_) => return Err(anyhow::anyhow!("Cursor does not belong to list_notes")),
+        };
+        let has_more = documents.len() > limit as usize;
+        documents.truncate(limit as usize);
+        let total = self.db.count_latest_documents().await?;
+
+        let next_cursor = match documents.last() {
+            Some(last) if has_more => Some(
+                Cursor::List {
+                    modified_at: last.modified_at.to_rfc3339(),
+                    id: last.id,
+                }
+                .encode(),
+            ),
+            _ => None,
+        };
+
+        let response_data = serde_json::json!({
+            "notes": documents,
+            "total": total,
+            "limit": limit,
+            "offset": page_offset(&start),
+            "next_cursor": next_cursor
+        });
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(response_data),
+            error: None,
+        })
+    }
+
+    async fn handle_read_note(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let id_str = args.get("id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing required field: id"))?;
+
+        let id = Uuid::parse_str(id_str)?;
+        
+        if let Some(document) = self.db.get_document_by_id(&id).await? {
+            // Read the full file content
+            let content = std::fs::read_to_string(&document.path)?;
+            
+            let response_data = serde_json::json!({
+                "document": document,
+                "content": content
+            });
+
+            Ok(MCPResponse {
+                success: true,
+                data: Some(response_data),
+                error: None,
+            })
+        } else {
+            Ok(MCPResponse {
+                success: false,
+                data: None,
+                error: Some("Document not found".to_string()),
+            })
+        }
+    }
+
+    async fn handle_search_notes(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let query = args.get("query")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing required field: query"))?;
+
+        let limit = parse_bounded_u32(&args, "limit", 20, 1, MAX_PAGE_SIZE)?;
+        let start = parse_page_start(&args)?;
+        let include_historical = args.get("include_historical").and_then(|v| v.as_bool()).unwrap_or(false);
+        let as_of = parse_as_of(&args)?;
+
+        // Parse filters if provided
+        let filters = if let Some(filters_value) = args.get("filters") {
+            Some(parse_search_filters(filters_value)?)
+        } else {
+            None
+        };
+
+        let mode = parse_search_mode(&args)?;
+
+        let include_facets = args.get("include_facets").and_then(|v| v.as_bool()).unwrap_or(false);
+        let explain = args.get("explain").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        let highlight: HighlightFormat = match args.get("highlight") {
+            Some(highlight) => serde_json::from_value(highlight.clone())
+                .map_err(|_| anyhow::anyhow!("Invalid highlight: expected \"markdown\", \"html\" or \"raw\""))?,
+            None => HighlightFormat::Markdown,
+        };
+
+        let options = SearchOptions {
+            mode,
+            filters,
+            limit,
+            start: start.clone(),
+            include_historical,
+            as_of,
+            include_facets,
+            highlight,
+            explain,
+        };
+        let response = self.search_engine.search_page(query, &options).await?;
+
+        let response_data = serde_json::json!({
+            "results": response.results,
+            "total": response.total,
+            "limit": limit,
+            "offset": page_offset(&start),
+            "next_cursor": response.next_cursor,
+            "suggestion": response.suggestion,
+            "used_suggestion": response.used_suggestion,
+            "mode": mode,
+            "as_of": as_of,
+            "facets": response.facets
+        });
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(response_data),
+            error: None,
+        })
+    }
+
+    /// Stream the answer of `answer_question` or the summary of
+    /// `summarize_note` or `summarize_collection` as [`StreamEvent`]s.
+    /// Errors before or during generation are returned; the caller reports
+    /// them to the client.
+    pub async fn handle_stream_request(&self, request: MCPRequest, events: UnboundedSender<StreamEvent>) -> Result<()> {
+        match request.tool.as_str() {
+            "answer_question" => self.stream_answer_question(request.arguments, &events).await,
+            "summarize_note" => self.stream_summarize_note(request.arguments, &events).await,
+            "summarize_collection" => {
+                let metadata = self.generate_collection_summary(&request.arguments, Some(&events)).await?;
+                let _ = events.send(StreamEvent::Done { metadata });
+                Ok(())
+            }
+            other => Err(anyhow::anyhow!("Tool does not support streaming: {}", other)),
+        }
+    }
+
+    async fn stream_answer_question(&self, args: serde_json::Value, events: &UnboundedSender<StreamEvent>) -> Result<()> {
+        let mut context = self.retrieve_answer_context(&args, true).await?;
+        let rendered = self.render_answer_prompt(&mut context, &args).await?;
+        // A closed channel means the client went away; generation below stops
+        let _ = events.send(StreamEvent::Citations { citations: context.citations.clone() });
+
+        let generated = if context.chunks.is_empty() {
+            let _ = events.send(StreamEvent::Token { text: NO_CONTEXT_ANSWER.to_string() });
+            Ok(NO_CONTEXT_ANSWER.to_string())
+        } else {
+            let mut streamed = false;
+            let generation = self
+                .llm
+                .answer_question_stream(&rendered.prompt, &rendered.model, rendered.budget.context_window, |token| {
+                    streamed = true;
+                    let _ = events.send(StreamEvent::Token { text: token.to_string() });
+                });
+            let generated = while_connected(Some(events), generation).await;
+            if generated.as_ref().is_err_and(is_model_unavailable) {
+                let separator = if streamed { "\n\n" } else { "" };
+                let _ = events.send(StreamEvent::Token { text: format!("{}{}", separator, MODEL_UNAVAILABLE_ANSWER) });
+            }
+            generated
+        };
+
+        let metadata = self.answer_metadata(&mut context, &rendered, generated).await?;
+        let _ = events.send(StreamEvent::Done { metadata });
+        Ok(())
+    }
+
+    /// The response to an `answer_question` request once the model has
+    /// answered, with `status` set to `answered` or `no_context`. When the
+    /// model server is unavailable the retrieved citations are still
+    /// returned, with status `model_unavailable` and the reason in
+    /// `model_error`; the question is not recorded as a conversation turn.
+    async fn answer_metadata(&self, context: &mut AnswerContext, rendered: &RenderedPrompt, generated: Result<String>) -> Result<serde_json::Value> {
+        let (answer, check, model_error) = match generated {
+            Ok(answer) => {
+                let (answer, check) = context.check_citations(answer);
+                (answer, check, None)
+            }
+            Err(e) if is_model_unavailable(&e) => {
+                tracing::warn!("Answering without the model: {}", e);
+                (MODEL_UNAVAILABLE_ANSWER.to_string(), None, Some(e.to_string()))
+            }
+            Err(e) => return Err(e),
+        };
+        let status = match (&model_error, context.chunks.is_empty()) {
+            (Some(_), _) => "model_unavailable",
+            (None, true) => "no_context",
+            (None, false) => "answered",
+        };
+
+        let mut metadata = context.metadata();
+        metadata["status"] = serde_json::json!(status);
+        metadata["model"] = serde_json::json!(rendered.model);
+        metadata["language"] = serde_json::json!(rendered.language);
+        metadata["answer"] = serde_json::json!(answer);
+        metadata["citations"] = serde_json::json!(context.citations);
+        metadata["citation_check"] = serde_json::json!(check);
+        metadata["context_budget"] = serde_json::json!(rendered.budget);
+        if let Some(model_error) = model_error {
+            metadata["model_error"] = serde_json::json!(model_error);
+        } else if let Some(turn) = self.record_turn(context, &answer).await? {
+            add_turn_metadata(&mut metadata, &turn);
+        }
+        Ok(metadata)
+    }
+
+    async fn stream_summarize_note(&self, args: serde_json::Value, events: &UnboundedSender<StreamEvent>) -> Result<()> {
+        let source = self
+            .summary_source(&args)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Document not found"))?;
+        let document = &source.document;
+        let citation = serde_json::json!({
+            "document_id": document.id,
+            "filename": document.filename,
+            "path": document.path,
+            "used_version": document.version
+        });
+        let _ = events.send(StreamEvent::Citations { citations: vec![citation] });
+
+        let outcome = self.generate_summary(&source, &args, Some(events)).await?;
+        let _ = events.send(StreamEvent::Done {
+            metadata: serde_json::json!({
+                "document": document,
+                "length": source.length_name,
+                "model": outcome.rendered.model,
+                "language": outcome.rendered.language,
+                "context_budget": outcome.rendered.budget,
+                "cached": outcome.cached,
+                "sections": outcome.sections
+            }),
+        });
+        Ok(())
+    }
+
+    /// The answer prompt for `context` in the language asked for by `args`
+    /// (or configured), from the template of the first project in scope
+    /// that overrides it. Chunks that do not fit the model's context window
+    /// next to the rest of the prompt and the answer are trimmed or dropped.
+    async fn render_answer_prompt(&self, context: &mut AnswerContext, args: &serde_json::Value) -> Result<RenderedPrompt> {
+        let template = self.prompts.resolve(PromptKind::Answer, &context.project_ids).await?;
+        let model = self.task_model(args, ModelTask::Answer);
+        let language = self.prompt_language(args, &context.question);
+        let mut variables = PromptVariables {
+            question: context.question.clone(),
+            language: language.clone(),
+            ..Default::default()
+        };
+
+        let overhead = budget::estimate_tokens(&prompts::render(&template.template, &variables));
+        let (mut budget, available) = self.budget.plan(&model, overhead).await;
+        context.fit_to_budget(available, &mut budget);
+
+        variables.context = context.prompt_context();
+        let prompt = prompts::render(&template.template, &variables);
+        Ok(RenderedPrompt { prompt, model, language, template, budget })
+    }
+
+    /// The summary prompt for a document, using its project's template.
+    /// Content beyond the model's context window is cut off.
+    async fn render_summary_prompt(&self, source: &SummarySource, args: &serde_json::Value) -> Result<RenderedPrompt> {
+        let project_ids: Vec<Uuid> = source.document.project_id.into_iter().collect();
+        let template = self.prompts.resolve(PromptKind::Summary, &project_ids).await?;
+        let model = self.task_model(args, ModelTask::Summary);
+        let language = self.prompt_language(args, &source.content);
+        let mut variables = PromptVariables {
+            language: language.clone(),
+            length: prompts::length_description(source.length).to_string(),
+            ..Default::default()
+        };
+
+        let overhead = budget::estimate_tokens(&prompts::render(&template.template, &variables));
+        let (mut budget, available) = self.budget.plan(&model, overhead).await;
+        let (content, truncated) = budget::trim_to_tokens(&source.content, available);
+        budget.context_tokens = budget::estimate_tokens(&content);
+        budget.prompt_tokens += budget.context_tokens;
+        budget.truncated = truncated;
+
+        variables.context = content;
+        let prompt = prompts::render(&template.template, &variables);
+        Ok(RenderedPrompt { prompt, model, language, template, budget })
+    }
+
+    /// Summarize `source`, reusing the cached summary while the content and
+    /// prompt are unchanged (unless `refresh` is set). Content that does not
+    /// fit the context window is summarized section by section first. With
+    /// `events`, progress and the final summary's tokens are streamed.
+    async fn generate_summary(
+        &self,
+        source: &SummarySource,
+        args: &serde_json::Value,
+        events: Option<&UnboundedSender<StreamEvent>>,
+    ) -> Result<SummaryOutcome> {
+        let mut rendered = self.render_summary_prompt(source, args).await?;
+        let model = rendered.model.clone();
+        let content_hash = format!("{:x}", md5::compute(&source.content));
+        let prompt_hash = format!("{:x}", md5::compute(format!("{}\0{}", rendered.template.template, rendered.language)));
+        let refresh = args.get("refresh").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        if !refresh {
+            let cached = self.db.get_cached_summary(&source.document.id, &source.length_name, &model).await?;
+            if let Some(cached) = cached.filter(|c| c.content_hash == content_hash && c.prompt_hash == prompt_hash) {
+                if let Some(events) = events {
+                    let _ = events.send(StreamEvent::Token { text: cached.summary.clone() });
+                }
+                return Ok(SummaryOutcome { summary: cached.summary, rendered, cached: true, sections: cached.sections });
+            }
+        }
+
+        let mut sections = 1;
+        if rendered.budget.truncated {
+            sections = self.map_reduce_summary_prompt(source, &mut rendered, events).await?;
+        }
+
+        let window = rendered.budget.context_window;
+        let summary = match events {
+            Some(events) => {
+                let generation = self.llm.summarize_stream(&rendered.prompt, &model, window, |token| {
+                    let _ = events.send(StreamEvent::Token { text: token.to_string() });
+                });
+                while_connected(Some(events), generation).await?
+            }
+            None => self.llm.summarize(&rendered.prompt, &model, window).await?,
+        };
+
+        self.db
+            .store_summary(&CachedSummary {
+                document_id: source.document.id,
+                length: source.length_name.clone(),
+                model,
+                content_hash,
+                prompt_hash,
+                summary: summary.clone(),
+                sections,
+                created_at: Utc::now().to_rfc3339(),
+            })
+            .await?;
+
+        Ok(SummaryOutcome { summary, rendered, cached: false, sections })
+    }
+
+    /// Replace the prompt of a summary whose content does not fit the
+    /// context window with one over section summaries: the content is split
+    /// into sections that fit, each is summarized, and the summaries are
+    /// merged the same way until they fit. Returns the number of sections.
+    async fn map_reduce_summary_prompt(
+        &self,
+        source: &SummarySource,
+        rendered: &mut RenderedPrompt,
+        events: Option<&UnboundedSender<StreamEvent>>,
+    ) -> Result<u32> {
+        let template = rendered.template.template.clone();
+        let model = rendered.model.clone();
+        let window = rendered.budget.context_window;
+        let section_variables = PromptVariables {
+            language: rendered.language.clone(),
+            length: prompts::SECTION_LENGTH.to_string(),
+            ..Default::default()
+        };
+        let final_variables = PromptVariables {
+            language: rendered.language.clone(),
+            length: prompts::length_description(source.length).to_string(),
+            ..Default::default()
+        };
+        let (_, section_room) = self.budget.plan(&model, budget::estimate_tokens(&prompts::render(&template, &section_variables))).await;
+        let final_overhead = budget::estimate_tokens(&prompts::render(&template, &final_variables));
+        let final_room = window.saturating_sub(rendered.budget.answer_reserve).saturating_sub(final_overhead);
+
+        let mut texts = budget::split_to_budget(&source.content, section_room);
+        let sections = texts.len() as u32;
+        let mut level = 0;
+        let merged = loop {
+            let stage = if level == 0 { "map" } else { "reduce" };
+            let mut summaries = Vec::with_capacity(texts.len());
+            for (i, text) in texts.iter().enumerate() {
+                let variables = PromptVariables { context: text.clone(), ..section_variables.clone() };
+                let prompt = prompts::render(&template, &variables);
+                summaries.push(while_connected(events, self.llm.summarize(&prompt, &model, window)).await?);
+
+                tracing::info!("Summarizing {}: {} {}/{}", source.document.filename, stage, i + 1, texts.len());
+                if let Some(events) = events {
+                    let _ = events.send(StreamEvent::Progress { stage: stage.to_string(), completed: i + 1, total: texts.len() });
+                }
+            }
+
+            let merged = summaries.join("\n\n");
+            level += 1;
+            if budget::estimate_tokens(&merged) <= final_room {
+                break merged;
+            }
+            let next = budget::split_to_budget(&merged, section_room);
+            // Stop when merging no longer shrinks the text; it is cut below
+            if level >= MAX_REDUCE_LEVELS || next.len() >= texts.len() {
+                break merged;
+            }
+            texts = next;
+        };
+
+        let (merged, truncated) = budget::trim_to_tokens(&merged, final_room);
+        rendered.budget.truncated = truncated;
+        rendered.budget.context_tokens = budget::estimate_tokens(&merged);
+        rendered.budget.prompt_tokens = final_overhead + rendered.budget.context_tokens;
+        rendered.prompt = prompts::render(&template, &PromptVariables { context: merged, ..final_variables });
+        Ok(sections)
+    }
+
+    /// The model named by the request's `model`, else the default for `task`.
+    fn task_model(&self, args: &serde_json::Value, task: ModelTask) -> String {
+        args.get("model")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|model| !model.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| self.llm.model_for(task).to_string())
+    }
+
+    /// `language` from the request or config; "auto" follows `text`.
+    fn prompt_language(&self, args: &serde_json::Value, text: &str) -> String {
+        let setting = args.get("language").and_then(|v| v.as_str()).unwrap_or(self.prompts.language());
+        prompts::answer_language(setting, text)
+    }
+
+    /// The document to summarize with its full content and the requested length.
+    async fn summary_source(&self, args: &serde_json::Value) -> Result<Option<SummarySource>> {
+        let id_str = args.get("id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing required field: id"))?;
+
+        let id = Uuid::parse_str(id_str)?;
+        let (length, length_name) = parse_summary_length(args);
+
+        match self.db.get_document_by_id(&id).await? {
+            Some(document) => {
+                // Read the full file content
+                let content = std::fs::read_to_string(&document.path)?;
+                Ok(Some(SummarySource { document, content, length, length_name }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn handle_summarize_note(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        if let Some(source) = self.summary_source(&args).await? {
+            // Generate summary with the model
+            let outcome = self.generate_summary(&source, &args, None).await?;
+
+            let response_data = serde_json::json!({
+                "document": source.document,
+                "summary": outcome.summary,
+                "length": source.length_name,
+                "model": outcome.rendered.model,
+                "language": outcome.rendered.language,
+                "context_budget": outcome.rendered.budget,
+                "cached": outcome.cached,
+                "sections": outcome.sections
+            });
+
+            Ok(MCPResponse {
+                success: true,
+                data: Some(response_data),
+                error: None,
+            })
+        } else {
+            Ok(MCPResponse {
+                success: false,
+                data: None,
+                error: Some("Document not found".to_string()),
+            })
+        }
+    }
+
+    async fn handle_summarize_collection(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let response_data = self.generate_collection_summary(&args, None).await?;
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(response_data),
+            error: None,
+        })
+    }
+
+    /// The documents named by exactly one of `project_id`, `folder`, `tag`
+    /// or `saved_search`, most recently modified (or best matching) first.
+    async fn resolve_collection(&self, args: &serde_json::Value) -> Result<Collection> {
+        let limit = parse_bounded_u32(args, "limit", 20, 1, MAX_COLLECTION_DOCUMENTS)?;
+        let selectors: Vec<&str> = ["project_id", "folder", "tag", "saved_search"]
+            .into_iter()
+            .filter(|key| args.get(*key).is_some_and(|v| !v.is_null()))
+            .collect();
+        let [selector] = selectors[..] else {
+            return Err(anyhow::anyhow!("Specify exactly one of project_id, folder, tag or saved_search"));
+        };
+        let value = args[selector]
+            .as_str()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Invalid {}: expected a non-empty string", selector))?;
+
+        let collection = match selector {
+            "project_id" => {
+                let project_id = parse_project_id(args)?.unwrap_or_default();
+                if self.db.get_project(&project_id).await?.is_none() {
+                    return Err(anyhow::anyhow!("Project not found: {}", project_id));
+                }
+                let documents = self.db.list_collection_documents(Some(&project_id), None, None, limit).await?;
+                Collection { kind: "project", value: project_id.to_string(), documents }
+            }
+            "folder" => {
+                // Indexed paths are canonical
+                let folder = std::fs::canonicalize(value)
+                    .map(|path| path.to_string_lossy().to_string())
+                    .unwrap_or_else(|_| value.to_string());
+                let documents = self.db.list_collection_documents(None, Some(&folder), None, limit).await?;
+                Collection { kind: "folder", value: folder, documents }
+            }
+            "tag" => {
+                let tag = value.trim_start_matches('#');
+                let documents = self.db.list_collection_documents(None, None, Some(tag), limit).await?;
+                Collection { kind: "tag", value: tag.to_string(), documents }
+            }
+            _ => {
+                let saved_search = self.find_saved_search(&serde_json::json!({ "id": value })).await?;
+                let options = SearchOptions { limit, ..saved_search_options(&saved_search)? };
+                let response = self.search_engine.search_page(&saved_search.query, &options).await?;
+                let documents = response.results.into_iter().map(|result| result.document).collect();
+                Collection { kind: "saved_search", value: saved_search.name, documents }
+            }
+        };
+        Ok(collection)
+    }
+
+    /// Summarize each document of a collection, reusing cached summaries,
+    /// then merge the summaries into one overview citing the documents by
+    /// number. Summaries that do not fit the context window are trimmed or
+    /// left out. With `events`, progress through the documents, the
+    /// citations and the overview's tokens are streamed.
+    async fn generate_collection_summary(
+        &self,
+        args: &serde_json::Value,
+        events: Option<&UnboundedSender<StreamEvent>>,
+    ) -> Result<serde_json::Value> {
+        let collection = self.resolve_collection(args).await?;
+        let (length, length_name) = parse_summary_length(args);
+        let (document_length, document_length_name) = COLLECTION_DOCUMENT_LENGTH;
+
+        let total = collection.documents.len();
+        let mut summaries = Vec::with_capacity(total);
+        let mut skipped = Vec::new();
+        for (i, document) in collection.documents.into_iter().enumerate() {
+            match std::fs::read_to_string(&document.path) {
+                Ok(content) => {
+                    let source = SummarySource {
+                        document,
+                        content,
+                        length: document_length,
+                        length_name: document_length_name.to_string(),
+                    };
+                    let outcome = while_connected(events, self.generate_summary(&source, args, None)).await?;
+                    summaries.push((source.document, outcome.summary, outcome.cached));
+                }
+                // Files removed since indexing should not sink the whole digest
+                Err(e) => skipped.push(serde_json::json!({
+                    "document_id": document.id,
+                    "path": document.path,
+                    "error": e.to_string()
+                })),
+            }
+
+            tracing::info!("Summarizing {} {}: document {}/{}", collection.kind, collection.value, i + 1, total);
+            if let Some(events) = events {
+                let _ = events.send(StreamEvent::Progress { stage: "documents".to_string(), completed: i + 1, total });
+            }
+        }
+
+        let model = self.task_model(args, ModelTask::Summary);
+        let all_summaries = summaries.iter().map(|(_, summary, _)| summary.as_str()).collect::<Vec<_>>().join("\n\n");
+        let language = self.prompt_language(args, &all_summaries);
+        let mut variables = PromptVariables {
+            language: language.clone(),
+            length: prompts::collection_length_description(length).to_string(),
+            ..Default::default()
+        };
+        let overhead = budget::estimate_tokens(&prompts::render(prompts::COLLECTION_TEMPLATE, &variables));
+        let (mut budget, available) = self.budget.plan(&model, overhead).await;
+        let candidates: Vec<(usize, &str)> = summaries
+            .iter()
+            .enumerate()
+            .map(|(i, (document, summary, _))| (budget::estimate_tokens(&chunk_label(i, &document.filename)) + 1, summary.as_str()))
+            .collect();
+        let kept = budget::fit_chunks(&candidates, available, &mut budget);
+
+        let mut markers = vec![None; summaries.len()];
+        let mut context = Vec::with_capacity(kept.len());
+        let mut citations = Vec::with_capacity(kept.len());
+        for (i, text) in kept {
+            let (document, summary, _) = &summaries[i];
+            markers[i] = Some(citations.len() + 1);
+            context.push(format!("{}{}", chunk_label(citations.len(), &document.filename), text));
+            citations.push(serde_json::json!({
+                "marker": citations.len() + 1,
+                "document_id": document.id,
+                "filename": document.filename,
+                "path": document.path,
+                "used_version": document.version,
+                "trimmed": text != *summary
+            }));
+        }
+        if let Some(events) = events {
+            let _ = events.send(StreamEvent::Citations { citations: citations.clone() });
+        }
+
+        variables.context = context.join("\n\n");
+        let prompt = prompts::render(prompts::COLLECTION_TEMPLATE, &variables);
+        let overview = if citations.is_empty() {
+            if let Some(events) = events {
+                let _ = events.send(StreamEvent::Token { text: EMPTY_COLLECTION_OVERVIEW.to_string() });
+            }
+            EMPTY_COLLECTION_OVERVIEW.to_string()
+        } else {
+            match events {
+                Some(events) => {
+                    let generation = self.llm.summarize_stream(&prompt, &model, budget.context_window, |token| {
+                        let _ = events.send(StreamEvent::Token { text: token.to_string() });
+                    });
+                    while_connected(Some(events), generation).await?
+                }
+                None => self.llm.summarize(&prompt, &model, budget.context_window).await?,
+            }
+        };
+
+        let (overview, check) = if citations.is_empty() {
+            (overview, None)
+        } else {
+            let (overview, check) = citations::verify_citations(&overview, citations.len());
+            for (i, citation) in citations.iter_mut().enumerate() {
+                citation["used"] = serde_json::json!(check.used.contains(&(i + 1)));
+            }
+            (overview, Some(check))
+        };
+
+        let documents: Vec<serde_json::Value> = summaries
+            .into_iter()
+            .zip(markers)
+            .map(|((document, summary, cached), marker)| {
+                serde_json::json!({ "document": document, "summary": summary, "cached": cached, "marker": marker })
+            })
+            .collect();
+
+        Ok(serde_json::json!({
+            "collection": { "kind": collection.kind, "value": collection.value },
+            "length": length_name,
+            "model": model,
+            "language": language,
+            "overview": overview,
+            "documents": documents,
+            "skipped": skipped,
+            "citations": citations,
+            "citation_check": check,
+            "context_budget": budget
+        }))
+    }
+
+    async fn handle_answer_question(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let mut context = self.retrieve_answer_context(&args, true).await?;
+
+        let rendered = self.render_answer_prompt(&mut context, &args).await?;
+        let generated = if context.chunks.is_empty() {
+            Ok(NO_CONTEXT_ANSWER.to_string())
+        } else {
+            // Generate answer with the model
+            self.llm.answer_question(&rendered.prompt, &rendered.model, rendered.budget.context_window).await
+        };
+        let response_data = self.answer_metadata(&mut context, &rendered, generated).await?;
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(response_data),
+            error: None,
+        })
+    }
+
+    /// Chunks relevant to an `answer_question` request and their citations.
+    /// Within a conversation, a follow-up question is first rewritten from
+    /// the recent turns and searched for in the conversation's projects;
+    /// unless `condense` is set, without asking the model to rewrite it.
+    async fn retrieve_answer_context(&self, args: &serde_json::Value, condense: bool) -> Result<AnswerContext> {
+        let asked = args.get("question")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing required field: question"))?;
+
+        let top_k = parse_bounded_u32(args, "top_k", 5, 1, MAX_TOP_K)?;
+        let max_chunks_per_document =
+            parse_bounded_u32(args, "max_chunks_per_document", DEFAULT_MAX_CHUNKS_PER_DOCUMENT, 1, MAX_TOP_K)?;
+        let as_of = parse_as_of(args)?;
+
+        // Extract project_ids if provided
+        let mut project_ids = args.get("project_ids")
+            .and_then(|v| v.as_array())
+            .and_then(|arr| {
+                let parsed_ids: Result<Vec<Uuid>, _> = arr.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(Uuid::parse_str)
+                    .collect();
+                parsed_ids.ok()
+            });
+
+        let conversation = match args.get("conversation_id").and_then(|v| v.as_str()) {
+            Some(id) => {
+                let id = Uuid::parse_str(id).map_err(|_| anyhow::anyhow!("Invalid conversation ID: {}", id))?;
+                Some(self.db.get_conversation(&id).await?.ok_or_else(|| anyhow::anyhow!("Conversation not found: {}", id))?)
+            }
+            None => None,
+        };
+        let question = match &conversation {
+            Some(conversation) => {
+                if project_ids.is_none() && !conversation.project_ids.is_empty() {
+                    project_ids = Some(conversation.project_ids.clone());
+                }
+                let history = self.db.get_conversation_turns(&conversation.id, Some(CONVERSATION_HISTORY_TURNS)).await?;
+                if condense {
+                    self.standalone_question(&history, asked, &self.task_model(args, ModelTask::Answer)).await
+                } else {
+                    joined_question(&history, asked)
+                }
+            }
+            None => asked.to_string(),
+        };
+        let question = question.as_str();
+
+        // Get relevant chunks with project filtering
+        let chunks = self.search_engine
+            .get_relevant_chunks_for_qa_with_filters(question, top_k, project_ids.as_deref(), max_chunks_per_document, as_of)
+            .await?;
+
+        // Create citations with version information
+        let mut citations = Vec::new();
+        for chunk in chunks.iter() {
+            let (doc, entry) = (&chunk.document, &chunk.entry);
+            // Get the latest version of this document
+            let latest_doc = self.db.get_latest_document_version(&std::path::PathBuf::from(&doc.path)).await?;
+            let latest_version = latest_doc.as_ref().map(|d| d.version).unwrap_or(doc.version);
+            
+            citations.push(serde_json::json!({
+                "marker": citations.len() + 1,
+                "document_id": doc.id,
+                "filename": doc.filename,
+                "path": doc.path,
+                "chunk_id": entry.chunk_id,
+                "excerpt": entry.chunk_text,
+                "used_version": doc.version,
+                "latest_version": latest_version,
+                "indexed_at": doc.indexed_at,
+                "is_latest": doc.is_latest,
+                "score": chunk.score,
+                "lexical_rank": chunk.lexical_rank,
+                "semantic_rank": chunk.semantic_rank
+            }));
+        }
+
+        Ok(AnswerContext {
+            question: question.to_string(),
+            asked: asked.to_string(),
+            conversation_id: conversation.map(|conversation| conversation.id),
+            chunks,
+            citations,
+            project_ids: project_ids.unwrap_or_default(),
+            as_of,
+        })
+    }
+
+    /// `question` rewritten to be understood without `history`. Without
+    /// earlier turns it is used as is; if the model is unavailable, it is
+    /// [joined](joined_question) with the previous question instead.
+    async fn standalone_question(&self, history: &[ConversationTurn], question: &str, model: &str) -> String {
+        if history.is_empty() {
+            return question.to_string();
+        }
+
+        let prompt = prompts::condense_question_prompt(history, question);
+        match self.llm.condense_question(&prompt, model).await {
+            Ok(rewritten) => clean_condensed_question(&rewritten).unwrap_or_else(|| joined_question(history, question)),
+            Err(e) => {
+                tracing::warn!("Failed to rewrite follow-up question: {}", e);
+                joined_question(history, question)
+            }
+        }
+    }
+
+    /// Store the answered question as the next turn of its conversation.
+    async fn record_turn(&self, context: &AnswerContext, answer: &str) -> Result<Option<ConversationTurn>> {
+        match &context.conversation_id {
+            Some(conversation_id) => {
+                let citations = serde_json::json!(context.citations);
+                let turn = self
+                    .db
+                    .add_conversation_turn(conversation_id, &context.asked, &context.question, answer, &citations)
+                    .await?;
+                Ok(Some(turn))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn handle_start_conversation(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let title = args.get("title")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|title| !title.is_empty());
+        let project_ids = parse_project_ids(&args)?.unwrap_or_default();
+        for project_id in &project_ids {
+            if self.db.get_project(project_id).await?.is_none() {
+                return Err(anyhow::anyhow!("Project not found: {}", project_id));
+            }
+        }
+
+        let conversation = self.db.create_conversation(title, &project_ids).await?;
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({ "conversation": conversation })),
+            error: None,
+        })
+    }
+
+    async fn handle_list_conversations(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let limit = parse_bounded_u32(&args, "limit", 20, 1, MAX_CONVERSATIONS)?;
+        let conversations = self.db.list_conversations(limit).await?;
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({ "conversations": conversations })),
+            error: None,
+        })
+    }
+
+    async fn handle_get_conversation(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let id = parse_conversation_id(&args)?;
+        let conversation = self
+            .db
+            .get_conversation(&id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Conversation not found: {}", id))?;
+        let turns = self.db.get_conversation_turns(&id, None).await?;
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({ "conversation": conversation, "turns": turns })),
+            error: None,
+        })
+    }
+
+    async fn handle_delete_conversation(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let id = parse_conversation_id(&args)?;
+        if !self.db.delete_conversation(&id).await? {
+            return Err(anyhow::anyhow!("Conversation not found: {}", id));
+        }
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({ "id": id, "deleted": true })),
+            error: None,
+        })
+    }
+
+    async fn handle_find_similar(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let id_str = args.get("id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing required field: id"))?;
+        let id = Uuid::parse_str(id_str)?;
+        let limit = parse_bounded_u32(&args, "limit", 10, 1, MAX_SIMILAR)?;
+        let project_ids = parse_project_ids(&args)?;
+
+        let similar = self.search_engine.find_similar(&id, project_ids.as_deref(), limit).await?;
 
-        let documents = self.db.search_documents("", limit, offset, false).await?;
-        
         let response_data = serde_json::json!({
-            "notes": documents,
-            "total": documents.len(),
-            "limit": limit,
-            "offset": offset
+            "id": id,
+            "similar": similar
         });
 
         Ok(MCPResponse {
@@ -211,59 +1608,136 @@ impl MCPServer {
         })
     }
 
-    async fn handle_read_note(&self, args: serde_json::Value) -> Result<MCPResponse> {
-        let id_str = args.get("id")
+    async fn handle_suggest(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let prefix = args.get("prefix")
             .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("Missing required field: id"))?;
+            .ok_or_else(|| anyhow::anyhow!("Missing required field: prefix"))?;
+        let limit = parse_bounded_u32(&args, "limit", 8, 1, MAX_SUGGESTIONS)?;
+        let project_ids = parse_project_ids(&args)?;
 
-        let id = Uuid::parse_str(id_str)?;
-        
-        if let Some(document) = self.db.get_document_by_id(&id).await? {
-            // Read the full file content
-            let content = std::fs::read_to_string(&document.path)?;
-            
-            let response_data = serde_json::json!({
-                "document": document,
-                "content": content
-            });
+        let suggestions = self.search_engine.suggest(prefix, project_ids.as_deref(), limit).await?;
 
-            Ok(MCPResponse {
-                success: true,
-                data: Some(response_data),
-                error: None,
-            })
-        } else {
-            Ok(MCPResponse {
-                success: false,
-                data: None,
-                error: Some("Document not found".to_string()),
-            })
-        }
+        let response_data = serde_json::json!({
+            "prefix": prefix,
+            "suggestions": suggestions
+        });
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(response_data),
+            error: None,
+        })
     }
 
-    async fn handle_search_notes(&self, args: serde_json::Value) -> Result<MCPResponse> {
+    async fn handle_save_search(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let name = args.get("name")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing required field: name"))?;
         let query = args.get("query")
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing required field: query"))?;
+        let mode = parse_search_mode(&args)?;
+        let filters = match args.get("filters") {
+            Some(filters_value) => Some(serde_json::to_value(parse_search_filters(filters_value)?)?),
+            None => None,
+        };
 
-        let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(20) as u32;
-        let offset = args.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
-        let include_historical = args.get("include_historical").and_then(|v| v.as_bool()).unwrap_or(false);
+        if self.db.get_saved_search(name).await?.is_some() {
+            return Err(anyhow::anyhow!("A saved search named \"{}\" already exists", name));
+        }
+        let mode_name = match mode {
+            SearchMode::Keyword => "keyword",
+            SearchMode::Semantic => "semantic",
+        };
+        let saved_search = self.db.create_saved_search(name, query, filters.as_ref(), mode_name).await?;
 
-        // Parse filters if provided
-        let filters = if let Some(filters_value) = args.get("filters") {
-            Some(parse_search_filters(filters_value)?)
-        } else {
-            None
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({ "saved_search": saved_search })),
+            error: None,
+        })
+    }
+
+    async fn handle_list_saved_searches(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        // Counting runs every search, semantic ones through the embedder
+        let counts = args.get("counts").and_then(|v| v.as_bool()).unwrap_or(false);
+        let mut saved_searches = Vec::new();
+        for saved_search in self.db.list_saved_searches().await? {
+            let mut entry = serde_json::to_value(&saved_search)?;
+            if counts {
+                // Like a folder's file count; unavailable if e.g. embeddings are down
+                let options = SearchOptions { limit: 1, ..saved_search_options(&saved_search)? };
+                let total = self.search_engine.search_page(&saved_search.query, &options).await.map(|r| r.total).ok();
+                entry["total"] = serde_json::json!(total);
+            }
+            saved_searches.push(entry);
+        }
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({ "saved_searches": saved_searches })),
+            error: None,
+        })
+    }
+
+    async fn handle_run_saved_search(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let mut saved_search = self.find_saved_search(&args).await?;
+        let limit = parse_bounded_u32(&args, "limit", 20, 1, MAX_PAGE_SIZE)?;
+        let start = parse_page_start(&args)?;
+        let new_only = args.get("new_only").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        // Cursors of a new_only run keep the cutoff of its first page
+        let (start, run_since) = match start {
+            PageStart::Cursor(Cursor::SavedSearchRun { new_since, page }) => (PageStart::Cursor(*page), Some(new_since)),
+            start => (start, None),
+        };
+        let since = parse_timestamp(&args, "since")?;
+        if new_only && since.is_none() && matches!(start, PageStart::Offset(offset) if offset > 0) {
+            return Err(anyhow::anyhow!("Page a new_only run with next_cursor, or pass the first page's new_since as since"));
+        }
+
+        let mut options = SearchOptions { limit, start: start.clone(), ..saved_search_options(&saved_search)? };
+        let new_since = match (since, run_since) {
+            (Some(since), _) => Some(since),
+            (None, Some(run_since)) => run_since,
+            (None, None) => saved_search
+                .last_run_at
+                .as_deref()
+                .map(DateTime::parse_from_rfc3339)
+                .transpose()?
+                .map(|date| date.with_timezone(&Utc)),
+        };
+        if new_only {
+            // Without a previous run everything is new
+            options.filters.get_or_insert_with(Default::default).indexed_after = new_since;
+        }
+
+        let run_at = Utc::now();
+        let response = self.search_engine.search_page(&saved_search.query, &options).await?;
+
+        // Later pages belong to the same run
+        if start == PageStart::Offset(0) {
+            self.db.mark_saved_search_run(&saved_search.id, run_at).await?;
+            saved_search.last_run_at = Some(run_at.to_rfc3339());
+        }
+        let next_cursor = match response.next_cursor {
+            Some(cursor) if new_only => Some(
+                Cursor::SavedSearchRun { new_since, page: Box::new(Cursor::decode(&cursor)?) }.encode(),
+            ),
+            cursor => cursor,
         };
 
-        let results = self.search_engine.search(query, filters, limit, offset, include_historical).await?;
-        
         let response_data = serde_json::json!({
-            "results": results,
-            "total": results.len(),
+            "saved_search": saved_search,
+            "results": response.results,
+            "total": response.total,
             "limit": limit,
-            "offset": offset
+            "offset": page_offset(&start),
+            "next_cursor": next_cursor,
+            "new_only": new_only,
+            "new_since": if new_only { new_since } else { None }
         });
 
         Ok(MCPResponse {
@@ -273,123 +1747,216 @@ impl MCPServer {
         })
     }
 
-    async fn handle_summarize_note(&self, args: serde_json::Value) -> Result<MCPResponse> {
-        let id_str = args.get("id")
+    async fn handle_delete_saved_search(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let saved_search = self.find_saved_search(&args).await?;
+        self.db.delete_saved_search(&saved_search.id).await?;
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({ "id": saved_search.id, "deleted": true })),
+            error: None,
+        })
+    }
+
+    async fn find_saved_search(&self, args: &serde_json::Value) -> Result<SavedSearch> {
+        let id_or_name = args.get("id")
+            .or_else(|| args.get("name"))
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing required field: id"))?;
+        self.db
+            .get_saved_search(id_or_name)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Saved search not found: {}", id_or_name))
+    }
+
+    async fn handle_list_synonyms(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let project_id = parse_project_id(&args)?;
+        let synonyms = match project_id {
+            Some(project_id) => self.db.get_synonyms(Some(&[project_id])).await?,
+            None => self.db.get_synonyms(None).await?,
+        };
 
-        let length_str = args.get("length")
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({ "synonyms": synonyms })),
+            error: None,
+        })
+    }
+
+    async fn handle_set_synonyms(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let term = args.get("term")
             .and_then(|v| v.as_str())
-            .unwrap_or("medium");
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing required field: term"))?;
+        let synonyms: Vec<String> = args.get("synonyms")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| anyhow::anyhow!("Missing required field: synonyms"))?
+            .iter()
+            .map(|v| v.as_str().map(|s| s.trim().to_string()).ok_or_else(|| anyhow::anyhow!("Invalid synonym: {}", v)))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|synonym| !synonym.is_empty() && !synonym.eq_ignore_ascii_case(term))
+            .collect();
+        if synonyms.is_empty() {
+            return Err(anyhow::anyhow!("At least one synonym different from the term is required"));
+        }
+        let project_id = parse_project_id(&args)?;
+        if let Some(project_id) = &project_id {
+            if self.db.get_project(project_id).await?.is_none() {
+                return Err(anyhow::anyhow!("Project not found: {}", project_id));
+            }
+        }
+
+        let synonym = self.db.upsert_synonym(project_id.as_ref(), term, &synonyms).await?;
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({ "synonym": synonym })),
+            error: None,
+        })
+    }
 
+    async fn handle_delete_synonym(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let id_str = args.get("id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing required field: id"))?;
         let id = Uuid::parse_str(id_str)?;
-        let length = match length_str {
-            "short" => SummaryLength::Short,
-            "long" => SummaryLength::Long,
-            _ => SummaryLength::Medium,
-        };
+        if !self.db.delete_synonym(&id).await? {
+            return Err(anyhow::anyhow!("Synonym not found: {}", id));
+        }
 
-        if let Some(document) = self.db.get_document_by_id(&id).await? {
-            // Read the full file content
-            let content = std::fs::read_to_string(&document.path)?;
-            
-            // Generate summary using Ollama
-            let summary = self.ollama_client.summarize(&content, length).await?;
-            
-            let response_data = serde_json::json!({
-                "document": document,
-                "summary": summary,
-                "length": length_str
-            });
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({ "id": id, "deleted": true })),
+            error: None,
+        })
+    }
 
-            Ok(MCPResponse {
-                success: true,
-                data: Some(response_data),
-                error: None,
-            })
-        } else {
-            Ok(MCPResponse {
-                success: false,
-                data: None,
-                error: Some("Document not found".to_string()),
-            })
+    async fn handle_list_prompt_templates(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let project_ids: Vec<Uuid> = parse_project_id(&args)?.into_iter().collect();
+        let mut effective = Vec::new();
+        for kind in PromptKind::ALL {
+            effective.push(self.prompts.resolve(kind, &project_ids).await?);
         }
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({
+                "effective": effective,
+                "overrides": self.db.list_prompt_templates().await?,
+                "language": self.prompts.language(),
+                "variables": prompts::TEMPLATE_VARIABLES
+            })),
+            error: None,
+        })
     }
 
-    async fn handle_answer_question(&self, args: serde_json::Value) -> Result<MCPResponse> {
-        let question = args.get("question")
+    async fn handle_set_prompt_template(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let kind = parse_prompt_kind(&args)?;
+        let template = args.get("template")
             .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("Missing required field: question"))?;
+            .ok_or_else(|| anyhow::anyhow!("Missing required field: template"))?;
+        prompts::validate(kind, template)?;
+        let project_id = parse_project_id(&args)?;
+        if let Some(project_id) = &project_id {
+            if self.db.get_project(project_id).await?.is_none() {
+                return Err(anyhow::anyhow!("Project not found: {}", project_id));
+            }
+        }
 
-        let top_k = args.get("top_k").and_then(|v| v.as_u64()).unwrap_or(5) as u32;
+        let template = self.db.set_prompt_template(project_id.as_ref(), kind.name(), template).await?;
 
-        // Extract project_ids if provided
-        let project_ids = args.get("project_ids")
-            .and_then(|v| v.as_array())
-            .and_then(|arr| {
-                let parsed_ids: Result<Vec<Uuid>, _> = arr.iter()
-                    .filter_map(|v| v.as_str())
-                    .map(|s| Uuid::parse_str(s))
-                    .collect();
-                parsed_ids.ok()
-            });
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({ "template": template })),
+            error: None,
+        })
+    }
 
-        // Get relevant chunks with project filtering
-        let chunks = self.search_engine.get_relevant_chunks_for_qa_with_filters(question, top_k, project_ids.as_ref().map(|ids| ids.as_slice())).await?;
-        
-        if chunks.is_empty() {
-            return Ok(MCPResponse {
-                success: true,
-                data: Some(serde_json::json!({
-                    "answer": "I couldn't find any relevant information in the knowledge base to answer your question.",
-                    "confidence": "low",
-                    "citations": []
-                })),
-                error: None,
-            });
-        }
+    async fn handle_reset_prompt_template(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let kind = parse_prompt_kind(&args)?;
+        let project_id = parse_project_id(&args)?;
+        let removed = self.db.delete_prompt_template(project_id.as_ref(), kind.name()).await?;
+        let project_ids: Vec<Uuid> = project_id.into_iter().collect();
 
-        // Combine context from chunks
-        let context = chunks.iter()
-            .map(|(doc, entry)| format!("[{}] {}", doc.filename, entry.chunk_text))
-            .collect::<Vec<_>>()
-            .join("\n\n");
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({
+                "removed": removed,
+                "effective": self.prompts.resolve(kind, &project_ids).await?
+            })),
+            error: None,
+        })
+    }
 
-        // Generate answer using Ollama
-        let answer = self.ollama_client.answer_question(question, &context).await?;
+    async fn handle_preview_prompt(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let rendered = match parse_prompt_kind(&args)? {
+            PromptKind::Answer => {
+                let mut context = self.retrieve_answer_context(&args, false).await?;
+                self.render_answer_prompt(&mut context, &args).await?
+            }
+            PromptKind::Summary => {
+                let source = self
+                    .summary_source(&args)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("Document not found"))?;
+                self.render_summary_prompt(&source, &args).await?
+            }
+        };
 
-        // Create citations with version information
-        let mut citations = Vec::new();
-        for (doc, entry) in chunks.iter() {
-            // Get the latest version of this document
-            let latest_doc = self.db.get_latest_document_version(&std::path::PathBuf::from(&doc.path)).await?;
-            let latest_version = latest_doc.as_ref().map(|d| d.version).unwrap_or(doc.version);
-            
-            citations.push(serde_json::json!({
-                "document_id": doc.id,
-                "filename": doc.filename,
-                "path": doc.path,
-                "chunk_id": entry.chunk_id,
-                "excerpt": entry.chunk_text,
-                "used_version": doc.version,
-                "latest_version": latest_version,
-                "is_latest": doc.is_latest
-            }));
-        }
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::to_value(&rendered)?),
+            error: None,
+        })
+    }
 
-        // Calculate confidence (simple heuristic)
-        let confidence = if chunks.len() >= 3 { "high" } else if chunks.len() >= 2 { "medium" } else { "low" };
+    async fn handle_list_models(&self, _args: serde_json::Value) -> Result<MCPResponse> {
+        let models = self.llm.list_models().await?;
+        let defaults: serde_json::Map<String, serde_json::Value> = ModelTask::ALL
+            .iter()
+            .map(|task| (task.name().to_string(), serde_json::json!(self.llm.model_for(*task))))
+            .collect();
 
-        let response_data = serde_json::json!({
-            "answer": answer,
-            "confidence": confidence,
-            "citations": citations,
-            "context_chunks": chunks.len()
-        });
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({
+                "provider": self.llm.provider_name(),
+                "models": models,
+                "defaults": defaults
+            })),
+            error: None,
+        })
+    }
+
+    async fn handle_show_model(&self, args: serde_json::Value) -> Result<MCPResponse> {
+        let name = args.get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing required field: name"))?;
+        let details = self
+            .llm
+            .show_model(name)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Model not found: {}", name))?;
+        let used_for: Vec<ModelTask> = ModelTask::ALL
+            .into_iter()
+            .filter(|task| crate::llm::same_model(self.llm.model_for(*task), name))
+            .collect();
 
         Ok(MCPResponse {
             success: true,
-            data: Some(response_data),
+            data: Some(serde_json::json!({ "model": details, "used_for": used_for })),
+            error: None,
+        })
+    }
+
+    async fn handle_check_models(&self, _args: serde_json::Value) -> Result<MCPResponse> {
+        let check = self.llm.check_models().await;
+
+        Ok(MCPResponse {
+            success: true,
+            data: Some(serde_json::json!({ "ok": check.ok(), "check": check })),
             error: None,
         })
     }
@@ -603,6 +2170,157 @@ impl MCPServer {
     }
 }
 
+/// Read an optional integer argument, rejecting values outside `min..=max`.
+/// Run `generation` while the client receiving `events` is connected; once
+/// it goes away the generation is dropped, which also closes the request to
+/// the model server. Without `events` it simply runs.
+async fn while_connected<T>(
+    events: Option<&UnboundedSender<StreamEvent>>,
+    generation: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    let Some(events) = events else {
+        return generation.await;
+    };
+    tokio::select! {
+        result = generation => result,
+        _ = events.closed() => Err(anyhow::anyhow!("The client disconnected")),
+    }
+}
+
+fn parse_bounded_u32(args: &serde_json::Value, field: &str, default: u32, min: u32, max: u32) -> Result<u32> {
+    match args.get(field) {
+        None | Some(serde_json::Value::Null) => Ok(default),
+        Some(value) => value
+            .as_u64()
+            .filter(|v| *v >= min as u64 && *v <= max as u64)
+            .map(|v| v as u32)
+            .ok_or_else(|| anyhow::anyhow!("{} must be an integer between {} and {}", field, min, max)),
+    }
+}
+
+fn parse_project_ids(args: &serde_json::Value) -> Result<Option<Vec<Uuid>>> {
+    match args.get("project_ids").and_then(|v| v.as_array()) {
+        Some(ids) => ids
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .and_then(|s| Uuid::parse_str(s).ok())
+                    .ok_or_else(|| anyhow::anyhow!("Invalid project ID: {}", v))
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Some),
+        None => Ok(None),
+    }
+}
+
+/// The `length` of a summary, `medium` unless given.
+fn parse_summary_length(args: &serde_json::Value) -> (SummaryLength, String) {
+    let length_str = args.get("length")
+        .and_then(|v| v.as_str())
+        .unwrap_or("medium");
+    let length = match length_str {
+        "short" => SummaryLength::Short,
+        "long" => SummaryLength::Long,
+        _ => SummaryLength::Medium,
+    };
+    (length, length_str.to_string())
+}
+
+fn parse_conversation_id(args: &serde_json::Value) -> Result<Uuid> {
+    let id = args.get("id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required field: id"))?;
+    Uuid::parse_str(id).map_err(|_| anyhow::anyhow!("Invalid conversation ID: {}", id))
+}
+
+/// `question` after the standalone form of the previous one, so retrieval
+/// still sees the topic of a follow-up without the model rewriting it.
+fn joined_question(history: &[ConversationTurn], question: &str) -> String {
+    match history.last() {
+        Some(previous) => format!("{} {}", previous.standalone_question, question),
+        None => question.to_string(),
+    }
+}
+
+/// The first line of a model's rewritten question, without a repeated
+/// label or quotes; `None` if nothing usable is left.
+fn clean_condensed_question(text: &str) -> Option<String> {
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = line.strip_prefix("Standalone question:").unwrap_or(line).trim();
+    let line = line.trim_matches(|c| c == '"' || c == '\'' || c == '`').trim();
+    (!line.is_empty()).then(|| line.to_string())
+}
+
+/// Add the conversation turn an answer was stored as to its response.
+fn add_turn_metadata(data: &mut serde_json::Value, turn: &ConversationTurn) {
+    data["conversation_id"] = serde_json::json!(turn.conversation_id);
+    data["turn"] = serde_json::json!(turn.position);
+    data["standalone_question"] = serde_json::json!(turn.standalone_question);
+}
+
+fn parse_prompt_kind(args: &serde_json::Value) -> Result<PromptKind> {
+    let kind = args.get("kind")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required field: kind"))?;
+    PromptKind::parse(kind)
+}
+
+fn parse_project_id(args: &serde_json::Value) -> Result<Option<Uuid>> {
+    match args.get("project_id").and_then(|v| v.as_str()) {
+        Some(id) => Uuid::parse_str(id)
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("Invalid project ID: {}", id)),
+        None => Ok(None),
+    }
+}
+
+/// Optional RFC 3339 `as_of` timestamp for searching past versions.
+fn parse_as_of(args: &serde_json::Value) -> Result<Option<DateTime<Utc>>> {
+    parse_timestamp(args, "as_of")
+}
+
+fn parse_timestamp(args: &serde_json::Value, field: &str) -> Result<Option<DateTime<Utc>>> {
+    match args.get(field).and_then(|v| v.as_str()) {
+        Some(value) => DateTime::parse_from_rfc3339(value)
+            .map(|date| Some(date.with_timezone(&Utc)))
+            .map_err(|_| anyhow::anyhow!("Invalid {}: expected an RFC 3339 timestamp", field)),
+        None => Ok(None),
+    }
+}
+
+/// Search options reproducing a saved search.
+fn saved_search_options(saved_search: &SavedSearch) -> Result<SearchOptions> {
+    let filters = saved_search.filters.clone().map(serde_json::from_value).transpose()?;
+    let mode = serde_json::from_value(serde_json::json!(saved_search.mode))?;
+    Ok(SearchOptions { mode, filters, ..Default::default() })
+}
+
+fn parse_search_mode(args: &serde_json::Value) -> Result<SearchMode> {
+    match args.get("mode") {
+        Some(mode) => serde_json::from_value(mode.clone())
+            .map_err(|_| anyhow::anyhow!("Invalid mode: expected \"keyword\" or \"semantic\"")),
+        None => Ok(SearchMode::Keyword),
+    }
+}
+
+fn parse_page_start(args: &serde_json::Value) -> Result<PageStart> {
+    let cursor = args.get("cursor").and_then(|v| v.as_str());
+    match (cursor, args.get("offset")) {
+        (Some(_), Some(offset)) if !offset.is_null() => {
+            Err(anyhow::anyhow!("cursor and offset cannot be combined"))
+        }
+        (Some(cursor), _) => Ok(PageStart::Cursor(Cursor::decode(cursor)?)),
+        (None, _) => Ok(PageStart::Offset(parse_bounded_u32(args, "offset", 0, 0, u32::MAX)?)),
+    }
+}
+
+fn page_offset(start: &PageStart) -> Option<u32> {
+    match start {
+        PageStart::Offset(offset) => Some(*offset),
+        PageStart::Cursor(_) => None,
+    }
+}
+
 fn parse_search_filters(filters_value: &serde_json::Value) -> Result<crate::search::SearchFilters> {
     let file_types = filters_value.get("file_types")
         .and_then(|v| v.as_array())
@@ -621,7 +2339,7 @@ fn parse_search_filters(filters_value: &serde_json::Value) -> Result<crate::sear
         .and_then(|arr| {
             let parsed_ids: Result<Vec<Uuid>, _> = arr.iter()
                 .filter_map(|v| v.as_str())
-                .map(|s| Uuid::parse_str(s))
+                .map(Uuid::parse_str)
                 .collect();
             parsed_ids.ok()
         });
@@ -633,5 +2351,6 @@ fn parse_search_filters(filters_value: &serde_json::Value) -> Result<crate::sear
         date_to: None,
         tags,
         project_ids,
+        indexed_after: None,
     })
 }
@@ -1,8 +1,13 @@
 use anyhow::Result;
-use reqwest::Client;
+use async_trait::async_trait;
+use crate::llm::{GenerationOptions, LlmProvider, ModelDetails, ModelInfo};
+use crate::resilience::{ResilienceConfig, ServerError};
+use reqwest::{Client, Response};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+pub const DEFAULT_EMBEDDING_MODEL: &str = "nomic-embed-text";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OllamaRequest {
     pub model: String,
@@ -16,6 +21,9 @@ pub struct OllamaOptions {
     pub temperature: Option<f32>,
     pub top_p: Option<f32>,
     pub max_tokens: Option<u32>,
+    /// Context window to run the model with, in tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +34,67 @@ pub struct OllamaResponse {
     pub done: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaShowRequest {
+    pub model: String,
+    pub name: String,
+}
+
+/// The parts of `/api/show` used to describe a model and find its context window.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OllamaShowResponse {
+    /// Modelfile parameters, one `name value` pair per line
+    #[serde(default)]
+    pub parameters: Option<String>,
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub modified_at: Option<String>,
+    #[serde(default)]
+    pub details: Option<OllamaModelDetails>,
+    #[serde(default)]
+    pub model_info: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OllamaModelDetails {
+    #[serde(default)]
+    pub family: Option<String>,
+    #[serde(default)]
+    pub parameter_size: Option<String>,
+    #[serde(default)]
+    pub quantization_level: Option<String>,
+}
+
+/// One entry of `/api/tags`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaModel {
+    pub name: String,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub modified_at: Option<String>,
+    #[serde(default)]
+    pub details: Option<OllamaModelDetails>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaTagsResponse {
+    #[serde(default)]
+    pub models: Vec<OllamaModel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaEmbeddingRequest {
+    pub model: String,
+    pub prompt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaEmbeddingResponse {
+    pub embedding: Vec<f32>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OllamaError {
     pub error: String,
@@ -34,27 +103,111 @@ pub struct OllamaError {
 #[derive(Clone)]
 pub struct OllamaClient {
     client: Client,
+    /// Total time a streamed generation may take
+    stream_timeout: Duration,
     base_url: String,
     model: String,
+    embedding_model: String,
 }
 
 impl OllamaClient {
     pub fn new(base_url: String, model: String) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(120))
-            .build()
-            .expect("Failed to create HTTP client");
-
+        let defaults = ResilienceConfig::default();
         Self {
-            client,
+            client: http_client(defaults.connect_timeout(), defaults.timeout()),
+            stream_timeout: defaults.stream_timeout(),
             base_url,
             model,
+            embedding_model: DEFAULT_EMBEDDING_MODEL.to_string(),
+        }
+    }
+
+    /// Give up connecting after `connect`, on requests after `total` and on
+    /// streamed generations after `stream`.
+    pub fn with_timeouts(mut self, connect: Duration, total: Duration, stream: Duration) -> Self {
+        self.client = http_client(connect, total);
+        self.stream_timeout = stream;
+        self
+    }
+
+    /// Use `model` for embeddings instead of the default embedding model.
+    pub fn with_embedding_model(mut self, model: String) -> Self {
+        self.embedding_model = model;
+        self
+    }
+
+    pub fn embedding_model(&self) -> &str {
+        &self.embedding_model
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Models pulled into Ollama (`/api/tags`).
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let response = self.client.get(format!("{}/api/tags", self.base_url)).send().await?;
+        if !response.status().is_success() {
+            return Err(ollama_error(response).await);
+        }
+        let tags: OllamaTagsResponse = response.json().await?;
+        Ok(tags.models.into_iter().map(|model| model_info(model.name, model.size, model.modified_at, model.details)).collect())
+    }
+
+    /// Details of `model` from `/api/show`, or `None` if it is not pulled.
+    /// Its context window is the `num_ctx` parameter if the Modelfile sets
+    /// one, else its trained context length capped at Ollama's default window.
+    pub async fn show_model(&self, model: &str) -> Result<Option<ModelDetails>> {
+        let request = OllamaShowRequest { model: model.to_string(), name: model.to_string() };
+        let response = self
+            .client
+            .post(format!("{}/api/show", self.base_url))
+            .json(&request)
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
         }
+        if !response.status().is_success() {
+            return Err(ollama_error(response).await);
+        }
+        let show: OllamaShowResponse = response.json().await?;
+
+        let num_ctx = show.parameters.as_deref().and_then(|parameters| {
+            parameters.lines().find_map(|line| {
+                let mut parts = line.split_whitespace();
+                (parts.next() == Some("num_ctx")).then(|| parts.next()?.parse().ok()).flatten()
+            })
+        });
+        let context_length = show.model_info.as_ref().and_then(|info| {
+            info.iter()
+                .find(|(key, _)| key.ends_with(".context_length"))
+                .and_then(|(_, value)| value.as_u64())
+                .map(|length| (length as usize).min(crate::budget::DEFAULT_CONTEXT_WINDOW))
+        });
+        Ok(Some(ModelDetails {
+            info: model_info(model.to_string(), None, show.modified_at, show.details),
+            context_window: num_ctx.or(context_length),
+            parameters: show.parameters,
+            template: show.template,
+        }))
+    }
+
+    /// The context window `model` runs with, or `None` if Ollama does not
+    /// report one (see [`show_model`](Self::show_model)).
+    pub async fn context_window(&self, model: &str) -> Result<Option<usize>> {
+        Ok(self.show_model(model).await?.and_then(|details| details.context_window))
     }
 
     pub async fn generate(&self, prompt: &str, options: Option<OllamaOptions>) -> Result<String> {
+        self.generate_with(&self.model, prompt, options).await
+    }
+
+    /// Like [`generate`](Self::generate), with `model` instead of the default.
+    pub async fn generate_with(&self, model: &str, prompt: &str, options: Option<OllamaOptions>) -> Result<String> {
         let request = OllamaRequest {
-            model: self.model.clone(),
+            model: model.to_string(),
             prompt: prompt.to_string(),
             stream: false,
             options,
@@ -62,7 +215,7 @@ impl OllamaClient {
 
         let response = self
             .client
-            .post(&format!("{}/api/generate", self.base_url))
+            .post(format!("{}/api/generate", self.base_url))
             .json(&request)
             .send()
             .await?;
@@ -71,57 +224,203 @@ impl OllamaClient {
             let ollama_response: OllamaResponse = response.json().await?;
             Ok(ollama_response.response)
         } else {
-            let error: OllamaError = response.json().await?;
-            Err(anyhow::anyhow!("Ollama error: {}", error.error))
+            Err(ollama_error(response).await)
         }
     }
 
-    pub async fn summarize(&self, content: &str, length: SummaryLength) -> Result<String> {
-        let length_instruction = match length {
-            SummaryLength::Short => "Provide a short summary (3-5 bullet points) of the following content:",
-            SummaryLength::Medium => "Provide a medium summary (1-2 paragraphs) of the following content:",
-            SummaryLength::Long => "Provide a detailed summary (4-6 paragraphs) of the following content:",
+    /// Like [`generate`](Self::generate), but reads Ollama's NDJSON stream
+    /// and hands every token to `on_token` as it arrives. Returns the full
+    /// generated text.
+    pub async fn generate_stream<F>(&self, prompt: &str, options: Option<OllamaOptions>, on_token: F) -> Result<String>
+    where
+        F: FnMut(&str),
+    {
+        self.generate_stream_with(&self.model, prompt, options, on_token).await
+    }
+
+    /// Like [`generate_stream`](Self::generate_stream), with `model` instead
+    /// of the default.
+    pub async fn generate_stream_with<F>(&self, model: &str, prompt: &str, options: Option<OllamaOptions>, mut on_token: F) -> Result<String>
+    where
+        F: FnMut(&str),
+    {
+        let request = OllamaRequest {
+            model: model.to_string(),
+            prompt: prompt.to_string(),
+            stream: true,
+            options,
         };
 
-        let prompt = format!(
-            "{}\n\nContent:\n{}\n\nSummary:",
-            length_instruction,
-            content
-        );
+        let mut response = self
+            .client
+            .post(format!("{}/api/generate", self.base_url))
+            .timeout(self.stream_timeout)
+            .json(&request)
+            .send()
+            .await?;
 
-        let options = OllamaOptions {
-            temperature: Some(0.3),
-            top_p: Some(0.9),
-            max_tokens: Some(1000),
-        };
+        if !response.status().is_success() {
+            return Err(ollama_error(response).await);
+        }
 
-        self.generate(&prompt, Some(options)).await
-    }
+        let mut text = String::new();
+        let mut buffer: Vec<u8> = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            buffer.extend_from_slice(&chunk);
+            // Lines can be split across chunks; only complete ones are parsed
+            while let Some(newline) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=newline).collect();
+                if parse_stream_line(&line, &mut text, &mut on_token)? {
+                    return Ok(text);
+                }
+            }
+        }
+        parse_stream_line(&buffer, &mut text, &mut on_token)?;
 
-    pub async fn answer_question(&self, question: &str, context: &str) -> Result<String> {
-        let prompt = format!(
-            "Based on the following context, please answer the question in Markdown format, using Hungarian language. Use proper Markdown formatting including headers, lists, code blocks, and emphasis where appropriate. If the answer cannot be found in the context, please say so clearly.\n\nContext:\n{}\n\nQuestion: {}\n\nAnswer (in Markdown):",
-            context,
-            question
-        );
+        Ok(text)
+    }
 
-        let options = OllamaOptions {
-            temperature: Some(0.2),
-            top_p: Some(0.8),
-            max_tokens: Some(1200),
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let request = OllamaEmbeddingRequest {
+            model: self.embedding_model.clone(),
+            prompt: text.to_string(),
         };
 
-        self.generate(&prompt, Some(options)).await
+        let response = self
+            .client
+            .post(format!("{}/api/embeddings", self.base_url))
+            .json(&request)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            let embedding_response: OllamaEmbeddingResponse = response.json().await?;
+            if embedding_response.embedding.is_empty() {
+                return Err(anyhow::anyhow!("Ollama returned an empty embedding"));
+            }
+            Ok(embedding_response.embedding)
+        } else {
+            Err(ollama_error(response).await)
+        }
     }
 
     pub async fn health_check(&self) -> Result<bool> {
-        match self.client.get(&format!("{}/api/tags", self.base_url)).send().await {
+        match self.client.get(format!("{}/api/tags", self.base_url)).send().await {
             Ok(response) => Ok(response.status().is_success()),
             Err(_) => Ok(false),
         }
     }
 }
 
+#[async_trait]
+impl LlmProvider for OllamaClient {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn embedding_model(&self) -> &str {
+        &self.embedding_model
+    }
+
+    async fn generate(&self, prompt: &str, options: &GenerationOptions) -> Result<String> {
+        let model = options.model.as_deref().unwrap_or(&self.model);
+        self.generate_with(model, prompt, Some(options.into())).await
+    }
+
+    async fn generate_stream(
+        &self,
+        prompt: &str,
+        options: &GenerationOptions,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<String> {
+        let model = options.model.as_deref().unwrap_or(&self.model);
+        self.generate_stream_with(model, prompt, Some(options.into()), on_token).await
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        OllamaClient::embed(self, text).await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        OllamaClient::health_check(self).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        OllamaClient::list_models(self).await
+    }
+
+    async fn show_model(&self, model: &str) -> Result<Option<ModelDetails>> {
+        OllamaClient::show_model(self, model).await
+    }
+}
+
+pub(crate) fn http_client(connect: Duration, total: Duration) -> Client {
+    Client::builder()
+        .connect_timeout(connect)
+        .timeout(total)
+        .build()
+        .expect("Failed to create HTTP client")
+}
+
+/// The error of a failed response, with Ollama's message if it sent one.
+async fn ollama_error(response: Response) -> anyhow::Error {
+    let status = response.status();
+    let message = match response.json::<OllamaError>().await {
+        Ok(error) => error.error,
+        Err(_) => status.to_string(),
+    };
+    ServerError { status: status.as_u16(), message: format!("Ollama error: {}", message) }.into()
+}
+
+fn model_info(name: String, size: Option<u64>, modified_at: Option<String>, details: Option<OllamaModelDetails>) -> ModelInfo {
+    let details = details.unwrap_or_default();
+    ModelInfo {
+        name,
+        size,
+        modified_at,
+        family: details.family,
+        parameter_size: details.parameter_size,
+        quantization: details.quantization_level,
+    }
+}
+
+impl From<&GenerationOptions> for OllamaOptions {
+    fn from(options: &GenerationOptions) -> Self {
+        Self {
+            temperature: options.temperature,
+            top_p: options.top_p,
+            max_tokens: options.max_tokens,
+            num_ctx: options.num_ctx,
+        }
+    }
+}
+
+/// Handle one NDJSON line of a streamed generation. Returns `true` once the
+/// final (`done`) line has been seen.
+fn parse_stream_line<F: FnMut(&str)>(line: &[u8], text: &mut String, on_token: &mut F) -> Result<bool> {
+    let line = std::str::from_utf8(line)?.trim();
+    if line.is_empty() {
+        return Ok(false);
+    }
+
+    let value: serde_json::Value = serde_json::from_str(line)?;
+    if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+        // The status was already 200; an error mid-stream is a server failure
+        return Err(ServerError { status: 503, message: format!("Ollama error: {}", error) }.into());
+    }
+    if let Some(token) = value.get("response").and_then(|r| r.as_str()) {
+        if !token.is_empty() {
+            text.push_str(token);
+            on_token(token);
+        }
+    }
+    Ok(value.get("done").and_then(|d| d.as_bool()).unwrap_or(false))
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum SummaryLength {
     Short,
@@ -0,0 +1,282 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use crate::llm::{GenerationOptions, LlmProvider, ModelDetails, ModelInfo};
+use crate::ollama::{http_client, DEFAULT_EMBEDDING_MODEL};
+use crate::resilience::{ResilienceConfig, ServerError};
+use reqwest::{Client, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionResponse {
+    pub choices: Vec<ChatChoice>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatChoice {
+    pub message: ChatMessage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingRequest {
+    pub model: String,
+    pub input: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingResponse {
+    pub data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingData {
+    pub embedding: Vec<f32>,
+}
+
+/// Client for servers speaking the OpenAI API: chat completions,
+/// embeddings and the model list, as served by llama.cpp, vLLM and others.
+#[derive(Clone)]
+pub struct OpenAiClient {
+    client: Client,
+    /// Total time a streamed generation may take
+    stream_timeout: Duration,
+    base_url: String,
+    model: String,
+    embedding_model: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiClient {
+    /// `base_url` includes the API version, e.g. `http://localhost:8000/v1`.
+    pub fn new(base_url: String, model: String) -> Self {
+        let defaults = ResilienceConfig::default();
+        Self {
+            client: http_client(defaults.connect_timeout(), defaults.timeout()),
+            stream_timeout: defaults.stream_timeout(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model,
+            embedding_model: DEFAULT_EMBEDDING_MODEL.to_string(),
+            api_key: None,
+        }
+    }
+
+    /// Use `model` for embeddings instead of the default embedding model.
+    pub fn with_embedding_model(mut self, model: String) -> Self {
+        self.embedding_model = model;
+        self
+    }
+
+    /// Give up connecting after `connect`, on requests after `total` and on
+    /// streamed generations after `stream`.
+    pub fn with_timeouts(mut self, connect: Duration, total: Duration, stream: Duration) -> Self {
+        self.client = http_client(connect, total);
+        self.stream_timeout = stream;
+        self
+    }
+
+    /// Send `key` as a bearer token with every request.
+    pub fn with_api_key(mut self, key: String) -> Self {
+        self.api_key = Some(key);
+        self
+    }
+
+    fn post(&self, path: &str) -> RequestBuilder {
+        self.authorize(self.client.post(format!("{}{}", self.base_url, path)))
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    fn chat_request(&self, prompt: &str, options: &GenerationOptions, stream: bool) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: options.model.clone().unwrap_or_else(|| self.model.clone()),
+            messages: vec![ChatMessage { role: "user".to_string(), content: prompt.to_string() }],
+            stream,
+            temperature: options.temperature,
+            top_p: options.top_p,
+            max_tokens: options.max_tokens,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiClient {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn embedding_model(&self) -> &str {
+        &self.embedding_model
+    }
+
+    async fn generate(&self, prompt: &str, options: &GenerationOptions) -> Result<String> {
+        let response = self
+            .post("/chat/completions")
+            .json(&self.chat_request(prompt, options, false))
+            .send()
+            .await?;
+        let completion: ChatCompletionResponse = check_status(response).await?.json().await?;
+        completion
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .ok_or_else(|| anyhow::anyhow!("OpenAI-compatible server returned no choices"))
+    }
+
+    /// Reads the server-sent events of a streamed completion: one `data:`
+    /// line per delta, ending with `data: [DONE]`.
+    async fn generate_stream(
+        &self,
+        prompt: &str,
+        options: &GenerationOptions,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<String> {
+        let response = self
+            .post("/chat/completions")
+            .timeout(self.stream_timeout)
+            .json(&self.chat_request(prompt, options, true))
+            .send()
+            .await?;
+        let mut response = check_status(response).await?;
+
+        let mut text = String::new();
+        let mut buffer: Vec<u8> = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            buffer.extend_from_slice(&chunk);
+            // Lines can be split across chunks; only complete ones are parsed
+            while let Some(newline) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=newline).collect();
+                if parse_event_line(&line, &mut text, on_token)? {
+                    return Ok(text);
+                }
+            }
+        }
+        parse_event_line(&buffer, &mut text, on_token)?;
+
+        Ok(text)
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let request = EmbeddingRequest { model: self.embedding_model.clone(), input: text.to_string() };
+        let response = self.post("/embeddings").json(&request).send().await?;
+        let embeddings: EmbeddingResponse = check_status(response).await?.json().await?;
+        match embeddings.data.into_iter().next() {
+            Some(data) if !data.embedding.is_empty() => Ok(data.embedding),
+            _ => Err(anyhow::anyhow!("OpenAI-compatible server returned an empty embedding")),
+        }
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        let request = self.authorize(self.client.get(format!("{}/models", self.base_url)));
+        match request.send().await {
+            Ok(response) => Ok(response.status().is_success()),
+            Err(_) => Ok(false),
+        }
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        Ok(self.models().await?.iter().map(openai_model_info).collect())
+    }
+
+    /// The model list entry of `model`. vLLM reports its `max_model_len`;
+    /// other servers only name their models.
+    async fn show_model(&self, model: &str) -> Result<Option<ModelDetails>> {
+        let models = self.models().await?;
+        Ok(models.iter().find(|entry| entry["id"] == model).map(|entry| ModelDetails {
+            info: openai_model_info(entry),
+            context_window: entry["max_model_len"].as_u64().map(|length| length as usize),
+            ..Default::default()
+        }))
+    }
+}
+
+impl OpenAiClient {
+    /// The `data` entries of `/models`.
+    async fn models(&self) -> Result<Vec<serde_json::Value>> {
+        let request = self.authorize(self.client.get(format!("{}/models", self.base_url)));
+        let models: serde_json::Value = check_status(request.send().await?).await?.json().await?;
+        Ok(models["data"].as_array().cloned().unwrap_or_default())
+    }
+}
+
+fn openai_model_info(entry: &serde_json::Value) -> ModelInfo {
+    ModelInfo {
+        name: entry["id"].as_str().unwrap_or_default().to_string(),
+        // `created` is a Unix timestamp
+        modified_at: entry["created"]
+            .as_i64()
+            .and_then(|created| chrono::DateTime::from_timestamp(created, 0))
+            .map(|created| created.to_rfc3339()),
+        ..Default::default()
+    }
+}
+
+/// Turn an error status into an error carrying the server's message.
+async fn check_status(response: Response) -> Result<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    let message = body["error"]["message"]
+        .as_str()
+        .or_else(|| body["error"].as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| status.to_string());
+    Err(ServerError { status: status.as_u16(), message: format!("OpenAI-compatible server error: {}", message) }.into())
+}
+
+/// Handle one line of a streamed completion. Returns `true` once the final
+/// `[DONE]` event has been seen.
+fn parse_event_line(line: &[u8], text: &mut String, on_token: &mut (dyn for<'t> FnMut(&'t str) + Send)) -> Result<bool> {
+    let line = std::str::from_utf8(line)?.trim();
+    let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+        // Comments, event names and blank separators
+        return Ok(false);
+    };
+    if data == "[DONE]" {
+        return Ok(true);
+    }
+
+    let value: serde_json::Value = serde_json::from_str(data)?;
+    if let Some(error) = value.get("error") {
+        let message = error["message"].as_str().or_else(|| error.as_str()).unwrap_or("unknown error");
+        // The status was already 200; an error mid-stream is a server failure
+        return Err(ServerError { status: 503, message: format!("OpenAI-compatible server error: {}", message) }.into());
+    }
+    if let Some(token) = value["choices"][0]["delta"]["content"].as_str() {
+        if !token.is_empty() {
+            text.push_str(token);
+            on_token(token);
+        }
+    }
+    Ok(false)
+}
@@ -0,0 +1,65 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Largest page size accepted by `list_notes` and `search_notes`.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// Position after the last item of a page. Cursors are keyed on the sort
+/// order itself rather than on an offset, so documents inserted before the
+/// position do not shift or repeat later pages.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Cursor {
+    /// Search results ordered by score (descending), then id. The query hash
+    /// covers the query with its mode, filters and versions, and rejects
+    /// cursors replayed against a different ranking. Scores include a
+    /// recency boost, so later pages are scored as of `scored_at`, the time
+    /// the first page was ranked.
+    Search { query_hash: String, score: f32, id: Uuid, scored_at: DateTime<Utc> },
+    /// Documents ordered by modification time (descending), then id
+    List { modified_at: String, id: Uuid },
+    /// A page of a `new_only` saved search run. The first page records the
+    /// run, so later pages carry the cutoff it used instead of reading the
+    /// saved search's last run time again.
+    SavedSearchRun { new_since: Option<DateTime<Utc>>, page: Box<Cursor> },
+}
+
+impl Cursor {
+    /// Encode as an opaque, URL-safe token.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).expect("cursor serializes"))
+    }
+
+    pub fn for_search(query_hash: String, score: f32, id: Uuid, scored_at: DateTime<Utc>) -> Self {
+        Cursor::Search { query_hash, score, id, scored_at }
+    }
+
+    pub fn decode(token: &str) -> Result<Self> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| anyhow::anyhow!("Invalid cursor"))?;
+        serde_json::from_slice(&bytes).map_err(|_| anyhow::anyhow!("Invalid cursor"))
+    }
+}
+
+/// Where a page starts: a plain offset or the cursor returned with the previous page.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PageStart {
+    Offset(u32),
+    Cursor(Cursor),
+}
+
+impl Default for PageStart {
+    fn default() -> Self {
+        PageStart::Offset(0)
+    }
+}
+
+/// Hash of `query` and the `scope` it ranks in, e.g. its mode and filters.
+pub fn query_hash(query: &str, scope: &impl Serialize) -> String {
+    let scope = serde_json::to_string(scope).expect("query scope serializes");
+    format!("{:x}", md5::compute(format!("{}\n{}", query.trim(), scope)))
+}
@@ -0,0 +1,281 @@
+use anyhow::Result;
+use crate::analysis::{detect_language, Language};
+use crate::database::{ConversationTurn, Database};
+use crate::ollama::SummaryLength;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const DEFAULT_ANSWER_TEMPLATE: &str = "Based on the following numbered context passages, please answer the question in Markdown format, in {language}. Use proper Markdown formatting including headers, lists, code blocks, and emphasis where appropriate. End every sentence that uses a passage with its number in square brackets, like [1] or [2, 3], and cite only the passages listed below. If the answer cannot be found in the context, please say so clearly.\n\nContext:\n{context}\n\nQuestion: {question}\n\nAnswer (in Markdown):";
+
+pub const DEFAULT_SUMMARY_TEMPLATE: &str = "Provide a {length} of the following content:\n\nContent:\n{context}\n\nSummary:";
+
+/// Prompt turning a follow-up question into one that can be searched for
+/// without the conversation; `{context}` is the recent transcript.
+pub const CONDENSE_QUESTION_TEMPLATE: &str = "Given the conversation below and a follow-up question, rewrite the follow-up question as a standalone question that can be understood without the conversation. Keep the language of the follow-up question. Reply with the rewritten question only.\n\nConversation:\n{context}\n\nFollow-up question: {question}\n\nStandalone question:";
+
+/// Prompt merging the summaries of several documents into one overview;
+/// `{context}` holds the summaries numbered by document.
+pub const COLLECTION_TEMPLATE: &str = "Below are summaries of several documents, each introduced by its number in square brackets. Write a {length} of the collection as a whole in Markdown, in {language}: start with a one-sentence overview, then group the main themes under headers and end with open questions or loose ends if there are any. End every sentence with the numbers of the documents it draws on, like [1] or [2, 3], and cite only the documents listed below.\n\nDocuments:\n{context}\n\nOverview:";
+
+/// Answers longer than this are cut in conversation transcripts.
+const TRANSCRIPT_ANSWER_CHARS: usize = 600;
+
+/// What `{length}` stands for when one section of a long document is
+/// summarized before the section summaries are merged.
+pub const SECTION_LENGTH: &str = "concise summary of one section of a longer document, keeping names, numbers and decisions";
+
+/// Placeholders a template may use.
+pub const TEMPLATE_VARIABLES: &[&str] = &["question", "context", "language", "length"];
+
+/// Language setting that follows the language of the question.
+pub const AUTO_LANGUAGE: &str = "auto";
+
+fn default_language() -> String {
+    AUTO_LANGUAGE.to_string()
+}
+
+/// `[prompts]` section of the config: templates used unless the database
+/// holds an override, and the language answers are written in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptConfig {
+    pub answer: Option<String>,
+    pub summary: Option<String>,
+    /// A language name such as "English", or "auto" to answer in the
+    /// language of the question
+    #[serde(default = "default_language")]
+    pub language: String,
+}
+
+impl Default for PromptConfig {
+    fn default() -> Self {
+        Self {
+            answer: None,
+            summary: None,
+            language: default_language(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptKind {
+    Answer,
+    Summary,
+}
+
+impl PromptKind {
+    pub const ALL: [PromptKind; 2] = [PromptKind::Answer, PromptKind::Summary];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PromptKind::Answer => "answer",
+            PromptKind::Summary => "summary",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "answer" => Ok(PromptKind::Answer),
+            "summary" => Ok(PromptKind::Summary),
+            _ => Err(anyhow::anyhow!("Invalid prompt kind: expected \"answer\" or \"summary\"")),
+        }
+    }
+
+    pub fn default_template(&self) -> &'static str {
+        match self {
+            PromptKind::Answer => DEFAULT_ANSWER_TEMPLATE,
+            PromptKind::Summary => DEFAULT_SUMMARY_TEMPLATE,
+        }
+    }
+
+    /// Placeholders without which the model would not see its input.
+    fn required_variables(&self) -> &'static [&'static str] {
+        match self {
+            PromptKind::Answer => &["question", "context"],
+            PromptKind::Summary => &["context"],
+        }
+    }
+}
+
+/// The template in effect and where it came from: `project`, `global`,
+/// `config` or `default`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedTemplate {
+    pub kind: PromptKind,
+    pub template: String,
+    pub source: String,
+    pub project_id: Option<Uuid>,
+}
+
+/// Values substituted into a template.
+#[derive(Debug, Clone, Default)]
+pub struct PromptVariables {
+    pub question: String,
+    pub context: String,
+    pub language: String,
+    pub length: String,
+}
+
+/// Substitute `{question}`, `{context}`, `{language}` and `{length}`. Other
+/// braces are left alone.
+pub fn render(template: &str, variables: &PromptVariables) -> String {
+    let mut rendered = String::with_capacity(template.len() + variables.context.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| {
+            let value = match &after[..end] {
+                "question" => &variables.question,
+                "context" => &variables.context,
+                "language" => &variables.language,
+                "length" => &variables.length,
+                _ => return None,
+            };
+            Some((value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                rendered.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Reject templates with unknown placeholders or without the input they need.
+pub fn validate(kind: PromptKind, template: &str) -> Result<()> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        if let Some(end) = after.find('}') {
+            let name = &after[..end];
+            let is_placeholder = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if is_placeholder && !TEMPLATE_VARIABLES.contains(&name) {
+                return Err(anyhow::anyhow!(
+                    "Unknown template variable {{{}}}; available: {}",
+                    name,
+                    TEMPLATE_VARIABLES.iter().map(|v| format!("{{{}}}", v)).collect::<Vec<_>>().join(", ")
+                ));
+            }
+        }
+        rest = after;
+    }
+
+    for required in kind.required_variables() {
+        if !template.contains(&format!("{{{}}}", required)) {
+            return Err(anyhow::anyhow!("A {} template must contain {{{}}}", kind.name(), required));
+        }
+    }
+    Ok(())
+}
+
+/// The language to answer `question` in for a language setting: a fixed
+/// language name, or the detected language of the question for "auto".
+pub fn answer_language(setting: &str, question: &str) -> String {
+    if !setting.trim().eq_ignore_ascii_case(AUTO_LANGUAGE) && !setting.trim().is_empty() {
+        return setting.trim().to_string();
+    }
+    match detect_language(question) {
+        Language::English => "English".to_string(),
+        Language::Hungarian => "Hungarian".to_string(),
+        Language::Unknown => "the language of the question".to_string(),
+    }
+}
+
+/// The prompt asking the model to rewrite `question` given earlier turns.
+pub fn condense_question_prompt(turns: &[ConversationTurn], question: &str) -> String {
+    let transcript = turns
+        .iter()
+        .map(|turn| {
+            let mut answer: String = turn.answer.chars().take(TRANSCRIPT_ANSWER_CHARS).collect();
+            if answer.len() < turn.answer.len() {
+                answer.push_str("...");
+            }
+            format!("User: {}\nAssistant: {}", turn.question, answer)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let variables = PromptVariables {
+        question: question.to_string(),
+        context: transcript,
+        ..Default::default()
+    };
+    render(CONDENSE_QUESTION_TEMPLATE, &variables)
+}
+
+/// What `{length}` stands for in collection overviews.
+pub fn collection_length_description(length: SummaryLength) -> &'static str {
+    match length {
+        SummaryLength::Short => "short overview (5-8 bullet points)",
+        SummaryLength::Medium => "structured overview (2-4 short sections)",
+        SummaryLength::Long => "detailed overview (one section per theme, with the key points of each document)",
+    }
+}
+
+/// What `{length}` stands for in summary templates.
+pub fn length_description(length: SummaryLength) -> &'static str {
+    match length {
+        SummaryLength::Short => "short summary (3-5 bullet points)",
+        SummaryLength::Medium => "medium summary (1-2 paragraphs)",
+        SummaryLength::Long => "detailed summary (4-6 paragraphs)",
+    }
+}
+
+/// Looks up the template in effect: a project override, then a global
+/// override, then the config, then the built-in default.
+#[derive(Clone)]
+pub struct PromptLibrary {
+    db: Database,
+    config: PromptConfig,
+}
+
+impl PromptLibrary {
+    pub fn new(db: Database, config: PromptConfig) -> Self {
+        Self { db, config }
+    }
+
+    /// Configured answer language ("auto" unless set).
+    pub fn language(&self) -> &str {
+        &self.config.language
+    }
+
+    /// The template for `kind` in the first of `project_ids` that overrides
+    /// it, falling back to the global one.
+    pub async fn resolve(&self, kind: PromptKind, project_ids: &[Uuid]) -> Result<ResolvedTemplate> {
+        for project_id in project_ids {
+            if let Some(template) = self.db.get_prompt_template(Some(project_id), kind.name()).await? {
+                return Ok(ResolvedTemplate {
+                    kind,
+                    template: template.template,
+                    source: "project".to_string(),
+                    project_id: Some(*project_id),
+                });
+            }
+        }
+
+        if let Some(template) = self.db.get_prompt_template(None, kind.name()).await? {
+            return Ok(ResolvedTemplate { kind, template: template.template, source: "global".to_string(), project_id: None });
+        }
+
+        let configured = match kind {
+            PromptKind::Answer => self.config.answer.as_ref(),
+            PromptKind::Summary => self.config.summary.as_ref(),
+        };
+        Ok(match configured {
+            Some(template) => ResolvedTemplate { kind, template: template.clone(), source: "config".to_string(), project_id: None },
+            None => ResolvedTemplate {
+                kind,
+                template: kind.default_template().to_string(),
+                source: "default".to_string(),
+                project_id: None,
+            },
+        })
+    }
+}
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// `[llm.resilience]` section of the config: how long to wait for the model
+/// server, how often to retry transient failures and when to stop trying.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResilienceConfig {
+    /// Seconds to wait for a connection to the model server
+    pub connect_timeout_secs: u64,
+    /// Seconds a request may take in total
+    pub timeout_secs: u64,
+    /// Seconds a streamed generation may take in total; tokens keep the
+    /// connection busy long after a buffered request would have timed out
+    pub stream_timeout_secs: u64,
+    /// Retries after the first attempt; 0 disables retrying
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Consecutive failed calls that open the circuit; 0 disables it
+    pub failure_threshold: u32,
+    /// Seconds an open circuit fails calls without trying the server
+    pub cooldown_secs: u64,
+}
+
+impl Default for ResilienceConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 5,
+            timeout_secs: 120,
+            stream_timeout_secs: 600,
+            max_retries: 2,
+            backoff_ms: 250,
+            max_backoff_ms: 4000,
+            failure_threshold: 5,
+            cooldown_secs: 30,
+        }
+    }
+}
+
+impl ResilienceConfig {
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout_secs)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    pub fn stream_timeout(&self) -> Duration {
+        Duration::from_secs(self.stream_timeout_secs)
+    }
+
+    /// Delay before retry number `retry` (from 0): exponential, capped, with
+    /// jitter between half and all of it so clients do not retry in step.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let delay = self.backoff_ms.saturating_mul(1 << retry.min(16)).min(self.max_backoff_ms);
+        let jitter = (uuid::Uuid::new_v4().as_u128() % 1000) as u64;
+        Duration::from_millis(delay / 2 + delay / 2 * jitter / 1000)
+    }
+}
+
+/// The model server answered with an error status.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct ServerError {
+    pub status: u16,
+    pub message: String,
+}
+
+/// The model server could not be reached, or kept failing, after retrying;
+/// or the circuit is open and it was not tried at all.
+#[derive(Debug, thiserror::Error)]
+#[error("The {provider} model server is unavailable: {reason}")]
+pub struct ModelUnavailable {
+    pub provider: String,
+    pub reason: String,
+}
+
+/// Whether `error` is a [`ModelUnavailable`] error.
+pub fn is_model_unavailable(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<ModelUnavailable>().is_some()
+}
+
+/// Whether `error` means the server is failing rather than rejecting the
+/// request: timeouts, refused or dropped connections, rate limiting and
+/// server-side errors. Client errors such as a missing model or a bad API
+/// key are not.
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(error) = cause.downcast_ref::<reqwest::Error>() {
+            return error.is_timeout() || error.is_connect() || error.is_body() || error.is_request();
+        }
+        if let Some(error) = cause.downcast_ref::<ServerError>() {
+            return matches!(error.status, 408 | 429 | 500 | 502 | 503 | 504);
+        }
+        false
+    })
+}
+
+/// Whether trying again may help: the connection failed, or the server
+/// asked to slow down or failed with a 5xx status. Requests that time out
+/// waiting for a response are not retried: the server may still be working
+/// on them, and waiting out the timeout again only delays the answer.
+pub fn is_retryable(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(error) = cause.downcast_ref::<reqwest::Error>() {
+            return error.is_connect();
+        }
+        if let Some(error) = cause.downcast_ref::<ServerError>() {
+            return matches!(error.status, 429 | 500 | 502 | 503 | 504);
+        }
+        false
+    })
+}
+
+/// State of a [`CircuitBreaker`] for health reports.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CircuitStatus {
+    /// `closed` (calls go through), `open` (calls fail fast) or `half_open`
+    /// (one call tests whether the server is back)
+    pub state: String,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_in_secs: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    /// When the call testing a half-open circuit was let through
+    trial_started: Option<Instant>,
+}
+
+/// Stops calling a model server that keeps failing: after `threshold`
+/// consecutive failures calls fail fast for `cooldown`, then one trial call
+/// is let through to test the server while the others keep failing fast.
+/// A failed trial opens the circuit again; a success closes it. A trial
+/// that has not reported back within `cooldown` is given up on, and the
+/// next call becomes the trial. Clones share their state.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    state: Arc<Mutex<BreakerState>>,
+    threshold: u32,
+    cooldown: Duration,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self { state: Arc::default(), threshold, cooldown }
+    }
+
+    /// `Err` with the time left while the circuit is open, or with zero
+    /// while another call is testing the half-open circuit.
+    pub fn check(&self) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        match state.open_until {
+            None => Ok(()),
+            Some(until) if until > now => Err(until - now),
+            Some(_) => match state.trial_started {
+                Some(started) if now.duration_since(started) < self.cooldown => Err(Duration::ZERO),
+                _ => {
+                    state.trial_started = Some(now);
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap() = BreakerState::default();
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        state.trial_started = None;
+        if self.threshold > 0 && state.consecutive_failures >= self.threshold {
+            state.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+
+    pub fn status(&self) -> CircuitStatus {
+        let state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let (name, retry_in) = match state.open_until {
+            Some(until) if until > now => ("open", Some((until - now).as_secs_f64().ceil() as u64)),
+            Some(_) => ("half_open", None),
+            None => ("closed", None),
+        };
+        CircuitStatus { state: name.to_string(), consecutive_failures: state.consecutive_failures, retry_in_secs: retry_in }
+    }
+}
@@ -1,6 +1,11 @@
 use anyhow::Result;
-use crate::database::{Database, Document, IndexEntry};
+use crate::analysis::{analyze_multilingual, detect_language, edit_distance, fold_accents, tokenize, Analyzer, Language};
+use crate::database::{Database, Document, IndexEntry, Suggestion, Versions};
+use crate::llm::LlmClient;
+use crate::pagination::{query_hash, Cursor, PageStart};
+use chrono::{DateTime, Utc};
 use regex::Regex;
+use std::sync::LazyLock;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -8,17 +13,130 @@ pub struct SearchResult {
     pub document: Document,
     pub score: f32,
     pub snippets: Vec<Snippet>,
+    /// Why the document ranked where it did; only filled in on request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<ScoreExplanation>,
 }
 
+/// Breakdown of a result's score, as returned by searches with `explain` set.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ScoreExplanation {
+    pub score: f32,
+    /// Contributions that add up to `score`, in the order they were applied
+    pub components: Vec<ScoreComponent>,
+    /// How the document passed each active filter
+    pub filters: Vec<FilterDecision>,
+    /// Chunks containing query matches (or, in semantic mode, the closest chunk)
+    pub matched_chunks: Vec<u32>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ScoreComponent {
+    /// One of `filename`, `title`, `content`, `phrase`, `recency` or `semantic`
+    pub signal: String,
+    pub detail: String,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FilterDecision {
+    pub filter: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SearchResponse {
+    pub results: Vec<SearchResult>,
+    /// Number of matches across all pages
+    pub total: usize,
+    /// Token for the page after this one, if any
+    pub next_cursor: Option<String>,
+    /// Corrected query when one or more terms had no exact hits
+    pub suggestion: Option<String>,
+    /// Set when the original query matched nothing and `results` come from the suggestion
+    pub used_suggestion: bool,
+    /// Counts over all matches, present when requested
+    pub facets: Option<SearchFacets>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SearchFacets {
+    pub file_types: Vec<FacetCount>,
+    pub tags: Vec<FacetCount>,
+    pub projects: Vec<FacetCount>,
+    pub folders: Vec<FacetCount>,
+    pub years: Vec<FacetCount>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FacetCount {
+    pub value: String,
+    /// Display name where `value` is an identifier (project names)
+    pub label: Option<String>,
+    pub count: u32,
+}
+
+/// A passage of a matching document. Positions are character offsets into
+/// the full document content.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Snippet {
+    /// Original text of the passage
     pub text: String,
     pub start_pos: usize,
     pub end_pos: usize,
+    /// `text` with matches marked up in the requested format
     pub highlighted: String,
+    pub matches: Vec<MatchRange>,
+    pub chunk_id: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightFormat {
+    /// Matches wrapped in `**`
+    #[default]
+    Markdown,
+    /// HTML-escaped text with matches wrapped in `<mark>`
+    Html,
+    /// Unmarked text; use `Snippet::matches` for the ranges
+    Raw,
+}
+
+/// A chunk selected for answering a question.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RetrievedChunk {
+    pub document: Document,
+    pub entry: IndexEntry,
+    /// Fused relevance in `0.0..=1.0`; 1.0 means ranked first by every retriever
+    pub score: f32,
+    pub lexical_rank: Option<u32>,
+    pub semantic_rank: Option<u32>,
+    /// Share of the question's keywords found in the chunk, if retrieved lexically
+    pub keyword_coverage: Option<f32>,
+    /// Cosine similarity to the question, if retrieved semantically
+    pub similarity: Option<f32>,
 }
 
+/// A neighbour returned by [`SearchEngine::find_similar`].
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SimilarDocument {
+    pub document: Document,
+    /// Combined similarity in `0.0..=1.0`
+    pub score: f32,
+    /// Short human-readable explanations, strongest evidence first
+    pub reasons: Vec<String>,
+    pub shared_tags: Vec<String>,
+    /// Shared analyzed terms, most distinctive first
+    pub shared_terms: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct SearchFilters {
     pub file_types: Option<Vec<String>>,
     pub folders: Option<Vec<String>>,
@@ -26,16 +144,108 @@ pub struct SearchFilters {
     pub date_to: Option<chrono::DateTime<chrono::Utc>>,
     pub tags: Option<Vec<String>>,
     pub project_ids: Option<Vec<uuid::Uuid>>,
+    /// Only documents indexed after this moment, e.g. since a saved search last ran
+    #[serde(default)]
+    pub indexed_after: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+pub const DEFAULT_MAX_CHUNKS_PER_DOCUMENT: u32 = 2;
+
+/// Keyword searches finding fewer documents than this get a "did you mean"
+/// suggestion; better ones are not checked for typos
+const WEAK_RESULT_COUNT: usize = 3;
+
+/// Weights of the signals combined by `find_similar`
+const TERM_WEIGHT: f32 = 0.45;
+const TAG_WEIGHT: f32 = 0.2;
+const LINK_WEIGHT: f32 = 0.15;
+const VECTOR_WEIGHT: f32 = 0.2;
+
+/// Neighbours scoring below this are not reported by `find_similar`
+const MIN_SIMILARITY: f32 = 0.05;
+
+/// Most frequent terms of a note compared by `find_similar`
+const MAX_SIMILARITY_TERMS: usize = 200;
+
+/// `[[target]]`, `[[target|label]]` and `[[target#heading]]`
+static WIKI_LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[\[([^\]|#]+)(?:[#|][^\]]*)?\]\]").unwrap());
+
+/// `[label](target "title")`
+static MARKDOWN_LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[[^\]]*\]\(([^)\s]+)[^)]*\)").unwrap());
+
+/// Rank offset of reciprocal rank fusion; dampens the weight of top ranks
+const RRF_K: f32 = 60.0;
+
+/// Token overlap above which two chunks are treated as duplicates
+const DUPLICATE_CHUNK_SIMILARITY: f32 = 0.8;
+
+/// Weight of a synonym or abbreviation relative to the term it expands
+const SYNONYM_WEIGHT: f32 = 0.8;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// Term and substring matching over titles, content and the term index
+    #[default]
+    Keyword,
+    /// Nearest-neighbour lookup over chunk embeddings
+    Semantic,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub mode: SearchMode,
+    pub filters: Option<SearchFilters>,
+    pub limit: u32,
+    pub start: PageStart,
+    pub include_historical: bool,
+    /// Search the corpus as it was at this moment: each path resolves to the
+    /// version that was latest then. Takes precedence over `include_historical`.
+    pub as_of: Option<DateTime<Utc>>,
+    pub include_facets: bool,
+    pub highlight: HighlightFormat,
+    /// Attach a [`ScoreExplanation`] to every returned result
+    pub explain: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            mode: SearchMode::Keyword,
+            filters: None,
+            limit: 20,
+            start: PageStart::default(),
+            include_historical: false,
+            as_of: None,
+            include_facets: false,
+            highlight: HighlightFormat::default(),
+            explain: false,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct SearchEngine {
     db: Database,
+    fold_accents: bool,
+    embedder: Option<LlmClient>,
 }
 
 impl SearchEngine {
     pub fn new(db: Database) -> Self {
-        Self { db }
+        Self { db, fold_accents: true, embedder: None }
+    }
+
+    /// Use the given client to embed queries for semantic search.
+    pub fn with_embeddings(mut self, client: impl Into<LlmClient>) -> Self {
+        self.embedder = Some(client.into());
+        self
+    }
+
+    /// Enable or disable accent-insensitive matching (`é` matches `e`).
+    pub fn with_accent_folding(mut self, enabled: bool) -> Self {
+        self.fold_accents = enabled;
+        self
     }
 
     pub async fn search(
@@ -45,14 +255,67 @@ impl SearchEngine {
         limit: u32,
         offset: u32,
         include_historical: bool,
+    ) -> Result<Vec<SearchResult>> {
+        let project_ids = filters.as_ref().and_then(|f| f.project_ids.clone());
+        let results = self.rank_matches(query, filters, include_historical, None, Utc::now()).await?;
+        let mut page = paginate(results, limit, offset);
+        self.attach_snippets(&mut page, query, project_ids.as_deref(), HighlightFormat::default()).await?;
+        Ok(page)
+    }
+
+    /// Like [`search`](Self::search), but also counts how the full matched
+    /// set (not just the returned page) is spread across facet values.
+    pub async fn search_with_facets(
+        &self,
+        query: &str,
+        filters: Option<SearchFilters>,
+        limit: u32,
+        offset: u32,
+        include_historical: bool,
+    ) -> Result<(Vec<SearchResult>, SearchFacets)> {
+        let project_ids = filters.as_ref().and_then(|f| f.project_ids.clone());
+        let results = self.rank_matches(query, filters, include_historical, None, Utc::now()).await?;
+        let facets = self.compute_facets(&results).await?;
+        let mut page = paginate(results, limit, offset);
+        self.attach_snippets(&mut page, query, project_ids.as_deref(), HighlightFormat::default()).await?;
+        Ok((page, facets))
+    }
+
+    /// Every document matching `query` and `filters`, best first.
+    async fn rank_matches(
+        &self,
+        query: &str,
+        filters: Option<SearchFilters>,
+        include_historical: bool,
+        as_of: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
     ) -> Result<Vec<SearchResult>> {
         // Parse query for AND/OR operations and quoted phrases
-        let parsed_query = self.parse_query(query);
-        
-        // Get all documents that match the basic text search with project filtering
-        let project_ids = filters.as_ref().and_then(|f| f.project_ids.as_ref());
-        let documents = self.db.search_documents_with_filters(query, 1000, 0, include_historical, project_ids.map(|ids| ids.as_slice())).await?;
+        let project_ids = filters.as_ref().and_then(|f| f.project_ids.as_deref());
+        let parsed_query = self.expanded_query(query, project_ids).await?;
         
+        // Get all documents that match the basic text search with project filtering,
+        // plus those whose analyzed terms match the stemmed query terms
+        // Rank the whole matched set so totals and later pages are complete.
+        let versions = Versions::new(include_historical, as_of);
+        let mut documents = self.db.search_documents_with_filters(query, u32::MAX, 0, versions, project_ids).await?;
+        let query_terms: Vec<String> = parsed_query.analyzed_terms.iter().flatten().cloned().collect();
+        let term_matches = self.db.search_documents_by_terms(&query_terms, self.fold_accents, u32::MAX, 0, versions, project_ids).await?;
+        let mut seen: std::collections::HashSet<Uuid> = documents.iter().map(|d| d.id).collect();
+        for document in term_matches {
+            if seen.insert(document.id) {
+                documents.push(document);
+            }
+        }
+        // Multi-word synonyms are looked up like the original query text
+        for (phrase, _) in parsed_query.phrases.iter().zip(&parsed_query.phrase_sources).filter(|(_, s)| s.is_some()) {
+            for document in self.db.search_documents_with_filters(phrase, u32::MAX, 0, versions, project_ids).await? {
+                if seen.insert(document.id) {
+                    documents.push(document);
+                }
+            }
+        }
+
         // Apply filters
         let filtered_documents = if let Some(filters) = filters {
             self.apply_filters(documents, &filters).await?
@@ -63,34 +326,349 @@ impl SearchEngine {
         // Score and rank results
         let mut scored_results = Vec::new();
         for document in filtered_documents {
-            let score = self.calculate_score(&document, &parsed_query);
-            let snippets = self.generate_snippets(&document, &parsed_query);
-            
+            let score = self.calculate_score(&document, &parsed_query, now);
+
+            // Snippets are only generated for the page that is returned
             scored_results.push(SearchResult {
                 document,
                 score,
+                snippets: Vec::new(),
+                explanation: None,
+            });
+        }
+
+        // Sort by score (highest first); ids break ties so the order, and
+        // with it every cursor, is stable
+        sort_results(&mut scored_results);
+
+        Ok(scored_results)
+    }
+
+    async fn compute_facets(&self, results: &[SearchResult]) -> Result<SearchFacets> {
+        let mut file_types = FacetCounter::default();
+        let mut tags = FacetCounter::default();
+        let mut projects = FacetCounter::default();
+        let mut folders = FacetCounter::default();
+        let mut years = FacetCounter::default();
+
+        for result in results {
+            let document = &result.document;
+            file_types.add(document.extension.to_lowercase());
+            for tag in &document.tags {
+                tags.add(tag.to_lowercase());
+            }
+            if let Some(project_id) = document.project_id {
+                projects.add(project_id.to_string());
+            }
+            if let Some(parent) = document.path.parent() {
+                folders.add(parent.to_string_lossy().to_string());
+            }
+            years.add(document.modified_at.format("%Y").to_string());
+        }
+
+        // Label projects with their names so filters can be offered directly
+        let project_names: std::collections::HashMap<String, String> = self.db
+            .list_projects()
+            .await?
+            .into_iter()
+            .map(|project| (project.id.to_string(), project.name))
+            .collect();
+        let mut projects = projects.into_counts();
+        for facet in &mut projects {
+            facet.label = project_names.get(&facet.value).cloned();
+        }
+
+        Ok(SearchFacets {
+            file_types: file_types.into_counts(),
+            tags: tags.into_counts(),
+            projects,
+            folders: folders.into_counts(),
+            years: years.into_counts(),
+        })
+    }
+
+    /// Rank documents by the cosine similarity between the query embedding
+    /// and their best-matching chunk.
+    pub async fn semantic_search(
+        &self,
+        query: &str,
+        filters: Option<SearchFilters>,
+        limit: u32,
+        offset: u32,
+        include_historical: bool,
+    ) -> Result<Vec<SearchResult>> {
+        let results = self.semantic_matches(query, filters, include_historical, None, HighlightFormat::default()).await?;
+        Ok(paginate(results, limit, offset))
+    }
+
+    async fn semantic_matches(
+        &self,
+        query: &str,
+        filters: Option<SearchFilters>,
+        include_historical: bool,
+        as_of: Option<DateTime<Utc>>,
+        format: HighlightFormat,
+    ) -> Result<Vec<SearchResult>> {
+        let project_ids = filters.as_ref().and_then(|f| f.project_ids.as_deref());
+        let parsed_query = self.expanded_query(query, project_ids).await?;
+        let chunks = self.semantic_chunk_scores(query, include_historical, as_of, project_ids).await?;
+
+        // Keep the best chunk per document (chunks are sorted by score)
+        let mut best_chunks: Vec<(Uuid, u32, f32)> = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        for (document_id, chunk_id, score) in chunks {
+            if seen.insert(document_id) {
+                best_chunks.push((document_id, chunk_id, score));
+            }
+        }
+
+        let mut documents = Vec::new();
+        for (document_id, _, _) in &best_chunks {
+            if let Some(document) = self.db.get_document_by_id(document_id).await? {
+                documents.push(document);
+            }
+        }
+        let documents = match &filters {
+            Some(filters) => self.apply_filters(documents, filters).await?,
+            None => documents,
+        };
+
+        let mut results = Vec::new();
+        for (document_id, chunk_id, score) in best_chunks {
+            let Some(document) = documents.iter().find(|d| d.id == document_id) else {
+                continue;
+            };
+            let snippets = match self.db.get_index_entry(&document_id, chunk_id).await? {
+                Some(entry) => vec![self.semantic_snippet(&entry, &parsed_query, format)],
+                None => Vec::new(),
+            };
+            results.push(SearchResult {
+                document: document.clone(),
+                score,
                 snippets,
+                explanation: None,
             });
         }
 
-        // Sort by score (highest first)
-        scored_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        sort_results(&mut results);
+        Ok(results)
+    }
 
-        // Apply pagination
-        let start = offset as usize;
-        let end = std::cmp::min(start + limit as usize, scored_results.len());
-        
-        Ok(scored_results[start..end].to_vec())
+    /// The `top_k` chunks closest to `query`, with their similarity scores.
+    /// Only latest versions are searched unless `as_of` picks earlier ones.
+    pub async fn semantic_chunks(
+        &self,
+        query: &str,
+        top_k: u32,
+        project_ids: Option<&[Uuid]>,
+        as_of: Option<DateTime<Utc>>,
+    ) -> Result<Vec<(Document, IndexEntry, f32)>> {
+        let chunks = self.semantic_chunk_scores(query, false, as_of, project_ids).await?;
+
+        let mut results = Vec::new();
+        for (document_id, chunk_id, score) in chunks.into_iter().take(top_k as usize) {
+            let document = self.db.get_document_by_id(&document_id).await?;
+            let entry = self.db.get_index_entry(&document_id, chunk_id).await?;
+            if let (Some(document), Some(entry)) = (document, entry) {
+                results.push((document, entry, score));
+            }
+        }
+        Ok(results)
+    }
+
+    /// Similarity of every embedded chunk to `query`, highest first.
+    async fn semantic_chunk_scores(
+        &self,
+        query: &str,
+        include_historical: bool,
+        as_of: Option<DateTime<Utc>>,
+        project_ids: Option<&[Uuid]>,
+    ) -> Result<Vec<(Uuid, u32, f32)>> {
+        let client = self
+            .embedder
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Semantic search requires an embedding model"))?;
+        let query_vector = client.embed(query).await?;
+
+        let embeddings = self
+            .db
+            .get_chunk_embeddings(client.embedding_model(), Versions::new(include_historical, as_of), project_ids)
+            .await?;
+        let mut scores: Vec<(Uuid, u32, f32)> = embeddings
+            .iter()
+            .filter(|e| e.vector.len() == query_vector.len())
+            .map(|e| (e.document_id, e.chunk_id, cosine_similarity(&query_vector, &e.vector)))
+            .collect();
+        scores.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(scores)
+    }
+
+    /// One page of results for `query` in the requested mode, with the true
+    /// number of matches and a cursor for the next page. Keyword searches
+    /// with few results get a typo-corrected suggestion, and fall back to it
+    /// when the original query returns nothing.
+    pub async fn search_page(&self, query: &str, options: &SearchOptions) -> Result<SearchResponse> {
+        let filters = options.filters.clone();
+        let project_ids = options.filters.as_ref().and_then(|f| f.project_ids.as_deref());
+        let include_historical = options.include_historical;
+        let as_of = options.as_of;
+        // Later pages are scored at the same moment as the first, so the
+        // recency boost cannot move results across the cursor
+        let now = match &options.start {
+            PageStart::Cursor(Cursor::Search { scored_at, .. }) => *scored_at,
+            _ => Utc::now(),
+        };
+        let mut suggestion = None;
+        let mut used_suggestion = false;
+        let mut matched_query = query.to_string();
+
+        let matches = match options.mode {
+            SearchMode::Keyword => {
+                let mut matches = self.rank_matches(query, filters.clone(), include_historical, as_of, now).await?;
+                if matches.len() < WEAK_RESULT_COUNT {
+                    suggestion = self.suggest_query(query).await?;
+                }
+                if matches.is_empty() {
+                    if let Some(corrected) = &suggestion {
+                        let corrected_matches = self.rank_matches(corrected, filters, include_historical, as_of, now).await?;
+                        if !corrected_matches.is_empty() {
+                            matches = corrected_matches;
+                            matched_query = corrected.clone();
+                            used_suggestion = true;
+                        }
+                    }
+                }
+                matches
+            }
+            SearchMode::Semantic => {
+                self.semantic_matches(query, filters, include_historical, as_of, options.highlight).await?
+            }
+        };
+
+        let facets = if options.include_facets {
+            Some(self.compute_facets(&matches).await?)
+        } else {
+            None
+        };
+        let total = matches.len();
+
+        // Skip everything up to and including the cursor position
+        let skip = match &options.start {
+            PageStart::Offset(offset) => *offset as usize,
+            PageStart::Cursor(Cursor::Search { query_hash: hash, score, id, .. }) => {
+                if *hash != cursor_hash(query, options) {
+                    return Err(anyhow::anyhow!("Cursor does not belong to this query"));
+                }
+                matches
+                    .iter()
+                    .take_while(|r| r.score > *score || (r.score == *score && r.document.id <= *id))
+                    .count()
+            }
+            PageStart::Cursor(_) => return Err(anyhow::anyhow!("Cursor does not belong to a search")),
+        };
+
+        let mut results: Vec<SearchResult> = matches.iter().skip(skip).take(options.limit as usize).cloned().collect();
+        if options.mode == SearchMode::Keyword {
+            self.attach_snippets(&mut results, &matched_query, project_ids, options.highlight).await?;
+        }
+        if options.explain {
+            let parsed_query = self.expanded_query(&matched_query, project_ids).await?;
+            for result in results.iter_mut() {
+                result.explanation = Some(self.explain(result, &parsed_query, options, now).await?);
+            }
+        }
+        let next_cursor = match results.last() {
+            Some(last) if skip + results.len() < total => {
+                Some(Cursor::for_search(cursor_hash(query, options), last.score, last.document.id, now).encode())
+            }
+            _ => None,
+        };
+
+        Ok(SearchResponse {
+            results,
+            total,
+            next_cursor,
+            suggestion,
+            used_suggestion,
+            facets,
+        })
+    }
+
+    /// Build a "did you mean" query by replacing every term without exact
+    /// hits by its closest vocabulary entry. Returns `None` if nothing changed.
+    async fn suggest_query(&self, query: &str) -> Result<Option<String>> {
+        let parsed_query = self.parse_query(query);
+        let mut corrected_terms = Vec::new();
+        let mut changed = false;
+
+        for (term, variants) in parsed_query.terms.iter().zip(&parsed_query.analyzed_terms) {
+            let correctable = term.chars().count() >= 3
+                && !term.chars().any(|c| c.is_ascii_digit())
+                && !variants.is_empty();
+            if !correctable || self.term_has_hits(term, variants).await? {
+                corrected_terms.push(term.clone());
+                continue;
+            }
+
+            match self.closest_vocabulary_term(term).await? {
+                Some(replacement) => {
+                    corrected_terms.push(replacement);
+                    changed = true;
+                }
+                None => corrected_terms.push(term.clone()),
+            }
+        }
+
+        if !changed {
+            return Ok(None);
+        }
+
+        let mut suggestion = corrected_terms.join(" ");
+        for phrase in &parsed_query.phrases {
+            suggestion.push_str(&format!(" \"{}\"", phrase));
+        }
+        Ok(Some(suggestion.trim().to_string()))
+    }
+
+    async fn term_has_hits(&self, term: &str, variants: &[String]) -> Result<bool> {
+        if self.db.vocabulary_contains(term, self.fold_accents).await? {
+            return Ok(true);
+        }
+        let matches = self.db.search_documents_by_terms(variants, self.fold_accents, 1, 0, Versions::Latest, None).await?;
+        Ok(!matches.is_empty())
+    }
+
+    /// The most frequent vocabulary term within a few edits of `term`.
+    /// Like most spell checkers, it trusts the first letter.
+    async fn closest_vocabulary_term(&self, term: &str) -> Result<Option<String>> {
+        let Some(first) = term.chars().next() else {
+            return Ok(None);
+        };
+        let length = term.chars().count();
+        // Allow one edit for short words, two for longer ones
+        let max_distance = if length <= 4 { 1 } else { 2 };
+        let candidates = self.db
+            .get_vocabulary_candidates(first, length.saturating_sub(max_distance), length + max_distance)
+            .await?;
+
+        let normalized_term = self.normalize_text(term);
+        let best = candidates
+            .into_iter()
+            .filter_map(|candidate| {
+                let distance = edit_distance(&normalized_term, &self.normalize_text(&candidate.term));
+                (distance <= max_distance).then_some((distance, candidate))
+            })
+            .min_by(|(da, a), (db, b)| da.cmp(db).then(b.frequency.cmp(&a.frequency)).then(a.term.cmp(&b.term)));
+
+        Ok(best.map(|(_, candidate)| candidate.term))
     }
 
     fn parse_query(&self, query: &str) -> ParsedQuery {
         let mut terms = Vec::new();
         let mut phrases = Vec::new();
-        let mut operators = Vec::new();
 
-        // Simple query parsing - look for quoted phrases and AND/OR operators
+        // Simple query parsing - look for quoted phrases and skip AND/OR operators
         let re_quotes = Regex::new(r#""([^"]+)""#).unwrap();
-        let re_operators = Regex::new(r"\b(AND|OR)\b").unwrap();
 
         // Extract quoted phrases
         for cap in re_quotes.captures_iter(query) {
@@ -103,11 +681,6 @@ impl SearchEngine {
             clean_query = clean_query.replace(&format!("\"{}\"", phrase), "");
         }
 
-        // Extract operators
-        for cap in re_operators.captures_iter(&clean_query) {
-            operators.push(cap[1].to_string());
-        }
-
         // Extract individual terms
         for term in clean_query.split_whitespace() {
             if !term.eq_ignore_ascii_case("AND") && !term.eq_ignore_ascii_case("OR") {
@@ -115,10 +688,125 @@ impl SearchEngine {
             }
         }
 
+        // Stem each term with every analyzer since short queries rarely
+        // carry enough signal for language detection
+        let analyzed_terms = terms
+            .iter()
+            .map(|term| analyze_multilingual(term, self.fold_accents))
+            .collect();
+
         ParsedQuery {
+            term_sources: vec![None; terms.len()],
+            phrase_sources: vec![None; phrases.len()],
             terms,
             phrases,
-            operators,
+            analyzed_terms,
+        }
+    }
+
+    /// [`parse_query`](Self::parse_query) plus the synonyms that apply to
+    /// searches in `project_ids`.
+    async fn expanded_query(&self, query: &str, project_ids: Option<&[Uuid]>) -> Result<ParsedQuery> {
+        let mut parsed_query = self.parse_query(query);
+        let dictionary = self.synonym_dictionary(project_ids).await?;
+        if dictionary.is_empty() {
+            return Ok(parsed_query);
+        }
+
+        let mut seen: std::collections::HashSet<String> = parsed_query
+            .terms
+            .iter()
+            .chain(&parsed_query.phrases)
+            .map(|t| self.normalize_text(t))
+            .collect();
+        for (source, expansions) in self.synonym_expansions(query, &parsed_query.terms, &parsed_query.phrases, &dictionary) {
+            for expansion in expansions {
+                if !seen.insert(self.normalize_text(&expansion)) {
+                    continue;
+                }
+                if expansion.contains(char::is_whitespace) {
+                    parsed_query.phrases.push(expansion);
+                    parsed_query.phrase_sources.push(Some(source.clone()));
+                } else {
+                    let term = expansion.to_lowercase();
+                    parsed_query.analyzed_terms.push(analyze_multilingual(&term, self.fold_accents));
+                    parsed_query.terms.push(term);
+                    parsed_query.term_sources.push(Some(source.clone()));
+                }
+            }
+        }
+
+        Ok(parsed_query)
+    }
+
+    /// Normalized dictionary term -> its equivalents. Entries are symmetric:
+    /// `k8s => kubernetes` also expands `kubernetes` to `k8s`.
+    async fn synonym_dictionary(&self, project_ids: Option<&[Uuid]>) -> Result<std::collections::HashMap<String, Vec<String>>> {
+        let mut dictionary: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
+        for entry in self.db.get_synonyms(project_ids).await? {
+            let group: Vec<&String> = std::iter::once(&entry.term).chain(&entry.synonyms).collect();
+            for member in &group {
+                let equivalents = dictionary.entry(self.normalize_text(member)).or_default();
+                for other in &group {
+                    let other = other.trim();
+                    if other.eq_ignore_ascii_case(member.trim()) || other.is_empty() {
+                        continue;
+                    }
+                    if !equivalents.iter().any(|e| e.eq_ignore_ascii_case(other)) {
+                        equivalents.push(other.to_string());
+                    }
+                }
+            }
+        }
+        Ok(dictionary)
+    }
+
+    /// Typed words and phrases with dictionary entries, paired with their
+    /// equivalents. Multi-word entries also match unquoted in `text`.
+    fn synonym_expansions(
+        &self,
+        text: &str,
+        terms: &[String],
+        phrases: &[String],
+        dictionary: &std::collections::HashMap<String, Vec<String>>,
+    ) -> Vec<(String, Vec<String>)> {
+        let mut expansions = Vec::new();
+        for source in terms.iter().chain(phrases) {
+            if let Some(equivalents) = dictionary.get(&self.normalize_text(source)) {
+                expansions.push((source.clone(), equivalents.clone()));
+            }
+        }
+
+        let words = tokenize(&self.normalize_text(text)).join(" ");
+        let padded = format!(" {} ", words);
+        let mut multi_word: Vec<(&String, &Vec<String>)> = dictionary
+            .iter()
+            .filter(|(key, _)| key.contains(' ') && padded.contains(&format!(" {} ", tokenize(key).join(" "))))
+            .filter(|(key, _)| !phrases.iter().any(|p| &self.normalize_text(p) == *key))
+            .collect();
+        multi_word.sort();
+        for (key, equivalents) in multi_word {
+            expansions.push((key.clone(), equivalents.clone()));
+        }
+        expansions
+    }
+
+    /// Lowercase and, if enabled, accent-fold text for substring comparisons.
+    fn normalize_text(&self, text: &str) -> String {
+        let lower = text.to_lowercase();
+        if self.fold_accents {
+            fold_accents(&lower)
+        } else {
+            lower
+        }
+    }
+
+    /// Analyze document text with the analyzer for the document's language.
+    fn analyze_document_text(&self, document: &Document, text: &str) -> Vec<String> {
+        let language = Language::from_code(document.language.as_deref().unwrap_or_default());
+        match language {
+            Language::Unknown => analyze_multilingual(text, self.fold_accents),
+            language => Analyzer::new(language, self.fold_accents).analyze(text),
         }
     }
 
@@ -150,6 +838,9 @@ impl SearchEngine {
         if let Some(date_to) = filters.date_to {
             filtered.retain(|doc| doc.modified_at <= date_to);
         }
+        if let Some(indexed_after) = filters.indexed_after {
+            filtered.retain(|doc| doc.indexed_at > indexed_after);
+        }
 
         // Filter by tags
         if let Some(tags) = &filters.tags {
@@ -163,270 +854,909 @@ impl SearchEngine {
         Ok(filtered)
     }
 
-    fn calculate_score(&self, document: &Document, query: &ParsedQuery) -> f32 {
-        let mut score = 0.0;
+    fn calculate_score(&self, document: &Document, query: &ParsedQuery, now: DateTime<Utc>) -> f32 {
+        self.score_components(document, query, now).iter().map(|c| c.score).sum()
+    }
+
+    /// The individual contributions making up [`calculate_score`](Self::calculate_score).
+    fn score_components(&self, document: &Document, query: &ParsedQuery, now: DateTime<Utc>) -> Vec<ScoreComponent> {
+        let mut components = Vec::new();
+        let mut add = |signal: &str, detail: String, score: f32| {
+            components.push(ScoreComponent { signal: signal.to_string(), detail, score });
+        };
 
         // Score based on filename matches
-        let filename_lower = document.filename.to_lowercase();
-        for term in &query.terms {
-            if filename_lower.contains(term) {
-                score += 2.0; // Higher weight for filename matches
+        let filename_lower = self.normalize_text(&document.filename);
+        for (i, term) in query.terms.iter().enumerate() {
+            if filename_lower.contains(&self.normalize_text(term)) {
+                // Higher weight for filename matches
+                add("filename", format!("{} in filename", query.describe_term(i)), 2.0 * query.term_weight(i));
             }
         }
 
         // Score based on title matches
         if let Some(title) = &document.title {
-            let title_lower = title.to_lowercase();
-            for term in &query.terms {
-                if title_lower.contains(term) {
-                    score += 1.5;
+            let title_terms = self.analyze_document_text(document, title);
+            for (i, variants) in query.analyzed_terms.iter().enumerate() {
+                if variants.iter().any(|v| title_terms.contains(v)) {
+                    add("title", format!("{} in title", query.describe_term(i)), 1.5 * query.term_weight(i));
                 }
             }
         }
 
         // Score based on content matches
-        let content_lower = document.content_excerpt.to_lowercase();
-        for term in &query.terms {
-            let matches = content_lower.matches(term).count();
-            score += matches as f32 * 0.5;
+        let content_terms = self.analyze_document_text(document, &document.content_excerpt);
+        for (i, variants) in query.analyzed_terms.iter().enumerate() {
+            let matches = content_terms.iter().filter(|t| variants.contains(t)).count();
+            if matches > 0 {
+                add(
+                    "content",
+                    format!("{} {} time(s) in content", query.describe_term(i), matches),
+                    matches as f32 * 0.5 * query.term_weight(i),
+                );
+            }
         }
 
         // Score based on phrase matches
-        for phrase in &query.phrases {
-            let phrase_lower = phrase.to_lowercase();
+        let content_lower = self.normalize_text(&document.content_excerpt);
+        for (i, phrase) in query.phrases.iter().enumerate() {
+            let phrase_lower = self.normalize_text(phrase);
             if content_lower.contains(&phrase_lower) {
-                score += 3.0; // Higher weight for exact phrase matches
+                // Higher weight for exact phrase matches
+                add("phrase", format!("phrase {} in content", query.describe_phrase(i)), 3.0 * query.phrase_weight(i));
             }
         }
 
         // Boost score for recent documents
-        let days_old = chrono::Utc::now().signed_duration_since(document.modified_at).num_days();
+        let days_old = now.signed_duration_since(document.modified_at).num_days();
         if days_old < 30 {
-            score += 0.5;
+            add("recency", format!("modified {} day(s) ago", days_old), 0.5);
         } else if days_old < 90 {
-            score += 0.2;
+            add("recency", format!("modified {} day(s) ago", days_old), 0.2);
         }
 
-        score
+        components
     }
 
-    fn generate_snippets(&self, document: &Document, query: &ParsedQuery) -> Vec<Snippet> {
-        let mut snippets = Vec::new();
-        let content = &document.content_excerpt;
-        let content_lower = content.to_lowercase();
+    /// Score breakdown, filter decisions and matched chunks of one result.
+    async fn explain(&self, result: &SearchResult, query: &ParsedQuery, options: &SearchOptions, now: DateTime<Utc>) -> Result<ScoreExplanation> {
+        let document = &result.document;
+        let (components, matched_chunks) = match options.mode {
+            SearchMode::Keyword => {
+                let entries = self.db.get_index_entries_for_document(&document.id).await?;
+                let matched_chunks = entries
+                    .iter()
+                    .filter(|entry| !self.find_matches(&entry.chunk_text, query).is_empty())
+                    .map(|entry| entry.chunk_id)
+                    .collect();
+                (self.score_components(document, query, now), matched_chunks)
+            }
+            SearchMode::Semantic => {
+                let chunk_ids: Vec<u32> = result.snippets.iter().map(|s| s.chunk_id).collect();
+                let component = ScoreComponent {
+                    signal: "semantic".to_string(),
+                    detail: format!("cosine similarity of closest chunk {:?}", chunk_ids),
+                    score: result.score,
+                };
+                (vec![component], chunk_ids)
+            }
+        };
 
-        // Find matches for terms and phrases
-        let mut match_positions = Vec::new();
-        
-        for term in &query.terms {
-            let term_lower = term.to_lowercase();
-            let mut start = 0;
-            while let Some(pos) = content_lower[start..].find(&term_lower) {
-                let actual_pos = start + pos;
-                match_positions.push((actual_pos, actual_pos + term.len()));
-                start = actual_pos + 1;
+        let mut filters = Vec::new();
+        let mut decide = |filter: &str, detail: String| {
+            filters.push(FilterDecision { filter: filter.to_string(), detail });
+        };
+        if let Some(as_of) = options.as_of {
+            decide("as_of", format!("version {} was latest at {}", document.version, as_of.to_rfc3339()));
+        } else if options.include_historical {
+            decide("version", format!("historical versions included; this is version {}", document.version));
+        } else {
+            decide("version", format!("latest version ({})", document.version));
+        }
+        if let Some(search_filters) = &options.filters {
+            if let Some(file_types) = &search_filters.file_types {
+                decide("file_types", format!("extension \"{}\" is one of {:?}", document.extension, file_types));
+            }
+            if let Some(folders) = &search_filters.folders {
+                let path = document.path.to_string_lossy();
+                let folder = folders.iter().find(|f| path.contains(f.as_str())).cloned().unwrap_or_default();
+                decide("folders", format!("path is inside \"{}\"", folder));
+            }
+            if search_filters.date_from.is_some() || search_filters.date_to.is_some() {
+                decide("date", format!("modified {} is within the date range", document.modified_at.to_rfc3339()));
+            }
+            if let Some(indexed_after) = search_filters.indexed_after {
+                decide("indexed_after", format!("indexed {} is after {}", document.indexed_at.to_rfc3339(), indexed_after.to_rfc3339()));
+            }
+            if let Some(tags) = &search_filters.tags {
+                let matched: Vec<&String> = document
+                    .tags
+                    .iter()
+                    .filter(|tag| tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+                    .collect();
+                decide("tags", format!("has tag(s) {:?}", matched));
+            }
+            if search_filters.project_ids.is_some() {
+                let project = document.project_id.map(|id| id.to_string()).unwrap_or_default();
+                decide("project_ids", format!("belongs to project {}", project));
             }
         }
 
-        for phrase in &query.phrases {
-            let phrase_lower = phrase.to_lowercase();
-            let mut start = 0;
-            while let Some(pos) = content_lower[start..].find(&phrase_lower) {
-                let actual_pos = start + pos;
-                match_positions.push((actual_pos, actual_pos + phrase.len()));
-                start = actual_pos + 1;
-            }
-        }
-
-        // Sort positions and merge overlapping ones
-        match_positions.sort_by_key(|(start, _)| *start);
-        let mut merged_positions = Vec::new();
-        for (start, end) in match_positions {
-            if let Some((_last_start, last_end)) = merged_positions.last_mut() {
-                if start <= *last_end + 50 { // Merge if within 50 characters
-                    *last_end = end;
-                } else {
-                    merged_positions.push((start, end));
-                }
-            } else {
-                merged_positions.push((start, end));
-            }
-        }
-
-        // Generate snippets around matches
-        for (start, end) in merged_positions {
-            let snippet_start = start.saturating_sub(100);
-            let snippet_end = std::cmp::min(end + 100, content.len());
-            
-            // Ensure we don't split in the middle of a UTF-8 character
-            let mut actual_start = snippet_start;
-            while actual_start < content.len() && !content.is_char_boundary(actual_start) {
-                actual_start += 1;
-            }
-            
-            let mut actual_end = snippet_end;
-            while actual_end > actual_start && !content.is_char_boundary(actual_end) {
-                actual_end -= 1;
-            }
-            
-            let snippet_text = content[actual_start..actual_end].to_string();
-            let highlighted = self.highlight_matches(&snippet_text, query);
-
-            snippets.push(Snippet {
-                text: snippet_text,
-                start_pos: actual_start,
-                end_pos: actual_end,
-                highlighted,
+        Ok(ScoreExplanation {
+            score: result.score,
+            components,
+            filters,
+            matched_chunks,
+        })
+    }
+
+    /// Fill in snippets for a page of results from their best-matching chunks.
+    async fn attach_snippets(
+        &self,
+        results: &mut [SearchResult],
+        query: &str,
+        project_ids: Option<&[Uuid]>,
+        format: HighlightFormat,
+    ) -> Result<()> {
+        let parsed_query = self.expanded_query(query, project_ids).await?;
+        for result in results.iter_mut() {
+            result.snippets = self.generate_snippets(&result.document, &parsed_query, format).await?;
+        }
+        Ok(())
+    }
+
+    async fn generate_snippets(
+        &self,
+        document: &Document,
+        query: &ParsedQuery,
+        format: HighlightFormat,
+    ) -> Result<Vec<Snippet>> {
+        let mut entries = self.db.get_index_entries_for_document(&document.id).await?;
+        if entries.is_empty() {
+            // Documents without chunks still have their excerpt
+            entries.push(IndexEntry {
+                id: document.id,
+                document_id: document.id,
+                chunk_id: 0,
+                chunk_text: document.content_excerpt.clone(),
+                positions: Vec::new(),
+                start_offset: 0,
             });
+        }
 
-            // Limit to 2 snippets per document
-            if snippets.len() >= 2 {
-                break;
+        // Chunks with the most matches first
+        let mut chunk_matches: Vec<(&IndexEntry, Vec<(usize, usize)>)> = entries
+            .iter()
+            .map(|entry| (entry, self.find_matches(&entry.chunk_text, query)))
+            .filter(|(_, matches)| !matches.is_empty())
+            .collect();
+        chunk_matches.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.chunk_id.cmp(&b.0.chunk_id)));
+
+        let mut snippets: Vec<Snippet> = Vec::new();
+        for (entry, matches) in chunk_matches {
+            for (window_start, window_end) in snippet_windows(&entry.chunk_text, &matches) {
+                let snippet = build_snippet(entry, window_start, window_end, &matches, format);
+                // Neighbouring chunks overlap, so the same passage can come up twice
+                let overlaps = snippets
+                    .iter()
+                    .any(|other| snippet.start_pos < other.end_pos && other.start_pos < snippet.end_pos);
+                if !overlaps {
+                    snippets.push(snippet);
+                }
+
+                // Limit to 2 snippets per document
+                if snippets.len() >= 2 {
+                    return Ok(snippets);
+                }
             }
         }
 
-        snippets
+        Ok(snippets)
     }
 
-    fn highlight_matches(&self, text: &str, query: &ParsedQuery) -> String {
-        let mut highlighted = text.to_string();
-        
-        // Highlight terms
-        for term in &query.terms {
-            let re = Regex::new(&format!(r"(?i)\b{}\b", regex::escape(term))).unwrap();
-            highlighted = re.replace_all(&highlighted, format!("**{}**", term)).to_string();
+    /// Snippet for a chunk found by meaning: centered on literal matches if
+    /// there are any, otherwise the start of the chunk.
+    fn semantic_snippet(&self, entry: &IndexEntry, query: &ParsedQuery, format: HighlightFormat) -> Snippet {
+        let matches = self.find_matches(&entry.chunk_text, query);
+        let (window_start, window_end) = snippet_windows(&entry.chunk_text, &matches)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| (0, floor_char_boundary(&entry.chunk_text, 200)));
+        build_snippet(entry, window_start, window_end, &matches, format)
+    }
+
+    /// Byte ranges in `text` of words matching a query term (directly or by
+    /// stem) and of quoted phrases, sorted and merged.
+    fn find_matches(&self, text: &str, query: &ParsedQuery) -> Vec<(usize, usize)> {
+        let terms: std::collections::HashSet<String> = query.terms.iter().map(|t| self.normalize_text(t)).collect();
+        let variants: std::collections::HashSet<&String> = query.analyzed_terms.iter().flatten().collect();
+        let mut ranges = Vec::new();
+
+        if !terms.is_empty() {
+            let mut word_start = None;
+            for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+                if c.is_alphanumeric() {
+                    word_start.get_or_insert(i);
+                } else if let Some(start) = word_start.take() {
+                    let word = &text[start..i];
+                    let matched = terms.contains(&self.normalize_text(word))
+                        || analyze_multilingual(word, self.fold_accents).iter().any(|v| variants.contains(v));
+                    if matched {
+                        ranges.push((start, i));
+                    }
+                }
+            }
         }
 
-        // Highlight phrases
         for phrase in &query.phrases {
             let re = Regex::new(&format!(r"(?i){}", regex::escape(phrase))).unwrap();
-            highlighted = re.replace_all(&highlighted, format!("**{}**", phrase)).to_string();
+            ranges.extend(re.find_iter(text).map(|m| (m.start(), m.end())));
         }
 
-        highlighted
+        ranges.sort();
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
+    }
+
+    /// Completions for search box input. The whole input is completed first;
+    /// for several words the last one is also completed on its own and the
+    /// preceding words are kept in front of it.
+    pub async fn suggest(&self, input: &str, project_ids: Option<&[Uuid]>, limit: u32) -> Result<Vec<Suggestion>> {
+        let input = input.trim_start();
+        let mut suggestions = self.db.suggest(input, limit, project_ids).await?;
+
+        if let Some(split) = input.rfind(char::is_whitespace) {
+            let (head, last) = (input[..split].trim_end(), input[split..].trim());
+            if !last.is_empty() && (suggestions.len() as u32) < limit {
+                for suggestion in self.db.suggest(last, limit, project_ids).await? {
+                    let text = format!("{} {}", head, suggestion.text);
+                    if !suggestions.iter().any(|s| s.text.eq_ignore_ascii_case(&text)) {
+                        suggestions.push(Suggestion { text, ..suggestion });
+                    }
+                }
+            }
+        }
+
+        suggestions.truncate(limit as usize);
+        Ok(suggestions)
+    }
+
+    /// Documents most similar to `document_id`, combining shared terms
+    /// (IDF-weighted), shared tags, links between or shared by the notes and,
+    /// when embeddings exist, vector similarity.
+    pub async fn find_similar(
+        &self,
+        document_id: &Uuid,
+        project_ids: Option<&[Uuid]>,
+        limit: u32,
+    ) -> Result<Vec<SimilarDocument>> {
+        let source = self.db
+            .get_document_by_id(document_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Document not found"))?;
+
+        // Terms: the share of the source's IDF mass found in the candidate
+        let source_terms: Vec<String> = self.db
+            .get_document_term_frequencies(document_id)
+            .await?
+            .into_iter()
+            .take(MAX_SIMILARITY_TERMS)
+            .map(|(term, _)| term)
+            .collect();
+        let total_documents = self.db.count_latest_documents().await?.max(1) as f32;
+        let idf: std::collections::HashMap<String, f32> = self.db
+            .get_term_document_counts(&source_terms)
+            .await?
+            .into_iter()
+            .map(|(term, count)| (term, (1.0 + total_documents / count.max(1) as f32).ln()))
+            .collect();
+        let source_mass: f32 = idf.values().sum();
+        let mut shared_terms: std::collections::HashMap<Uuid, Vec<String>> = std::collections::HashMap::new();
+        for (candidate_id, term) in self.db.find_documents_sharing_terms(&source_terms, document_id, project_ids).await? {
+            shared_terms.entry(candidate_id).or_default().push(term);
+        }
+
+        let source_tags: std::collections::HashSet<String> = source.tags.iter().map(|t| t.to_lowercase()).collect();
+        let source_links = self.document_links(&source).await?;
+
+        // Mean chunk vector per document, if an embedding model is configured
+        let mut vectors: std::collections::HashMap<Uuid, Vec<f32>> = std::collections::HashMap::new();
+        if let Some(client) = &self.embedder {
+            let mut sums: std::collections::HashMap<Uuid, (Vec<f32>, usize)> = std::collections::HashMap::new();
+            for embedding in self.db.get_chunk_embeddings(client.embedding_model(), Versions::Latest, project_ids).await? {
+                let (sum, count) = sums
+                    .entry(embedding.document_id)
+                    .or_insert_with(|| (vec![0.0; embedding.vector.len()], 0));
+                if sum.len() == embedding.vector.len() {
+                    sum.iter_mut().zip(&embedding.vector).for_each(|(s, v)| *s += v);
+                    *count += 1;
+                }
+            }
+            vectors = sums
+                .into_iter()
+                .map(|(id, (sum, count))| (id, sum.into_iter().map(|v| v / count.max(1) as f32).collect()))
+                .collect();
+        }
+        let source_vector = vectors.get(document_id).cloned();
+
+        // Signals the source note has no data for would only drag scores down
+        let mut total_weight = TERM_WEIGHT;
+        if !source_tags.is_empty() {
+            total_weight += TAG_WEIGHT;
+        }
+        if !source_links.is_empty() {
+            total_weight += LINK_WEIGHT;
+        }
+        if source_vector.is_some() {
+            total_weight += VECTOR_WEIGHT;
+        }
+
+        // Only notes sharing a term or tag with the source, or mentioning it
+        // or one of its link targets, can score above zero on those signals
+        let related_ids: Vec<Uuid> = shared_terms.keys().copied().collect();
+        let tag_list: Vec<String> = source_tags.iter().cloned().collect();
+        let mut mentions: Vec<String> = source_links.iter().filter(|l| !l.is_empty()).cloned().collect();
+        mentions.push(link_key(&source.filename));
+        let candidates = self.db
+            .find_related_documents(&source.id, &related_ids, &tag_list, &mentions, project_ids)
+            .await?;
+        let mut similar = Vec::new();
+        for candidate in candidates {
+            if candidate.id == source.id || candidate.path == source.path {
+                continue;
+            }
+
+            let mut terms = shared_terms.remove(&candidate.id).unwrap_or_default();
+            terms.sort_by(|a, b| {
+                let weight = |t: &String| idf.get(t).copied().unwrap_or(0.0);
+                weight(b).partial_cmp(&weight(a)).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.cmp(b))
+            });
+            let term_score = if source_mass > 0.0 {
+                terms.iter().filter_map(|t| idf.get(t)).sum::<f32>() / source_mass
+            } else {
+                0.0
+            };
+
+            let candidate_tags: std::collections::HashSet<String> = candidate.tags.iter().map(|t| t.to_lowercase()).collect();
+            let mut tags: Vec<String> = source_tags.intersection(&candidate_tags).cloned().collect();
+            tags.sort();
+            let tag_score = jaccard(&source_tags, &candidate_tags);
+            let tag_score = if source_tags.is_empty() { 0.0 } else { tag_score };
+
+            let candidate_links = self.document_links(&candidate).await?;
+            let links_to = source_links.contains(&link_key(&candidate.filename));
+            let linked_from = candidate_links.contains(&link_key(&source.filename));
+            let shared_link_count = source_links.intersection(&candidate_links).count();
+            let link_score = if links_to || linked_from {
+                1.0
+            } else if source_links.is_empty() {
+                0.0
+            } else {
+                jaccard(&source_links, &candidate_links)
+            };
+
+            let vector_score = match (&source_vector, vectors.get(&candidate.id)) {
+                (Some(a), Some(b)) if a.len() == b.len() => Some(cosine_similarity(a, b).max(0.0)),
+                _ => None,
+            };
+
+            let score = (TERM_WEIGHT * term_score
+                + TAG_WEIGHT * tag_score
+                + LINK_WEIGHT * link_score
+                + VECTOR_WEIGHT * vector_score.unwrap_or(0.0))
+                / total_weight;
+            if score < MIN_SIMILARITY {
+                continue;
+            }
+
+            let mut reasons = Vec::new();
+            if !tags.is_empty() {
+                reasons.push(format!("shared tags: {}", tags.join(", ")));
+            }
+            if !terms.is_empty() {
+                reasons.push(format!("shared terms: {}", terms.iter().take(5).cloned().collect::<Vec<_>>().join(", ")));
+            }
+            if links_to {
+                reasons.push("linked from this note".to_string());
+            }
+            if linked_from {
+                reasons.push("links to this note".to_string());
+            }
+            if shared_link_count > 0 {
+                reasons.push(format!("{} shared link(s)", shared_link_count));
+            }
+            if let Some(vector_score) = vector_score.filter(|s| *s >= 0.5) {
+                reasons.push(format!("similar content ({:.2})", vector_score));
+            }
+
+            terms.truncate(10);
+            similar.push(SimilarDocument {
+                document: candidate,
+                score,
+                reasons,
+                shared_tags: tags,
+                shared_terms: terms,
+            });
+        }
+
+        similar.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.document.id.cmp(&b.document.id))
+        });
+        similar.truncate(limit as usize);
+        Ok(similar)
+    }
+
+    /// Normalized targets of the Markdown and wiki links in a document.
+    async fn document_links(&self, document: &Document) -> Result<std::collections::HashSet<String>> {
+        let content = match self.db.get_document_snapshot(&document.id).await? {
+            Some(content) => content,
+            None => document.content_excerpt.clone(),
+        };
+
+        let mut links: std::collections::HashSet<String> = WIKI_LINK
+            .captures_iter(&content)
+            .map(|cap| link_key(&cap[1]))
+            .collect();
+        for cap in MARKDOWN_LINK.captures_iter(&content) {
+            let target = &cap[1];
+            if target.starts_with('#') {
+                continue;
+            }
+            if target.contains("://") || target.starts_with("mailto:") {
+                links.insert(target.trim_end_matches('/').to_lowercase());
+            } else {
+                let path = target.split('#').next().unwrap_or(target);
+                links.insert(link_key(&path.replace("%20", " ")));
+            }
+        }
+        Ok(links)
     }
 
     pub async fn get_relevant_chunks_for_qa(
         &self,
         question: &str,
         top_k: u32,
-    ) -> Result<Vec<(Document, IndexEntry)>> {
-        self.get_relevant_chunks_for_qa_with_filters(question, top_k, None).await
+    ) -> Result<Vec<RetrievedChunk>> {
+        self.get_relevant_chunks_for_qa_with_filters(question, top_k, None, DEFAULT_MAX_CHUNKS_PER_DOCUMENT, None).await
     }
 
+    /// Hybrid retrieval for Q&A: lexical and semantic candidates are merged
+    /// with reciprocal rank fusion, near-duplicate chunks are dropped and at
+    /// most `max_chunks_per_document` chunks are kept per document. With
+    /// `as_of`, chunks come from the versions that were latest at that moment.
     pub async fn get_relevant_chunks_for_qa_with_filters(
         &self,
         question: &str,
         top_k: u32,
         project_ids: Option<&[Uuid]>,
-    ) -> Result<Vec<(Document, IndexEntry)>> {
-        // Extract keywords from the question (remove common words and punctuation)
-        let keywords = self.extract_keywords_from_question(question);
-        
-        if keywords.is_empty() {
+        max_chunks_per_document: u32,
+        as_of: Option<DateTime<Utc>>,
+    ) -> Result<Vec<RetrievedChunk>> {
+        let candidate_limit = std::cmp::max(top_k * 4, 20);
+
+        let lexical = self.lexical_chunks(question, candidate_limit, project_ids, as_of).await?;
+        let semantic = if self.embedder.is_some() {
+            match self.semantic_chunks(question, candidate_limit, project_ids, as_of).await {
+                Ok(chunks) => chunks,
+                Err(e) => {
+                    // Fall back to lexical retrieval when embeddings are unavailable
+                    tracing::warn!("Semantic retrieval failed: {}", e);
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
+        let retrievers = [&lexical, &semantic].iter().filter(|list| !list.is_empty()).count();
+        if retrievers == 0 {
             return Ok(Vec::new());
         }
-        
-        // Search for each keyword and collect results
-        let mut all_chunks = Vec::new();
-        for keyword in keywords {
-            let filters = if let Some(project_ids) = project_ids {
-                Some(SearchFilters {
-                    file_types: None,
-                    folders: None,
-                    date_from: None,
-                    date_to: None,
-                    tags: None,
-                    project_ids: Some(project_ids.to_vec()),
-                })
-            } else {
-                None
-            };
-            let search_results = self.search(&keyword, filters, top_k * 2, 0, false).await?;
-            
-            for result in search_results {
-                let index_entries = self.db.get_index_entries_for_document(&result.document.id).await?;
-                for entry in index_entries {
-                    all_chunks.push((result.document.clone(), entry));
+
+        // Reciprocal rank fusion: each list contributes 1 / (k + rank)
+        let mut fused: Vec<RetrievedChunk> = Vec::new();
+        let mut positions: std::collections::HashMap<(Uuid, u32), usize> = std::collections::HashMap::new();
+        for (list_index, list) in [lexical, semantic].into_iter().enumerate() {
+            for (rank, (document, entry, relevance)) in list.into_iter().enumerate() {
+                let key = (document.id, entry.chunk_id);
+                let position = *positions.entry(key).or_insert_with(|| {
+                    fused.push(RetrievedChunk {
+                        document,
+                        entry,
+                        score: 0.0,
+                        lexical_rank: None,
+                        semantic_rank: None,
+                        keyword_coverage: None,
+                        similarity: None,
+                    });
+                    fused.len() - 1
+                });
+                let chunk = &mut fused[position];
+                chunk.score += 1.0 / (RRF_K + rank as f32 + 1.0);
+                if list_index == 0 {
+                    chunk.lexical_rank = Some(rank as u32 + 1);
+                    chunk.keyword_coverage = Some(relevance);
+                } else {
+                    chunk.semantic_rank = Some(rank as u32 + 1);
+                    chunk.similarity = Some(relevance);
                 }
             }
         }
 
-        // Remove duplicates and sort by relevance
-        all_chunks.sort_by(|a, b| {
-            let score_a = self.calculate_chunk_relevance(&a.1.chunk_text, question);
-            let score_b = self.calculate_chunk_relevance(&b.1.chunk_text, question);
-            score_b.partial_cmp(&score_a).unwrap()
-        });
+        // Scale so that a chunk ranked first by every retriever scores 1.0
+        let max_score = retrievers as f32 / (RRF_K + 1.0);
+        for chunk in &mut fused {
+            chunk.score /= max_score;
+        }
+        fused.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
 
-        // Remove duplicates based on document ID and chunk ID
-        let mut unique_chunks = Vec::new();
-        let mut seen = std::collections::HashSet::new();
-        
-        for (doc, entry) in all_chunks {
-            let key = (doc.id, entry.chunk_id);
-            if !seen.contains(&key) {
-                seen.insert(key);
-                unique_chunks.push((doc, entry));
+        let mut selected: Vec<RetrievedChunk> = Vec::new();
+        let mut selected_terms: Vec<std::collections::HashSet<String>> = Vec::new();
+        let mut per_document: std::collections::HashMap<Uuid, u32> = std::collections::HashMap::new();
+        for chunk in fused {
+            if selected.len() >= top_k as usize {
+                break;
+            }
+            let count = per_document.entry(chunk.document.id).or_insert(0);
+            if *count >= max_chunks_per_document {
+                continue;
             }
+
+            // Overlapping windows and copied files produce near-identical chunks
+            let terms: std::collections::HashSet<String> = tokenize(&chunk.entry.chunk_text).into_iter().collect();
+            if selected_terms.iter().any(|other| jaccard(&terms, other) >= DUPLICATE_CHUNK_SIMILARITY) {
+                continue;
+            }
+
+            *count += 1;
+            selected_terms.push(terms);
+            selected.push(chunk);
         }
 
-        unique_chunks.truncate(top_k as usize);
-        Ok(unique_chunks)
+        Ok(selected)
     }
 
-    fn extract_keywords_from_question(&self, question: &str) -> Vec<String> {
-        // Common stop words to filter out
-        let stop_words = [
-            "what", "do", "does", "did", "is", "are", "was", "were", "be", "been", "being",
-            "have", "has", "had", "having", "will", "would", "could", "should", "can", "may",
-            "might", "must", "shall", "the", "a", "an", "and", "or", "but", "in", "on", "at",
-            "to", "for", "of", "with", "by", "from", "up", "about", "into", "through", "during",
-            "before", "after", "above", "below", "between", "among", "this", "that", "these",
-            "those", "i", "you", "he", "she", "it", "we", "they", "me", "him", "her", "us",
-            "them", "my", "your", "his", "her", "its", "our", "their", "mine", "yours", "hers",
-            "ours", "theirs", "who", "whom", "whose", "which", "where", "when", "why", "how",
-            "all", "any", "both", "each", "few", "more", "most", "other", "some", "such", "no",
-            "nor", "not", "only", "own", "same", "so", "than", "too", "very", "just", "now"
-        ];
-        
-        let stop_words_set: std::collections::HashSet<&str> = stop_words.iter().cloned().collect();
-        
-        question
-            .to_lowercase()
-            .split_whitespace()
-            .filter_map(|word| {
-                // Remove punctuation
-                let clean_word = word.trim_matches(|c: char| !c.is_alphanumeric());
-                if clean_word.len() > 2 && !stop_words_set.contains(clean_word) {
-                    Some(clean_word.to_string())
+    /// Chunks containing the question's keywords, ranked by how many
+    /// distinct keywords they contain and then by total occurrences, with
+    /// the share of the keywords each contains.
+    async fn lexical_chunks(
+        &self,
+        question: &str,
+        limit: u32,
+        project_ids: Option<&[Uuid]>,
+        as_of: Option<DateTime<Utc>>,
+    ) -> Result<Vec<(Document, IndexEntry, f32)>> {
+        let keywords = self.extract_keywords_from_question(question);
+        let mut keyword_variants: Vec<(Vec<String>, f32)> = keywords
+            .iter()
+            .map(|keyword| (analyze_multilingual(keyword, self.fold_accents), 1.0))
+            .filter(|(variants, _)| !variants.is_empty())
+            .collect();
+        // Synonyms add to the coverage of a chunk but not to what full
+        // coverage takes
+        let question_weight = keyword_variants.len().max(1) as f32;
+
+        // Synonyms count slightly less than the words of the question. Every
+        // word is looked up since abbreviations are often too short to be keywords.
+        let dictionary = self.synonym_dictionary(project_ids).await?;
+        let mut synonym_phrases = Vec::new();
+        let mut seen: std::collections::HashSet<String> = keywords.iter().map(|k| self.normalize_text(k)).collect();
+        for (_, expansions) in self.synonym_expansions(question, &tokenize(question), &[], &dictionary) {
+            for expansion in expansions {
+                if !seen.insert(self.normalize_text(&expansion)) {
+                    continue;
+                }
+                if expansion.contains(char::is_whitespace) {
+                    synonym_phrases.push((self.normalize_text(&expansion), SYNONYM_WEIGHT));
                 } else {
-                    None
+                    let variants = analyze_multilingual(&expansion, self.fold_accents);
+                    if !variants.is_empty() {
+                        keyword_variants.push((variants, SYNONYM_WEIGHT));
+                    }
                 }
-            })
+            }
+        }
+
+        if keyword_variants.is_empty() && synonym_phrases.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut all_variants: Vec<String> = keyword_variants.iter().flat_map(|(v, _)| v.iter().cloned()).collect();
+        for (phrase, _) in &synonym_phrases {
+            all_variants.extend(analyze_multilingual(phrase, self.fold_accents));
+        }
+        let entries = self.db
+            .get_index_entries_matching_terms(&all_variants, self.fold_accents, Versions::new(false, as_of), project_ids)
+            .await?;
+
+        let mut documents: std::collections::HashMap<Uuid, Option<Document>> = std::collections::HashMap::new();
+        let mut scored_chunks = Vec::new();
+        for entry in entries {
+            let score = self.calculate_chunk_relevance(&entry.chunk_text, &keyword_variants, &synonym_phrases);
+            if score <= 0.0 {
+                continue;
+            }
+            let document = match documents.entry(entry.document_id) {
+                std::collections::hash_map::Entry::Occupied(known) => known.into_mut(),
+                std::collections::hash_map::Entry::Vacant(slot) => slot.insert(self.db.get_document_by_id(&entry.document_id).await?),
+            };
+            if let Some(document) = document {
+                scored_chunks.push((score, document.clone(), entry));
+            }
+        }
+
+        scored_chunks.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(scored_chunks
+            .into_iter()
+            .take(limit as usize)
+            .map(|(score, document, entry)| (document, entry, (score / question_weight).min(1.0)))
+            .collect())
+    }
+
+    fn extract_keywords_from_question(&self, question: &str) -> Vec<String> {
+        // Drop stop words of the question's language (or of every supported
+        // language when it cannot be detected)
+        let analyzer = Analyzer::new(detect_language(question), self.fold_accents);
+
+        tokenize(question)
+            .into_iter()
+            .filter(|word| word.chars().count() > 2 && !analyzer.is_stop_word(word))
             .collect()
     }
 
-    fn calculate_chunk_relevance(&self, chunk_text: &str, question: &str) -> f32 {
-        let chunk_lower = chunk_text.to_lowercase();
-        let question_lower = question.to_lowercase();
-        
-        let question_terms: Vec<&str> = question_lower.split_whitespace().collect();
+    fn calculate_chunk_relevance(&self, chunk_text: &str, keyword_variants: &[(Vec<String>, f32)], phrases: &[(String, f32)]) -> f32 {
+        let chunk_terms = analyze_multilingual(chunk_text, self.fold_accents);
+
         let mut score = 0.0;
-        
-        for term in question_terms {
-            if chunk_lower.contains(term) {
-                score += 1.0;
+        for (variants, weight) in keyword_variants {
+            let occurrences = chunk_terms.iter().filter(|t| variants.contains(t)).count();
+            if occurrences > 0 {
+                // Distinct keywords dominate; repetitions only break ties
+                score += weight * (1.0 + (occurrences as f32).ln() * 0.1);
             }
         }
-        
+
+        if !phrases.is_empty() {
+            let chunk_lower = self.normalize_text(chunk_text);
+            for (phrase, weight) in phrases {
+                let occurrences = chunk_lower.matches(phrase.as_str()).count();
+                if occurrences > 0 {
+                    score += weight * (1.0 + (occurrences as f32).ln() * 0.1);
+                }
+            }
+        }
+
         score
     }
 }
 
+/// Confidence in `0.0..=1.0` that the retrieved chunks can answer the
+/// question: the mean relevance of the three most relevant chunks, each
+/// judged by its keyword coverage or its similarity, whichever is higher.
+/// Unlike the fused score, which is relative to the other candidates, this
+/// stays low when even the best chunks barely match.
+pub fn retrieval_confidence(chunks: &[RetrievedChunk]) -> f32 {
+    let mut relevance: Vec<f32> = chunks
+        .iter()
+        .map(|c| c.keyword_coverage.unwrap_or(0.0).max(c.similarity.unwrap_or(0.0)).clamp(0.0, 1.0))
+        .collect();
+    relevance.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    // Fewer than three chunks count as missing evidence
+    relevance.iter().take(3).sum::<f32>() / 3.0
+}
+
+fn sort_results(results: &mut [SearchResult]) {
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.document.id.cmp(&b.document.id))
+    });
+}
+
+fn paginate(results: Vec<SearchResult>, limit: u32, offset: u32) -> Vec<SearchResult> {
+    results.into_iter().skip(offset as usize).take(limit as usize).collect()
+}
+
+/// Cursor hash of `query` together with everything that changes its ranking.
+fn cursor_hash(query: &str, options: &SearchOptions) -> String {
+    query_hash(query, &(options.mode, &options.filters, options.include_historical, options.as_of))
+}
+
+#[derive(Default)]
+struct FacetCounter {
+    counts: std::collections::HashMap<String, u32>,
+}
+
+impl FacetCounter {
+    fn add(&mut self, value: String) {
+        *self.counts.entry(value).or_insert(0) += 1;
+    }
+
+    /// Counts ordered by frequency, then value.
+    fn into_counts(self) -> Vec<FacetCount> {
+        let mut counts: Vec<FacetCount> = self.counts
+            .into_iter()
+            .map(|(value, count)| FacetCount { value, label: None, count })
+            .collect();
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        counts
+    }
+}
+
+/// Link target reduced to a lowercase file stem, so `[[Note]]`,
+/// `[x](../Note.md)` and the file `note.md` compare equal.
+fn link_key(target: &str) -> String {
+    let name = target.trim().rsplit(['/', '\\']).next().unwrap_or(target).to_lowercase();
+    for extension in [".md", ".markdown", ".txt", ".docx", ".pdf"] {
+        if let Some(stem) = name.strip_suffix(extension) {
+            return stem.to_string();
+        }
+    }
+    name
+}
+
+fn jaccard(a: &std::collections::HashSet<String>, b: &std::collections::HashSet<String>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Windows of `text` (byte ranges) around clusters of nearby matches.
+fn snippet_windows(text: &str, matches: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut clusters: Vec<(usize, usize)> = Vec::new();
+    for &(start, end) in matches {
+        match clusters.last_mut() {
+            // Merge if within 50 characters
+            Some((_, last_end)) if start <= *last_end + 50 => *last_end = end,
+            _ => clusters.push((start, end)),
+        }
+    }
+
+    clusters
+        .into_iter()
+        .map(|(start, end)| {
+            let mut window_start = start.saturating_sub(100);
+            while !text.is_char_boundary(window_start) {
+                window_start += 1;
+            }
+            let window_end = floor_char_boundary(text, end + 100);
+            (window_start, window_end)
+        })
+        .collect()
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = std::cmp::min(index, text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Cut `window` out of a chunk. Positions are converted from chunk bytes to
+/// document characters.
+fn build_snippet(
+    entry: &IndexEntry,
+    window_start: usize,
+    window_end: usize,
+    matches: &[(usize, usize)],
+    format: HighlightFormat,
+) -> Snippet {
+    let chunk = &entry.chunk_text;
+    let text = &chunk[window_start..window_end];
+    let start_pos = entry.start_offset + chunk[..window_start].chars().count();
+
+    let local: Vec<(usize, usize)> = matches
+        .iter()
+        .filter(|(start, end)| *start >= window_start && *end <= window_end)
+        .map(|(start, end)| (start - window_start, end - window_start))
+        .collect();
+    let ranges = local
+        .iter()
+        .map(|&(start, end)| {
+            let char_start = start_pos + text[..start].chars().count();
+            MatchRange {
+                start: char_start,
+                end: char_start + text[start..end].chars().count(),
+            }
+        })
+        .collect();
+
+    Snippet {
+        text: text.to_string(),
+        start_pos,
+        end_pos: start_pos + text.chars().count(),
+        highlighted: highlight(text, &local, format),
+        matches: ranges,
+        chunk_id: entry.chunk_id,
+    }
+}
+
+/// Mark `ranges` (byte ranges of `text`) without altering the matched text.
+fn highlight(text: &str, ranges: &[(usize, usize)], format: HighlightFormat) -> String {
+    let (open, close) = match format {
+        HighlightFormat::Raw => return text.to_string(),
+        HighlightFormat::Markdown => ("**", "**"),
+        HighlightFormat::Html => ("<mark>", "</mark>"),
+    };
+    let escape = |part: &str| match format {
+        HighlightFormat::Html => html_escape::encode_text(part).to_string(),
+        _ => part.to_string(),
+    };
+
+    let mut highlighted = String::new();
+    let mut last = 0;
+    for &(start, end) in ranges {
+        highlighted.push_str(&escape(&text[last..start]));
+        highlighted.push_str(open);
+        highlighted.push_str(&escape(&text[start..end]));
+        highlighted.push_str(close);
+        last = end;
+    }
+    highlighted.push_str(&escape(&text[last..]));
+    highlighted
+}
+
 #[derive(Debug, Clone)]
 struct ParsedQuery {
     terms: Vec<String>,
     phrases: Vec<String>,
-    operators: Vec<String>,
+    /// Analyzed variants (stems, optionally accent-folded) per entry in `terms`
+    analyzed_terms: Vec<Vec<String>>,
+    /// For synonym expansions, the typed term or phrase each entry in
+    /// `terms` was expanded from
+    term_sources: Vec<Option<String>>,
+    /// Like `term_sources`, for `phrases`
+    phrase_sources: Vec<Option<String>>,
+}
+
+impl ParsedQuery {
+    fn term_weight(&self, index: usize) -> f32 {
+        expansion_weight(&self.term_sources[index])
+    }
+
+    fn phrase_weight(&self, index: usize) -> f32 {
+        expansion_weight(&self.phrase_sources[index])
+    }
+
+    fn describe_term(&self, index: usize) -> String {
+        describe_expansion(&self.terms[index], &self.term_sources[index])
+    }
+
+    fn describe_phrase(&self, index: usize) -> String {
+        describe_expansion(&self.phrases[index], &self.phrase_sources[index])
+    }
+}
+
+fn expansion_weight(source: &Option<String>) -> f32 {
+    if source.is_some() { SYNONYM_WEIGHT } else { 1.0 }
+}
+
+fn describe_expansion(text: &str, source: &Option<String>) -> String {
+    match source {
+        Some(source) => format!("\"{}\" (synonym of \"{}\")", text, source),
+        None => format!("\"{}\"", text),
+    }
 }
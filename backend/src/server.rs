@@ -1,11 +1,13 @@
 use anyhow::Result;
 use crate::config::Config;
 use crate::database::Database;
-use crate::mcp::MCPServer;
-use crate::ollama::OllamaClient;
+use crate::mcp::{MCPServer, StreamEvent};
+use crate::llm::LlmClient;
 use serde::{Deserialize, Serialize};
 use std::convert::Infallible;
 use std::path::PathBuf;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::StreamExt;
 use uuid::Uuid;
 use warp::{Filter, Rejection};
 
@@ -23,16 +25,23 @@ pub struct IndexResponse {
 }
 
 pub async fn start_server(config: Config, db: Database) -> Result<()> {
-    // Initialize Ollama client
-    let ollama_client = OllamaClient::new(config.ollama_url.clone(), config.ollama_model.clone());
-    
-    // Check Ollama health
-    if !ollama_client.health_check().await? {
-        tracing::warn!("Ollama is not available at {}", config.ollama_url);
+    // Initialize the model provider
+    let llm_client = crate::llm::client_from_config(&config)?;
+
+    // Check provider health
+    if !llm_client.health_check().await? {
+        tracing::warn!("The {} model server is not available", llm_client.provider_name());
+    } else {
+        for status in llm_client.check_models().await.models.iter().filter(|status| !status.available) {
+            tracing::warn!("The {} model {} is not installed", status.task.name(), status.model);
+        }
     }
 
     // Initialize MCP server
-    let mcp_server = MCPServer::new(db.clone(), ollama_client);
+    let mcp_server = MCPServer::new(db.clone(), llm_client.clone())
+        .with_accent_folding(config.fold_accents)
+        .with_prompt_config(config.prompts.clone())
+        .with_context_config(config.context.clone());
 
     // CORS configuration
     let cors = warp::cors()
@@ -41,7 +50,63 @@ pub async fn start_server(config: Config, db: Database) -> Result<()> {
         .allow_methods(vec!["GET", "POST", "PUT", "DELETE"]);
 
     // API routes
-    let api_routes = warp::path("api")
+    let api_routes = api_routes(db, llm_client, mcp_server);
+
+    // Serve static files from build directory
+    let static_files = warp::path("assets")
+        .and(warp::fs::dir("../build/assets"))
+        .or(warp::path("static")
+            .and(warp::fs::dir("../build/static")))
+        .or(warp::path("manifest.json")
+            .and(warp::fs::file("../build/manifest.json")))
+        .or(warp::path("asset-manifest.json")
+            .and(warp::fs::file("../build/asset-manifest.json")))
+        .or(warp::path("favicon.ico")
+            .and(warp::fs::file("../build/favicon.ico")))
+        .or(warp::path("logo192.png")
+            .and(warp::fs::file("../build/logo192.png")));
+
+    // Serve React app for all other routes (fallback)
+    let react_app = warp::any()
+        .and(warp::path::full())
+        .and_then(|path: warp::path::FullPath| async move {
+            // Only serve index.html for non-API, non-static file requests
+            if !path.as_str().starts_with("/api") && 
+               !path.as_str().starts_with("/static") &&
+               !path.as_str().starts_with("/assets") &&
+               !path.as_str().contains(".") {
+                Ok::<_, Infallible>(warp::reply::html(
+                    std::fs::read_to_string("../build/index.html").unwrap_or_default()
+                ))
+            } else {
+                Ok::<_, Infallible>(warp::reply::html("Not Found".to_string()))
+            }
+        });
+
+    let routes = api_routes
+        .or(static_files)
+        .or(react_app)
+        .with(cors);
+
+    let port = config.server_port;
+    println!("Starting server on http://localhost:{}", port);
+    
+    warp::serve(routes)
+        .run(([0, 0, 0, 0], port))
+        .await;
+
+    Ok(())
+}
+
+/// The `/api` routes, including `/api/health`, served by `mcp_server`;
+/// indexing uses `llm_client` for embeddings. [`start_server`] adds the
+/// frontend and CORS; tests can serve these on their own.
+pub fn api_routes(
+    db: Database,
+    llm_client: LlmClient,
+    mcp_server: MCPServer,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path("api")
         .and(
             // MCP tools endpoint
             warp::path("tools")
@@ -75,6 +140,31 @@ pub async fn start_server(config: Config, db: Database) -> Result<()> {
                             }
                         })
                 )
+                .or(
+                    // POST /api/stream - answer_question or summarize_note as Server-Sent Events:
+                    // `citations`, then `token` events, then `done` (or `error`)
+                    warp::path!("stream")
+                        .and(warp::post())
+                        .and(warp::body::json())
+                        .map({
+                            let mcp_server = mcp_server.clone();
+                            move |request: crate::mcp::MCPRequest| {
+                                let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+                                let mcp_server = mcp_server.clone();
+                                tokio::spawn(async move {
+                                    if let Err(e) = mcp_server.handle_stream_request(request, sender.clone()).await {
+                                        let _ = sender.send(StreamEvent::Error { message: e.to_string() });
+                                    }
+                                });
+
+                                let events = UnboundedReceiverStream::new(receiver).map(|event| {
+                                    let data = serde_json::to_string(&event).unwrap_or_default();
+                                    Ok::<_, Infallible>(warp::sse::Event::default().event(event.name()).data(data))
+                                });
+                                warp::sse::reply(warp::sse::keep_alive().stream(events))
+                            }
+                        })
+                )
                 .or({
                     // Index management endpoints
                     let index_post = warp::path("index")
@@ -82,10 +172,12 @@ pub async fn start_server(config: Config, db: Database) -> Result<()> {
                         .and(warp::body::json())
                         .and_then({
                             let db = db.clone();
+                            let llm_client = llm_client.clone();
                             move |request: IndexRequest| {
                                 let db = db.clone();
+                                let llm_client = llm_client.clone();
                                 async move {
-                                    match index_folders(db, request.folders, request.project_id).await {
+                                    match index_folders(db, llm_client, request.folders, request.project_id).await {
                                         Ok(result) => Ok::<_, Infallible>(warp::reply::json(&IndexResponse {
                                             success: true,
                                             message: "Indexing completed".to_string(),
@@ -468,63 +560,445 @@ pub async fn start_server(config: Config, db: Database) -> Result<()> {
                         )
                 )
                 .or(
-                    // Health check
-                    warp::path("health")
+                    // GET /api/documents/{id}/similar?limit=10&project_ids=a,b - Related notes
+                    warp::path!("documents" / String / "similar")
                         .and(warp::get())
-                        .map(|| warp::reply::json(&serde_json::json!({
-                            "status": "healthy",
-                            "timestamp": chrono::Utc::now()
-                        })))
-                )
-        );
-
-    // Serve static files from build directory
-    let static_files = warp::path("assets")
-        .and(warp::fs::dir("../build/assets"))
-        .or(warp::path("static")
-            .and(warp::fs::dir("../build/static")))
-        .or(warp::path("manifest.json")
-            .and(warp::fs::file("../build/manifest.json")))
-        .or(warp::path("asset-manifest.json")
-            .and(warp::fs::file("../build/asset-manifest.json")))
-        .or(warp::path("favicon.ico")
-            .and(warp::fs::file("../build/favicon.ico")))
-        .or(warp::path("logo192.png")
-            .and(warp::fs::file("../build/logo192.png")));
+                        .and(warp::query::<std::collections::HashMap<String, String>>())
+                        .and_then({
+                            let mcp_server = mcp_server.clone();
+                            move |id: String, params: std::collections::HashMap<String, String>| {
+                                let mcp_server = mcp_server.clone();
+                                async move {
+                                    let mut arguments = serde_json::json!({ "id": id });
+                                    if let Some(limit) = params.get("limit").and_then(|l| l.parse::<u64>().ok()) {
+                                        arguments["limit"] = serde_json::json!(limit);
+                                    }
+                                    if let Some(project_ids) = params.get("project_ids") {
+                                        let ids: Vec<&str> = project_ids.split(',').filter(|s| !s.is_empty()).collect();
+                                        arguments["project_ids"] = serde_json::json!(ids);
+                                    }
 
-    // Serve React app for all other routes (fallback)
-    let react_app = warp::any()
-        .and(warp::path::full())
-        .and_then(|path: warp::path::FullPath| async move {
-            // Only serve index.html for non-API, non-static file requests
-            if !path.as_str().starts_with("/api") && 
-               !path.as_str().starts_with("/static") &&
-               !path.as_str().starts_with("/assets") &&
-               !path.as_str().contains(".") {
-                Ok::<_, Infallible>(warp::reply::html(
-                    std::fs::read_to_string("../build/index.html").unwrap_or_default()
-                ))
-            } else {
-                Ok::<_, Infallible>(warp::reply::html("Not Found".to_string()))
-            }
-        });
+                                    reply_with_tool(&mcp_server, "find_similar", arguments).await
+                                }
+                            }
+                        })
+                )
+                .or(
+                    // GET /api/suggest?q=kube&limit=8&project_ids=a,b - Search box completions
+                    warp::path!("suggest")
+                        .and(warp::get())
+                        .and(warp::query::<std::collections::HashMap<String, String>>())
+                        .and_then({
+                            let mcp_server = mcp_server.clone();
+                            move |params: std::collections::HashMap<String, String>| {
+                                let mcp_server = mcp_server.clone();
+                                async move {
+                                    let prefix = params.get("q").cloned().unwrap_or_default();
+                                    let mut arguments = serde_json::json!({ "prefix": prefix });
+                                    if let Some(limit) = params.get("limit").and_then(|l| l.parse::<u64>().ok()) {
+                                        arguments["limit"] = serde_json::json!(limit);
+                                    }
+                                    if let Some(project_ids) = params.get("project_ids") {
+                                        let ids: Vec<&str> = project_ids.split(',').filter(|s| !s.is_empty()).collect();
+                                        arguments["project_ids"] = serde_json::json!(ids);
+                                    }
 
-    let routes = api_routes
-        .or(static_files)
-        .or(react_app)
-        .with(cors);
+                                    reply_with_tool(&mcp_server, "suggest", arguments).await
+                                }
+                            }
+                        })
+                )
+                .or(
+                    // Saved searches, backed by the MCP tools of the same name
+                    warp::path("saved-searches")
+                        .and(
+                            // GET /api/saved-searches?counts=true - List saved searches, optionally with match counts
+                            warp::path::end()
+                                .and(warp::get())
+                                .and(warp::query::<std::collections::HashMap<String, String>>())
+                                .and_then({
+                                    let mcp_server = mcp_server.clone();
+                                    move |params: std::collections::HashMap<String, String>| {
+                                        let mcp_server = mcp_server.clone();
+                                        async move {
+                                            let counts = params.get("counts").is_some_and(|c| c == "true");
+                                            reply_with_tool(&mcp_server, "list_saved_searches", serde_json::json!({ "counts": counts })).await
+                                        }
+                                    }
+                                })
+                                .or(
+                                    // POST /api/saved-searches - Save a query and filters under a name
+                                    warp::path::end()
+                                        .and(warp::post())
+                                        .and(warp::body::json())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move |body: serde_json::Value| {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    reply_with_tool(&mcp_server, "save_search", body).await
+                                                }
+                                            }
+                                        })
+                                )
+                                .or(
+                                    // GET /api/saved-searches/{id}/results?new_only=true&limit=20&cursor=...
+                                    warp::path!(String / "results")
+                                        .and(warp::get())
+                                        .and(warp::query::<std::collections::HashMap<String, String>>())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move |id: String, params: std::collections::HashMap<String, String>| {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    let mut arguments = serde_json::json!({ "id": id });
+                                                    if let Some(limit) = params.get("limit").and_then(|l| l.parse::<u64>().ok()) {
+                                                        arguments["limit"] = serde_json::json!(limit);
+                                                    }
+                                                    if let Some(new_only) = params.get("new_only") {
+                                                        arguments["new_only"] = serde_json::json!(new_only == "true");
+                                                    }
+                                                    for field in ["cursor", "since"] {
+                                                        if let Some(value) = params.get(field) {
+                                                            arguments[field] = serde_json::json!(value);
+                                                        }
+                                                    }
+                                                    reply_with_tool(&mcp_server, "run_saved_search", arguments).await
+                                                }
+                                            }
+                                        })
+                                )
+                                .or(
+                                    // DELETE /api/saved-searches/{id}
+                                    warp::path::param::<String>()
+                                        .and(warp::path::end())
+                                        .and(warp::delete())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move |id: String| {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    reply_with_tool(&mcp_server, "delete_saved_search", serde_json::json!({ "id": id })).await
+                                                }
+                                            }
+                                        })
+                                )
+                        )
+                )
+                .or(
+                    // Synonym dictionary, backed by the synonym MCP tools
+                    warp::path("synonyms")
+                        .and(
+                            // GET /api/synonyms?project_id=... - Entries applying to a project (all if omitted)
+                            warp::path::end()
+                                .and(warp::get())
+                                .and(warp::query::<std::collections::HashMap<String, String>>())
+                                .and_then({
+                                    let mcp_server = mcp_server.clone();
+                                    move |params: std::collections::HashMap<String, String>| {
+                                        let mcp_server = mcp_server.clone();
+                                        async move {
+                                            let mut arguments = serde_json::json!({});
+                                            if let Some(project_id) = params.get("project_id") {
+                                                arguments["project_id"] = serde_json::json!(project_id);
+                                            }
+                                            reply_with_tool(&mcp_server, "list_synonyms", arguments).await
+                                        }
+                                    }
+                                })
+                                .or(
+                                    // POST /api/synonyms - {term, synonyms, project_id?}, replacing the term's entry
+                                    warp::path::end()
+                                        .and(warp::post())
+                                        .and(warp::body::json())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move |body: serde_json::Value| {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    reply_with_tool(&mcp_server, "set_synonyms", body).await
+                                                }
+                                            }
+                                        })
+                                )
+                                .or(
+                                    // DELETE /api/synonyms/{id}
+                                    warp::path::param::<String>()
+                                        .and(warp::path::end())
+                                        .and(warp::delete())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move |id: String| {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    reply_with_tool(&mcp_server, "delete_synonym", serde_json::json!({ "id": id })).await
+                                                }
+                                            }
+                                        })
+                                )
+                        )
+                )
+                .or(
+                    // Prompt templates, backed by the prompt MCP tools
+                    warp::path("prompts")
+                        .and(
+                            // GET /api/prompts?project_id=... - Templates in effect and stored overrides
+                            warp::path::end()
+                                .and(warp::get())
+                                .and(warp::query::<std::collections::HashMap<String, String>>())
+                                .and_then({
+                                    let mcp_server = mcp_server.clone();
+                                    move |params: std::collections::HashMap<String, String>| {
+                                        let mcp_server = mcp_server.clone();
+                                        async move {
+                                            let mut arguments = serde_json::json!({});
+                                            if let Some(project_id) = params.get("project_id") {
+                                                arguments["project_id"] = serde_json::json!(project_id);
+                                            }
+                                            reply_with_tool(&mcp_server, "list_prompt_templates", arguments).await
+                                        }
+                                    }
+                                })
+                                .or(
+                                    // POST /api/prompts/preview - {kind, question | id, ...} rendered without calling the model
+                                    warp::path!("preview")
+                                        .and(warp::post())
+                                        .and(warp::body::json())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move |body: serde_json::Value| {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    reply_with_tool(&mcp_server, "preview_prompt", body).await
+                                                }
+                                            }
+                                        })
+                                )
+                                .or(
+                                    // PUT /api/prompts/{kind} - {template, project_id?}
+                                    warp::path!(String)
+                                        .and(warp::put())
+                                        .and(warp::body::json())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move |kind: String, body: serde_json::Value| {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    reply_with_tool_and_field(&mcp_server, "set_prompt_template", body, "kind", kind).await
+                                                }
+                                            }
+                                        })
+                                )
+                                .or(
+                                    // DELETE /api/prompts/{kind}?project_id=... - Remove an override
+                                    warp::path!(String)
+                                        .and(warp::delete())
+                                        .and(warp::query::<std::collections::HashMap<String, String>>())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move |kind: String, params: std::collections::HashMap<String, String>| {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    let mut arguments = serde_json::json!({ "kind": kind });
+                                                    if let Some(project_id) = params.get("project_id") {
+                                                        arguments["project_id"] = serde_json::json!(project_id);
+                                                    }
+                                                    reply_with_tool(&mcp_server, "reset_prompt_template", arguments).await
+                                                }
+                                            }
+                                        })
+                                )
+                        )
+                )
+                .or(
+                    // Conversational Q&A, backed by the conversation MCP tools
+                    warp::path("conversations")
+                        .and(
+                            // GET /api/conversations?limit=20 - Most recently active first
+                            warp::path::end()
+                                .and(warp::get())
+                                .and(warp::query::<std::collections::HashMap<String, String>>())
+                                .and_then({
+                                    let mcp_server = mcp_server.clone();
+                                    move |params: std::collections::HashMap<String, String>| {
+                                        let mcp_server = mcp_server.clone();
+                                        async move {
+                                            let mut arguments = serde_json::json!({});
+                                            if let Some(limit) = params.get("limit").and_then(|l| l.parse::<u64>().ok()) {
+                                                arguments["limit"] = serde_json::json!(limit);
+                                            }
+                                            reply_with_tool(&mcp_server, "list_conversations", arguments).await
+                                        }
+                                    }
+                                })
+                                .or(
+                                    // POST /api/conversations - {title?, project_ids?}
+                                    warp::path::end()
+                                        .and(warp::post())
+                                        .and(warp::body::json())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move |body: serde_json::Value| {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    reply_with_tool(&mcp_server, "start_conversation", body).await
+                                                }
+                                            }
+                                        })
+                                )
+                                .or(
+                                    // GET /api/conversations/{id} - The conversation with its turns
+                                    warp::path!(String)
+                                        .and(warp::get())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move |id: String| {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    reply_with_tool(&mcp_server, "get_conversation", serde_json::json!({ "id": id })).await
+                                                }
+                                            }
+                                        })
+                                )
+                                .or(
+                                    // DELETE /api/conversations/{id}
+                                    warp::path!(String)
+                                        .and(warp::delete())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move |id: String| {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    reply_with_tool(&mcp_server, "delete_conversation", serde_json::json!({ "id": id })).await
+                                                }
+                                            }
+                                        })
+                                )
+                                .or(
+                                    // POST /api/conversations/{id}/messages - {question, ...} answered as the next turn
+                                    warp::path!(String / "messages")
+                                        .and(warp::post())
+                                        .and(warp::body::json())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move |id: String, body: serde_json::Value| {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    reply_with_tool_and_field(&mcp_server, "answer_question", body, "conversation_id", id).await
+                                                }
+                                            }
+                                        })
+                                )
+                        )
+                )
+                .or(
+                    // Models on the configured LLM server, backed by the model MCP tools
+                    warp::path("models")
+                        .and(
+                            // GET /api/models - Installed models and the model used for each task
+                            warp::path::end()
+                                .and(warp::get())
+                                .and_then({
+                                    let mcp_server = mcp_server.clone();
+                                    move || {
+                                        let mcp_server = mcp_server.clone();
+                                        async move {
+                                            reply_with_tool(&mcp_server, "list_models", serde_json::json!({})).await
+                                        }
+                                    }
+                                })
+                                .or(
+                                    // GET /api/models/check - Whether the configured models are installed
+                                    warp::path!("check")
+                                        .and(warp::get())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move || {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    reply_with_tool(&mcp_server, "check_models", serde_json::json!({})).await
+                                                }
+                                            }
+                                        })
+                                )
+                                .or(
+                                    // GET /api/models/{name} - Details and context window of one model
+                                    warp::path!(String)
+                                        .and(warp::get())
+                                        .and_then({
+                                            let mcp_server = mcp_server.clone();
+                                            move |name: String| {
+                                                let mcp_server = mcp_server.clone();
+                                                async move {
+                                                    reply_with_tool(&mcp_server, "show_model", serde_json::json!({ "name": name })).await
+                                                }
+                                            }
+                                        })
+                                )
+                        )
+                )
+                .or(
+                    // Health check, including whether the configured models are installed
+                    warp::path("health")
+                        .and(warp::get())
+                        .and_then({
+                            let llm_client = llm_client.clone();
+                            move || {
+                                let llm_client = llm_client.clone();
+                                async move {
+                                    let check = llm_client.check_models().await;
+                                    Ok::<_, warp::Rejection>(warp::reply::json(&serde_json::json!({
+                                        "status": if check.ok() { "healthy" } else { "degraded" },
+                                        "timestamp": chrono::Utc::now(),
+                                        "llm": check
+                                    })))
+                                }
+                            }
+                        })
+                )
+        )
+}
 
-    let port = config.server_port;
-    println!("Starting server on http://localhost:{}", port);
-    
-    warp::serve(routes)
-        .run(([0, 0, 0, 0], port))
-        .await;
+/// Run an MCP tool and reply with its response, turning errors into a
+/// failed `MCPResponse`.
+async fn reply_with_tool(mcp_server: &MCPServer, tool: &str, arguments: serde_json::Value) -> Result<warp::reply::Json, Infallible> {
+    let request = crate::mcp::MCPRequest {
+        tool: tool.to_string(),
+        arguments,
+    };
+    match mcp_server.handle_request(request).await {
+        Ok(response) => Ok(warp::reply::json(&response)),
+        Err(e) => Ok(warp::reply::json(&crate::mcp::MCPResponse {
+            success: false,
+            data: None,
+            error: Some(e.to_string()),
+        })),
+    }
+}
 
-    Ok(())
+/// Call `tool` with the JSON object `body` plus `field` taken from the path.
+/// Bodies that are not objects are rejected with 400 Bad Request.
+async fn reply_with_tool_and_field(
+    mcp_server: &MCPServer,
+    tool: &str,
+    mut body: serde_json::Value,
+    field: &str,
+    value: String,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, Infallible> {
+    let Some(arguments) = body.as_object_mut() else {
+        let response = crate::mcp::MCPResponse {
+            success: false,
+            data: None,
+            error: Some("Request body must be a JSON object".to_string()),
+        };
+        return Ok(warp::reply::with_status(warp::reply::json(&response), warp::http::StatusCode::BAD_REQUEST));
+    };
+    arguments.insert(field.to_string(), serde_json::Value::String(value));
+    let reply = reply_with_tool(mcp_server, tool, body).await?;
+    Ok(warp::reply::with_status(reply, warp::http::StatusCode::OK))
 }
 
-async fn index_folders(db: Database, folders: Vec<PathBuf>, project_id: Option<Uuid>) -> Result<crate::corpus::IndexingResult> {
+async fn index_folders(db: Database, llm_client: LlmClient, folders: Vec<PathBuf>, project_id: Option<Uuid>) -> Result<crate::corpus::IndexingResult> {
     let mut total_result = crate::corpus::IndexingResult {
         files_processed: 0,
         files_skipped: 0,
@@ -553,7 +1027,7 @@ async fn index_folders(db: Database, folders: Vec<PathBuf>, project_id: Option<U
         }
     }
 
-    let corpus_manager = crate::corpus::CorpusManager::new(db.clone(), exclusions);
+    let corpus_manager = crate::corpus::CorpusManager::new(db.clone(), exclusions).with_embeddings(llm_client);
 
     for raw in folders {
         // Normalize: trim and canonicalize if possible
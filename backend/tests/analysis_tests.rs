@@ -0,0 +1,103 @@
+use knowledge_base_backend::analysis::{analyze_multilingual, detect_language, Analyzer, Language};
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::search::SearchEngine;
+use std::fs;
+
+#[test]
+fn detects_hungarian_and_english() {
+    assert_eq!(
+        detect_language("Ez egy magyar nyelvű jegyzet, amely a projekt céljairól szól és nem angol."),
+        Language::Hungarian
+    );
+    assert_eq!(
+        detect_language("This is an English note about the goals of the project and how we work."),
+        Language::English
+    );
+    assert_eq!(detect_language("12345"), Language::Unknown);
+}
+
+#[test]
+fn hungarian_stemming_strips_case_and_plural_suffixes() {
+    let analyzer = Analyzer::new(Language::Hungarian, false);
+    assert_eq!(analyzer.stem("házban"), "ház");
+    assert_eq!(analyzer.stem("projektekről"), "projekt");
+    assert_eq!(analyzer.stem("projekt"), "projekt");
+    assert_eq!(analyzer.stem("almát"), "alma");
+}
+
+#[test]
+fn accent_folding_is_optional() {
+    let folding = Analyzer::new(Language::Hungarian, true);
+    let strict = Analyzer::new(Language::Hungarian, false);
+    assert_eq!(folding.analyze("Kérés"), folding.analyze("keres"));
+    assert_ne!(strict.analyze("Kérés"), strict.analyze("keres"));
+}
+
+#[test]
+fn stop_words_are_dropped() {
+    let analyzer = Analyzer::new(Language::Hungarian, true);
+    assert!(analyzer.analyze("a ház és az autó").iter().all(|t| t != "a" && t != "az" && t != "es"));
+    let terms = analyze_multilingual("what is the budget", true);
+    assert!(terms.contains(&"budget".to_string()));
+    assert!(!terms.iter().any(|t| t == "what" || t == "the" || t == "is"));
+}
+
+#[tokio::test]
+async fn search_matches_inflected_and_unaccented_terms() {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        temp_dir.path().join("terv.md"),
+        "# Terv\n\nA költségvetésről szóló döntés a következő projektekben is érvényes.",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("notes.md"),
+        "# Notes\n\nThe budget for the migration projects was approved.",
+    )
+    .unwrap();
+
+    let corpus = CorpusManager::new(db.clone(), vec![]);
+    let res = corpus.index_folder(temp_dir.path(), None).await.unwrap();
+    assert_eq!(res.files_processed, 2);
+
+    let engine = SearchEngine::new(db.clone());
+
+    // Inflected Hungarian form, no accents
+    let results = engine.search("koltsegvetes", None, 10, 0, false).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].document.filename, "terv.md");
+    assert_eq!(results[0].document.language.as_deref(), Some("hu"));
+
+    // Plural forms in both languages
+    let results = engine.search("projekt", None, 10, 0, false).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].document.filename, "terv.md");
+    let results = engine.search("project", None, 10, 0, false).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].document.filename, "notes.md");
+
+    // Accent-sensitive engine does not fold
+    let strict = SearchEngine::new(db.clone()).with_accent_folding(false);
+    let results = strict.search("koltsegvetes", None, 10, 0, false).await.unwrap();
+    assert!(results.is_empty());
+}
+
+#[tokio::test]
+async fn notes_of_unknown_language_match_stemmed_queries() {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+
+    // Too short to detect a language
+    let temp_dir = tempfile::tempdir().unwrap();
+    fs::write(temp_dir.path().join("lista.md"), "# Lista\n\nHázakban raktárak.").unwrap();
+    CorpusManager::new(db.clone(), vec![]).index_folder(temp_dir.path(), None).await.unwrap();
+
+    let engine = SearchEngine::new(db.clone());
+    let results = engine.search("házban", None, 10, 0, false).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].document.language.as_deref(), Some("unknown"));
+}
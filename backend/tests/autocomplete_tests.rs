@@ -0,0 +1,86 @@
+mod common;
+
+use common::{index_notes, index_notes_into};
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::mcp::{MCPRequest, MCPServer};
+use knowledge_base_backend::ollama::OllamaClient;
+use knowledge_base_backend::search::SearchEngine;
+use std::fs;
+
+async fn setup() -> (Database, tempfile::TempDir) {
+    let notes = [
+        (
+            "cluster.md",
+            "---\ntags: [kubernetes]\n---\n# Kubernetes cluster\n\n## Kubectl basics\n\nKubernetes pods and kubelet logs.",
+        ),
+        ("deploy.md", "# Deploys\n\nKubernetes deploys run nightly."),
+        ("kitchen.md", "# Kitchen\n\nKale recipes."),
+    ];
+    index_notes(&notes, None).await
+}
+
+fn texts(suggestions: &[knowledge_base_backend::database::Suggestion]) -> Vec<String> {
+    suggestions.iter().map(|s| s.text.to_lowercase()).collect()
+}
+
+#[tokio::test]
+async fn completions_match_prefixes_ranked_by_document_frequency() {
+    let (db, _dir) = setup().await;
+    let engine = SearchEngine::new(db);
+
+    let suggestions = engine.suggest("Ku", None, 10).await.unwrap();
+    let found = texts(&suggestions);
+    // In two documents, so ahead of everything only found in one
+    assert_eq!(found[0], "kubernetes");
+    assert_eq!(suggestions[0].document_frequency, 2);
+    for expected in ["kubernetes cluster", "kubectl basics", "kubelet"] {
+        assert!(found.contains(&expected.to_string()), "missing {expected}");
+    }
+    assert!(found.iter().all(|t| t.starts_with("ku")));
+
+    let headings = engine.suggest("kubectl", None, 10).await.unwrap();
+    assert!(headings.iter().any(|s| s.kind == "heading"));
+
+    // The last word is completed after the ones already typed
+    let phrase = engine.suggest("nightly kal", None, 10).await.unwrap();
+    assert!(texts(&phrase).contains(&"nightly kale".to_string()));
+
+    assert!(engine.suggest("   ", None, 10).await.unwrap().is_empty());
+    assert_eq!(engine.suggest("k", None, 2).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn completions_are_scoped_to_projects() {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let work = db.create_project("Work", None).await.unwrap();
+    let home = db.create_project("Home", None).await.unwrap();
+
+    let _work_dir = index_notes_into(&db, &[("infra.md", "# Terraform\n\nTerraform modules.")], Some(work.id)).await;
+    let _home_dir = index_notes_into(&db, &[("garden.md", "# Tomatoes\n\nTomatoes need sun.")], Some(home.id)).await;
+
+    let ollama = OllamaClient::new("http://localhost:11434".to_string(), "gpt-oss:20b".to_string());
+    let mcp = MCPServer::new(db, ollama);
+    let request = MCPRequest {
+        tool: "suggest".to_string(),
+        arguments: serde_json::json!({"prefix": "t", "project_ids": [home.id]}),
+    };
+    let data = mcp.handle_request(request).await.unwrap().data.unwrap();
+    let found: Vec<&str> = data["suggestions"].as_array().unwrap().iter().map(|s| s["text"].as_str().unwrap()).collect();
+    assert!(found.iter().any(|t| t.eq_ignore_ascii_case("tomatoes")));
+    assert!(!found.iter().any(|t| t.eq_ignore_ascii_case("terraform")));
+}
+
+#[tokio::test]
+async fn reindexing_replaces_the_previous_versions_candidates() {
+    let (db, dir) = setup().await;
+    let engine = SearchEngine::new(db.clone());
+    assert!(!engine.suggest("kale", None, 10).await.unwrap().is_empty());
+
+    fs::write(dir.path().join("kitchen.md"), "# Kitchen\n\nLentil soup.").unwrap();
+    CorpusManager::new(db, vec![]).index_folder(dir.path(), None).await.unwrap();
+
+    assert!(engine.suggest("kale", None, 10).await.unwrap().is_empty());
+    assert!(!engine.suggest("lent", None, 10).await.unwrap().is_empty());
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::MockLlm;
+use knowledge_base_backend::citations::verify_citations;
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::mcp::{MCPRequest, MCPServer};
+use std::fs;
+
+#[test]
+fn markers_are_verified_and_invalid_ones_stripped() {
+    let answer = "The rollout starts on Monday [1]. Traffic shifts gradually.[2, 9] It was approved by everyone [4].";
+    let (cleaned, check) = verify_citations(answer, 2);
+    assert_eq!(cleaned, "The rollout starts on Monday [1]. Traffic shifts gradually.[2] It was approved by everyone.");
+    assert_eq!(check.used, vec![1, 2]);
+    assert_eq!(check.invalid_markers, vec![4, 9]);
+    assert_eq!(check.uncited_sentences, vec!["It was approved by everyone."]);
+    assert!(!check.uncited);
+}
+
+#[test]
+fn code_links_and_short_lines_are_not_checked() {
+    let answer = "## Steps\n\nRun the following:\n\n```\nitems[3] = 1\n```\n\nUse `list[0]` or see [1](http://x) for more. Done.";
+    let (cleaned, check) = verify_citations(answer, 1);
+    assert_eq!(cleaned, answer);
+    assert!(check.used.is_empty());
+    assert!(check.invalid_markers.is_empty());
+    assert_eq!(check.uncited_sentences, vec!["Use `list[0]` or see [1](http://x) for more."]);
+    assert!(check.uncited);
+}
+
+#[tokio::test]
+async fn answers_flag_used_citations_and_uncited_sentences() {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let temp_dir = tempfile::tempdir().unwrap();
+    fs::write(temp_dir.path().join("rollout.md"), "# Rollout\n\nThe canary rollout starts on Monday.").unwrap();
+    fs::write(temp_dir.path().join("freeze.md"), "# Freeze\n\nThe rollout freeze ends on Friday.").unwrap();
+    CorpusManager::new(db.clone(), vec![]).index_folder(temp_dir.path(), None).await.unwrap();
+
+    let llm = MockLlm::new()
+        .reply_when("When does the canary rollout start?", "The canary rollout starts on Monday [1]. Everyone agreed on the plan [5].")
+        .start()
+        .await;
+    let mcp = MCPServer::new(db, llm.client());
+    let request = MCPRequest {
+        tool: "answer_question".to_string(),
+        arguments: serde_json::json!({"question": "When does the canary rollout start?"}),
+    };
+    let data = mcp.handle_request(request).await.unwrap().data.unwrap();
+
+    assert_eq!(data["answer"], "The canary rollout starts on Monday [1]. Everyone agreed on the plan.");
+    let citations = data["citations"].as_array().unwrap();
+    assert_eq!(citations.len(), 2);
+    assert_eq!(citations[0]["marker"], 1);
+    assert_eq!(citations[0]["filename"], "rollout.md");
+    assert_eq!(citations[0]["used"], true);
+    assert_eq!(citations[1]["used"], false);
+    assert_eq!(data["citation_check"]["invalid_markers"], serde_json::json!([5]));
+    assert_eq!(data["citation_check"]["uncited_sentences"], serde_json::json!(["Everyone agreed on the plan."]));
+    assert_eq!(data["citation_check"]["uncited"], false);
+}
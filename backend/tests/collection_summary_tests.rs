@@ -0,0 +1,151 @@
+mod common;
+
+use common::{call, index_notes_into, MockLlm, MockLlmServer};
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::mcp::{MCPRequest, MCPServer, StreamEvent};
+
+const OVERVIEW: &str = "Releases and the pager rotation are handled by ops [1, 2]. The budget doubles next year [7].";
+
+/// Summarize documents as "Summary N." (N counting document summaries) and
+/// answer overview prompts with [`OVERVIEW`].
+async fn setup() -> (MCPServer, MockLlmServer, uuid::Uuid, tempfile::TempDir) {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let project = db.create_project("Operations", None).await.unwrap();
+
+    let notes = [
+        ("ops/deploy.md", "---\ntags: [ops, release]\n---\n# Deploy\n\nRollout checklist for the release."),
+        ("ops/oncall.md", "---\ntags: [Ops]\n---\n# Oncall\n\nThe release pager rotation."),
+        ("ops-archive/old.md", "# Old deploys\n\nThe previous rollout process."),
+        ("finance/budget.md", "---\ntags: [finance]\n---\n# Budget\n\nInfrastructure spending review."),
+    ];
+    let temp_dir = index_notes_into(&db, &notes, Some(project.id)).await;
+
+    let llm = (1..=50)
+        .fold(MockLlm::new().reply_when("summaries of several documents", OVERVIEW), |llm, n| {
+            llm.reply(&format!("Summary {}.", n))
+        })
+        .start()
+        .await;
+    (MCPServer::new(db, llm.client()), llm, project.id, temp_dir)
+}
+
+fn filenames(data: &serde_json::Value) -> Vec<String> {
+    let mut names: Vec<String> = data["documents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["document"]["filename"].as_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn tag_digest_cites_documents_and_reuses_cached_summaries() {
+    let (mcp, llm, _, _dir) = setup().await;
+
+    let data = call(&mcp, "summarize_collection", serde_json::json!({"tag": "#ops", "length": "long"})).await.unwrap();
+    assert_eq!(data["collection"], serde_json::json!({"kind": "tag", "value": "ops"}));
+    assert_eq!(filenames(&data), vec!["deploy.md", "oncall.md"]);
+    assert_eq!(data["length"], "long");
+    {
+        let prompts = llm.prompts();
+        assert_eq!(prompts.len(), 3);
+        // Each document gets a short summary; the overview is built from them
+        assert!(prompts[0].contains("short summary"));
+        let overview_prompt = &prompts[2];
+        assert!(overview_prompt.contains("detailed overview"));
+        assert!(overview_prompt.contains("[1] (") && overview_prompt.contains("[2] ("));
+        assert!(overview_prompt.contains("Summary 1.") && overview_prompt.contains("Summary 2."));
+    }
+
+    // The marker pointing at no document is removed
+    assert_eq!(data["overview"], "Releases and the pager rotation are handled by ops [1, 2]. The budget doubles next year.");
+    assert_eq!(data["citation_check"]["invalid_markers"], serde_json::json!([7]));
+    let citations = data["citations"].as_array().unwrap();
+    assert_eq!(citations.len(), 2);
+    assert!(citations.iter().all(|c| c["used"] == true));
+    assert!(data["documents"].as_array().unwrap().iter().all(|d| d["cached"] == false && d["marker"].is_u64()));
+
+    // A second digest only writes the overview again
+    let again = call(&mcp, "summarize_collection", serde_json::json!({"tag": "ops"})).await.unwrap();
+    assert!(again["documents"].as_array().unwrap().iter().all(|d| d["cached"] == true));
+    assert_eq!(llm.prompts().len(), 4);
+
+    // Document summaries are shared with summarize_note
+    let id = citations[0]["document_id"].as_str().unwrap();
+    let note = call(&mcp, "summarize_note", serde_json::json!({"id": id, "length": "short"})).await.unwrap();
+    assert_eq!(note["cached"], true);
+
+    // Unless refreshed
+    call(&mcp, "summarize_collection", serde_json::json!({"tag": "ops", "refresh": true})).await.unwrap();
+    assert_eq!(llm.prompts().len(), 7);
+}
+
+#[tokio::test]
+async fn collections_are_selected_by_project_folder_or_saved_search() {
+    let (mcp, _llm, project_id, dir) = setup().await;
+
+    let project = call(&mcp, "summarize_collection", serde_json::json!({"project_id": project_id})).await.unwrap();
+    assert_eq!(filenames(&project), vec!["budget.md", "deploy.md", "old.md", "oncall.md"]);
+    assert_eq!(project["collection"]["kind"], "project");
+
+    // Sibling folders sharing a prefix are not inside the folder
+    let folder = dir.path().join("ops").to_string_lossy().to_string();
+    let data = call(&mcp, "summarize_collection", serde_json::json!({"folder": format!("{}/", folder)})).await.unwrap();
+    assert_eq!(filenames(&data), vec!["deploy.md", "oncall.md"]);
+
+    let limited = call(&mcp, "summarize_collection", serde_json::json!({"project_id": project_id, "limit": 1})).await.unwrap();
+    assert_eq!(limited["documents"].as_array().unwrap().len(), 1);
+
+    call(&mcp, "save_search", serde_json::json!({"name": "spending", "query": "spending"})).await.unwrap();
+    let saved = call(&mcp, "summarize_collection", serde_json::json!({"saved_search": "spending"})).await.unwrap();
+    assert_eq!(saved["collection"], serde_json::json!({"kind": "saved_search", "value": "spending"}));
+    assert_eq!(filenames(&saved), vec!["budget.md"]);
+}
+
+#[tokio::test]
+async fn exactly_one_selector_is_required() {
+    let (mcp, llm, project_id, _dir) = setup().await;
+
+    assert!(call(&mcp, "summarize_collection", serde_json::json!({})).await.is_err());
+    assert!(call(&mcp, "summarize_collection", serde_json::json!({"tag": "ops", "project_id": project_id})).await.is_err());
+    assert!(call(&mcp, "summarize_collection", serde_json::json!({"project_id": uuid::Uuid::new_v4()})).await.is_err());
+    assert!(call(&mcp, "summarize_collection", serde_json::json!({"saved_search": "missing"})).await.is_err());
+
+    // An empty collection is reported without calling the model
+    let empty = call(&mcp, "summarize_collection", serde_json::json!({"tag": "unused"})).await.unwrap();
+    assert!(empty["documents"].as_array().unwrap().is_empty());
+    assert!(empty["overview"].as_str().unwrap().contains("does not contain any documents"));
+    assert!(llm.prompts().is_empty());
+}
+
+#[tokio::test]
+async fn streamed_digests_report_progress_through_documents() {
+    let (mcp, _llm, _, _dir) = setup().await;
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let request = MCPRequest { tool: "summarize_collection".to_string(), arguments: serde_json::json!({"tag": "ops"}) };
+    mcp.handle_stream_request(request, sender).await.unwrap();
+    let mut events = Vec::new();
+    while let Some(event) = receiver.recv().await {
+        events.push(event);
+    }
+
+    let names: Vec<&str> = events.iter().map(|e| e.name()).collect();
+    assert_eq!(&names[..3], &["progress", "progress", "citations"]);
+    assert!(names[3..names.len() - 1].iter().all(|name| *name == "token"));
+    assert_eq!(names.last(), Some(&"done"));
+    match &events[1] {
+        StreamEvent::Progress { stage, completed, total } => assert_eq!((stage.as_str(), *completed, *total), ("documents", 2, 2)),
+        other => panic!("unexpected event: {:?}", other),
+    }
+    match events.last().unwrap() {
+        StreamEvent::Done { metadata } => {
+            assert_eq!(metadata["documents"].as_array().unwrap().len(), 2);
+            assert_eq!(metadata["citation_check"]["used"], serde_json::json!([1, 2]));
+        }
+        other => panic!("unexpected event: {:?}", other),
+    }
+}
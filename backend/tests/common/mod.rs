@@ -0,0 +1,332 @@
+//! An in-process fake of the Ollama API for integration tests: `/api/generate`
+//! (buffered and streamed), `/api/tags`, `/api/show` and `/api/embeddings`.
+//! Generations are scripted, or echo the prompt when nothing is scripted;
+//! streamed ones arrive in small pieces that split NDJSON lines. Embeddings
+//! are derived from the words of the text, so texts sharing words are
+//! similar, or from a concept vocabulary, so synonyms are. Everything the server receives is recorded. Also home to
+//! helpers the test suites share.
+#![allow(dead_code)]
+
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::mcp::{MCPRequest, MCPServer};
+use knowledge_base_backend::ollama::OllamaClient;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+use warp::http::StatusCode;
+use warp::Filter;
+
+pub const MODEL: &str = "mock-model";
+pub const EMBEDDING_MODEL: &str = "mock-embed";
+
+/// Dimensions of the fake embeddings.
+const DIMENSIONS: usize = 64;
+
+/// Context length `/api/show` reports for models without a description.
+const CONTEXT_LENGTH: u64 = 8192;
+
+/// Bytes per piece of a streamed response body.
+const STREAM_PIECE: usize = 7;
+
+/// Concept vocabulary for [`MockLlm::concepts`]: paraphrases of these share
+/// no keywords but embed close together.
+pub const CONCEPTS: &[&[&str]] = &[
+    &["car", "vehicle", "automobile", "engine"],
+    &["recipe", "cooking", "dinner", "meal"],
+    &["holiday", "vacation", "trip", "travel"],
+];
+
+/// What the fake server answers with; build it, then [`start`](Self::start) it.
+pub struct MockLlm {
+    /// Installed models with the body `/api/show` returns for them
+    models: Vec<(String, serde_json::Value)>,
+    rules: Vec<(String, String)>,
+    script: VecDeque<String>,
+    stream_error: Option<String>,
+    concepts: Vec<Vec<String>>,
+}
+
+impl Default for MockLlm {
+    fn default() -> Self {
+        Self {
+            models: vec![(MODEL.to_string(), default_show()), (EMBEDDING_MODEL.to_string(), default_show())],
+            rules: Vec::new(),
+            script: VecDeque::new(),
+            stream_error: None,
+            concepts: Vec::new(),
+        }
+    }
+}
+
+impl MockLlm {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer the next generation without a matching rule with `text`;
+    /// queued replies are used once each, in order. `{model}` in a reply is
+    /// replaced by the model the generation asked for.
+    pub fn reply(mut self, text: &str) -> Self {
+        self.script.push_back(text.to_string());
+        self
+    }
+
+    /// Answer every prompt containing `needle` with `text`; an empty
+    /// `needle` matches every prompt.
+    pub fn reply_when(mut self, needle: &str, text: &str) -> Self {
+        self.rules.push((needle.to_string(), text.to_string()));
+        self
+    }
+
+    /// Models `/api/tags` lists and `/api/show` knows, instead of [`MODEL`]
+    /// and [`EMBEDDING_MODEL`].
+    pub fn with_models(mut self, models: &[&str]) -> Self {
+        self.models = models.iter().map(|model| (model.to_string(), default_show())).collect();
+        self
+    }
+
+    /// Install `model` (or replace its description): `/api/show` returns
+    /// `show`, and `/api/tags` lists it with `show["details"]`.
+    pub fn describe(mut self, model: &str, show: serde_json::Value) -> Self {
+        self.models.retain(|(name, _)| name != model);
+        self.models.push((model.to_string(), show));
+        self
+    }
+
+    /// Embed texts with one dimension per group of synonyms in `concepts`
+    /// (see [`embed_concepts`]) instead of by their words.
+    pub fn concepts(mut self, concepts: &[&[&str]]) -> Self {
+        self.concepts = concepts.iter().map(|synonyms| synonyms.iter().map(|word| word.to_string()).collect()).collect();
+        self
+    }
+
+    /// End streamed generations with an `error` line instead of `done`.
+    pub fn stream_error(mut self, message: &str) -> Self {
+        self.stream_error = Some(message.to_string());
+        self
+    }
+
+    /// The first matching rule, else the next queued reply, else the prompt.
+    fn reply_to(&mut self, prompt: &str, model: &str) -> String {
+        if let Some((_, text)) = self.rules.iter().find(|(needle, _)| prompt.contains(needle.as_str())) {
+            return text.replace("{model}", model);
+        }
+        match self.script.pop_front() {
+            Some(text) => text.replace("{model}", model),
+            None => prompt.to_string(),
+        }
+    }
+
+    /// The description of `name`; like Ollama, a bare name means its `latest` tag.
+    fn show(&self, name: &str) -> Option<serde_json::Value> {
+        let tagged = format!("{}:latest", name);
+        self.models.iter().find(|(model, _)| model == name || *model == tagged).map(|(_, show)| show.clone())
+    }
+
+    pub async fn start(self) -> MockLlmServer {
+        let state = Arc::new(Mutex::new(MockState { config: self, generate_requests: Vec::new(), embedded: Vec::new(), shown: Vec::new() }));
+
+        let generate = warp::path!("api" / "generate").and(warp::post()).and(warp::body::json()).map({
+            let state = state.clone();
+            move |body: serde_json::Value| {
+                let prompt = body["prompt"].as_str().unwrap_or_default().to_string();
+                let model = body["model"].as_str().unwrap_or_default().to_string();
+                let (text, stream_error) = {
+                    let mut state = state.lock().unwrap();
+                    state.generate_requests.push(body.clone());
+                    (state.config.reply_to(&prompt, &model), state.config.stream_error.clone())
+                };
+                if body["stream"] != true {
+                    return warp::http::Response::new(warp::hyper::Body::from(generate_line(&model, &text, true).to_string()));
+                }
+
+                // One NDJSON line per word, then the final `done` (or error) line
+                let mut lines: String = text
+                    .split_inclusive(' ')
+                    .map(|token| format!("{}\n", generate_line(&model, token, false)))
+                    .collect();
+                let last = match stream_error {
+                    Some(message) => serde_json::json!({ "error": message }),
+                    None => generate_line(&model, "", true),
+                };
+                lines.push_str(&format!("{}\n", last));
+                let pieces: Vec<Result<Vec<u8>, Infallible>> =
+                    lines.into_bytes().chunks(STREAM_PIECE).map(|piece| Ok(piece.to_vec())).collect();
+                warp::http::Response::new(warp::hyper::Body::wrap_stream(tokio_stream::iter(pieces)))
+            }
+        });
+
+        let tags = warp::path!("api" / "tags").and(warp::get()).map({
+            let state = state.clone();
+            move || {
+                let state = state.lock().unwrap();
+                let models: Vec<serde_json::Value> = state
+                    .config
+                    .models
+                    .iter()
+                    .map(|(name, show)| serde_json::json!({"name": name, "size": 1024, "details": show["details"]}))
+                    .collect();
+                warp::reply::json(&serde_json::json!({ "models": models }))
+            }
+        });
+
+        let show = warp::path!("api" / "show").and(warp::post()).and(warp::body::json()).map({
+            let state = state.clone();
+            move |body: serde_json::Value| {
+                let name = body["model"].as_str().unwrap_or_default();
+                let show = {
+                    let mut state = state.lock().unwrap();
+                    state.shown.push(name.to_string());
+                    state.config.show(name)
+                };
+                match show {
+                    Some(show) => warp::reply::with_status(warp::reply::json(&show), StatusCode::OK),
+                    None => {
+                        let error = serde_json::json!({"error": format!("model '{}' not found", name)});
+                        warp::reply::with_status(warp::reply::json(&error), StatusCode::NOT_FOUND)
+                    }
+                }
+            }
+        });
+
+        let embeddings = warp::path!("api" / "embeddings").and(warp::post()).and(warp::body::json()).map({
+            let state = state.clone();
+            move |body: serde_json::Value| {
+                let text = body["prompt"].as_str().unwrap_or_default().to_string();
+                let mut state = state.lock().unwrap();
+                let embedding = match state.config.concepts.as_slice() {
+                    [] => embed(&text),
+                    concepts => embed_concepts(&text, concepts),
+                };
+                state.embedded.push(text);
+                warp::reply::json(&serde_json::json!({ "embedding": embedding }))
+            }
+        });
+
+        let routes = generate.or(tags).or(show).or(embeddings);
+        let (address, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        MockLlmServer { url: format!("http://{}", address), state }
+    }
+}
+
+struct MockState {
+    config: MockLlm,
+    generate_requests: Vec<serde_json::Value>,
+    embedded: Vec<String>,
+    shown: Vec<String>,
+}
+
+/// A running [`MockLlm`].
+pub struct MockLlmServer {
+    pub url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockLlmServer {
+    /// A client generating with [`MODEL`] and embedding with [`EMBEDDING_MODEL`].
+    pub fn client(&self) -> OllamaClient {
+        OllamaClient::new(self.url.clone(), MODEL.to_string()).with_embedding_model(EMBEDDING_MODEL.to_string())
+    }
+
+    /// Prompts of all generations so far.
+    pub fn prompts(&self) -> Vec<String> {
+        self.generate_requests().iter().map(|body| body["prompt"].as_str().unwrap_or_default().to_string()).collect()
+    }
+
+    /// Bodies of all `/api/generate` requests so far.
+    pub fn generate_requests(&self) -> Vec<serde_json::Value> {
+        self.state.lock().unwrap().generate_requests.clone()
+    }
+
+    /// Models asked about through `/api/show` so far.
+    pub fn shown(&self) -> Vec<String> {
+        self.state.lock().unwrap().shown.clone()
+    }
+
+    /// Texts embedded so far.
+    pub fn embedded(&self) -> Vec<String> {
+        self.state.lock().unwrap().embedded.clone()
+    }
+}
+
+/// A new in-memory database with `notes`, given as paths relative to a
+/// temporary folder and their content, written and indexed into
+/// `project_id`. The folder lives as long as the returned handle.
+pub async fn index_notes(notes: &[(&str, &str)], project_id: Option<Uuid>) -> (Database, tempfile::TempDir) {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let temp_dir = index_notes_into(&db, notes, project_id).await;
+    (db, temp_dir)
+}
+
+/// Like [`index_notes`], into an existing database, e.g. one with projects.
+pub async fn index_notes_into(db: &Database, notes: &[(&str, &str)], project_id: Option<Uuid>) -> tempfile::TempDir {
+    let temp_dir = write_notes(notes);
+    CorpusManager::new(db.clone(), vec![]).index_folder(temp_dir.path(), project_id.as_ref()).await.unwrap();
+    temp_dir
+}
+
+/// Write `notes` into a new temporary folder without indexing them.
+pub fn write_notes(notes: &[(&str, &str)]) -> tempfile::TempDir {
+    let temp_dir = tempfile::tempdir().unwrap();
+    for (path, content) in notes {
+        let path = temp_dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    temp_dir
+}
+
+/// Call `tool` on `mcp`, returning its data or the error it reported.
+pub async fn call(mcp: &MCPServer, tool: &str, arguments: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+    let request = MCPRequest { tool: tool.to_string(), arguments };
+    let response = mcp.handle_request(request).await?;
+    match response.data {
+        Some(data) if response.success => Ok(data),
+        _ => Err(anyhow::anyhow!(response.error.unwrap_or_default())),
+    }
+}
+
+fn default_show() -> serde_json::Value {
+    serde_json::json!({
+        "details": {"family": "mock"},
+        "model_info": {"mock.context_length": CONTEXT_LENGTH}
+    })
+}
+
+fn generate_line(model: &str, text: &str, done: bool) -> serde_json::Value {
+    serde_json::json!({"model": model, "created_at": "2024-01-01T00:00:00Z", "response": text, "done": done})
+}
+
+/// One dimension per group of synonyms in `concepts`, counting the words of
+/// `text` in that group.
+pub fn embed_concepts(text: &str, concepts: &[Vec<String>]) -> Vec<f32> {
+    let mut vector = vec![0.01; concepts.len()];
+    for word in text.to_lowercase().split(|c: char| !c.is_alphanumeric()) {
+        for (dimension, synonyms) in concepts.iter().enumerate() {
+            if synonyms.iter().any(|synonym| synonym == word) {
+                vector[dimension] += 1.0;
+            }
+        }
+    }
+    vector
+}
+
+/// A unit vector counting the words of `text` hashed into [`DIMENSIONS`] buckets.
+pub fn embed(text: &str) -> Vec<f32> {
+    let mut vector = vec![0.0f32; DIMENSIONS];
+    for word in text.split(|c: char| !c.is_alphanumeric()).filter(|word| word.len() > 2) {
+        let digest = md5::compute(word.to_lowercase());
+        vector[digest[0] as usize % DIMENSIONS] += 1.0;
+    }
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        // Texts without words still get a valid embedding
+        vector[0] = 1.0;
+        return vector;
+    }
+    vector.iter().map(|x| x / norm).collect()
+}
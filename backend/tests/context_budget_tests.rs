@@ -0,0 +1,154 @@
+mod common;
+
+use common::{index_notes, MockLlm, MockLlmServer};
+use knowledge_base_backend::budget::{estimate_tokens, fit_chunks, trim_to_tokens, BudgetReport, ContextBudgeter, ContextConfig};
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::llm::LlmClient;
+use knowledge_base_backend::mcp::{MCPRequest, MCPServer};
+use knowledge_base_backend::ollama::OllamaClient;
+use std::collections::HashMap;
+
+/// Describe the model with Modelfile `parameters` and answer every
+/// generation with a fixed text.
+async fn mock_llm(parameters: &str) -> MockLlmServer {
+    MockLlm::new()
+        .describe(common::MODEL, serde_json::json!({
+            "parameters": parameters,
+            "model_info": {"llama.context_length": 131072}
+        }))
+        .reply_when("", "On Monday [1].")
+        .start()
+        .await
+}
+
+async fn setup(ollama: OllamaClient, config: ContextConfig) -> (MCPServer, Database, tempfile::TempDir) {
+    // Several long chunks about the same rollout
+    let paragraph = "The canary rollout of the billing service starts on Monday and widens every day. ";
+    let content = format!("# Rollout\n\n{}", paragraph.repeat(40));
+    let notes = [("rollout.md", content.as_str()), ("rollout-notes.md", &content), ("rollout-faq.md", &content)];
+    let (db, temp_dir) = index_notes(&notes, None).await;
+
+    let mcp = MCPServer::new(db.clone(), ollama).with_context_config(config);
+    (mcp, db, temp_dir)
+}
+
+#[test]
+fn text_is_trimmed_and_chunks_fitted_to_the_budget() {
+    assert_eq!(estimate_tokens("abcdefgh"), 2);
+    assert_eq!(estimate_tokens("a b c d e"), 5);
+
+    let text = "word ".repeat(200);
+    let (trimmed, truncated) = trim_to_tokens(&text, 50);
+    assert!(truncated);
+    assert!(estimate_tokens(&trimmed) <= 50);
+    assert!(trimmed.ends_with('…'));
+    assert_eq!(trim_to_tokens("short text", 50), ("short text".to_string(), false));
+
+    let long = "x".repeat(800);
+    let chunks = vec![(5, "a fitting first chunk"), (5, long.as_str()), (5, "a short third chunk")];
+    let mut report = BudgetReport::default();
+    let kept = fit_chunks(&chunks, 100, &mut report);
+    // The long chunk is trimmed into the room left after the first one
+    assert_eq!(kept.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![0, 1]);
+    assert!(kept[1].1.len() < long.len());
+    assert_eq!(report.trimmed_chunks, 1);
+    assert_eq!(report.dropped_chunks, 1);
+    assert!(report.truncated);
+    assert!(report.context_tokens <= 100);
+}
+
+#[tokio::test]
+async fn answers_fit_the_configured_window_and_report_truncation() {
+    let llm = mock_llm("stop \"<|end|>\"").await;
+    let config = ContextConfig { windows: HashMap::from([(common::MODEL.to_string(), 600)]), answer_reserve: 250 };
+    let (mcp, _db, _dir) = setup(llm.client(), config).await;
+
+    let request = MCPRequest {
+        tool: "answer_question".to_string(),
+        arguments: serde_json::json!({"question": "When does the canary rollout start?", "top_k": 3, "max_chunks_per_document": 3}),
+    };
+    let data = mcp.handle_request(request).await.unwrap().data.unwrap();
+
+    let budget = &data["context_budget"];
+    assert_eq!(budget["context_window"], 600);
+    assert_eq!(budget["window_source"], "config");
+    assert_eq!(budget["truncated"], true);
+    assert!(budget["prompt_tokens"].as_u64().unwrap() <= 350);
+
+    let requests = llm.generate_requests();
+    let sent = &requests[0];
+    assert_eq!(sent["options"]["num_ctx"], 600);
+    assert!(estimate_tokens(sent["prompt"].as_str().unwrap()) <= 350);
+    // Citations only cover the chunks that made it into the prompt, numbered in order
+    let citations = data["citations"].as_array().unwrap();
+    assert_eq!(citations.len() as u64, data["context_chunks"].as_u64().unwrap());
+    for (i, citation) in citations.iter().enumerate() {
+        assert_eq!(citation["marker"], i + 1);
+    }
+}
+
+#[tokio::test]
+async fn summaries_use_the_window_reported_by_ollama() {
+    let llm = mock_llm("num_ctx 600\nstop \"<|end|>\"").await;
+    let config = ContextConfig { answer_reserve: 200, ..Default::default() };
+    let (mcp, db, _dir) = setup(llm.client(), config).await;
+    let document_id = db.list_documents_page(1, 0, None).await.unwrap()[0].id;
+
+    let request = MCPRequest {
+        tool: "summarize_note".to_string(),
+        arguments: serde_json::json!({"id": document_id, "length": "short"}),
+    };
+    let data = mcp.handle_request(request).await.unwrap().data.unwrap();
+
+    let budget = &data["context_budget"];
+    assert_eq!(budget["context_window"], 600);
+    assert_eq!(budget["window_source"], "ollama");
+    // The note does not fit, so it is summarized in sections rather than cut
+    assert!(data["sections"].as_u64().unwrap() > 1);
+    assert_eq!(budget["truncated"], false);
+    for request in &llm.generate_requests() {
+        assert_eq!(request["options"]["num_ctx"], 600);
+        assert!(estimate_tokens(request["prompt"].as_str().unwrap()) <= 400);
+    }
+
+    // Without a window from either source, Ollama's default is assumed
+    let unreachable = OllamaClient::new("http://127.0.0.1:9".to_string(), common::MODEL.to_string());
+    let (mcp, db, _dir) = setup(unreachable, ContextConfig::default()).await;
+    let document_id = db.list_documents_page(1, 0, None).await.unwrap()[0].id;
+    let request = MCPRequest {
+        tool: "preview_prompt".to_string(),
+        arguments: serde_json::json!({"kind": "summary", "id": document_id}),
+    };
+    let data = mcp.handle_request(request).await.unwrap().data.unwrap();
+    assert_eq!(data["budget"]["window_source"], "default");
+    assert_eq!(data["budget"]["context_window"], 4096);
+    assert_eq!(data["budget"]["truncated"], false);
+}
+
+#[tokio::test]
+async fn windows_are_looked_up_once_per_model() {
+    let llm = MockLlm::new().start().await;
+    let budgeter = ContextBudgeter::new(LlmClient::from(llm.client()), ContextConfig::default());
+
+    // Unknown models fall back to the default, which is cached as well
+    for _ in 0..2 {
+        let window = budgeter.context_window("missing-model").await;
+        assert_eq!((window.tokens, window.source.as_str()), (4096, "default"));
+    }
+    assert_eq!(budgeter.context_window(common::MODEL).await.source, "ollama");
+    budgeter.context_window(common::MODEL).await;
+    assert_eq!(llm.shown(), vec!["missing-model", common::MODEL]);
+}
+
+#[tokio::test]
+async fn failed_window_lookups_are_not_cached() {
+    // A description Ollama could not have sent fails to decode
+    let llm = MockLlm::new().describe("broken-model", serde_json::json!({"model_info": "unreadable"})).start().await;
+    let budgeter = ContextBudgeter::new(LlmClient::from(llm.client()), ContextConfig::default());
+
+    for _ in 0..2 {
+        let window = budgeter.context_window("broken-model").await;
+        assert_eq!((window.tokens, window.source.as_str()), (4096, "default"));
+    }
+    assert_eq!(llm.shown(), vec!["broken-model", "broken-model"]);
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{call, index_notes, MockLlm};
+use knowledge_base_backend::mcp::MCPServer;
+use knowledge_base_backend::ollama::OllamaClient;
+
+const REWRITTEN: &str = "What is the rollback plan for the blue-green deployment?";
+
+async fn setup(ollama: OllamaClient) -> (MCPServer, tempfile::TempDir) {
+    let notes = [
+        ("rollout.md", "# Rollout options\n\nOption one is a canary rollout. Option two is a blue-green deployment."),
+        ("rollback.md", "# Rollback\n\nThe rollback plan switches traffic back within five minutes."),
+    ];
+    let (db, temp_dir) = index_notes(&notes, None).await;
+    (MCPServer::new(db, ollama), temp_dir)
+}
+
+#[tokio::test]
+async fn follow_ups_are_rewritten_from_earlier_turns_and_stored() {
+    // Condense prompts get the rewritten question, every other prompt a fixed answer
+    let llm = MockLlm::new().reply_when("Follow-up question:", REWRITTEN).reply_when("", "It is reverted.").start().await;
+    let (mcp, _dir) = setup(llm.client()).await;
+
+    let started = call(&mcp, "start_conversation", serde_json::json!({"title": "Rollout"})).await.unwrap();
+    let id = started["conversation"]["id"].as_str().unwrap().to_string();
+
+    let first = call(&mcp, "answer_question", serde_json::json!({"question": "What are the rollout options?", "conversation_id": id}))
+        .await
+        .unwrap();
+    assert_eq!(first["turn"], 1);
+    assert_eq!(first["standalone_question"], "What are the rollout options?");
+    // The first question has nothing to be rewritten from
+    assert!(!llm.prompts().iter().any(|p| p.contains("Follow-up question:")));
+
+    let second = call(&mcp, "answer_question", serde_json::json!({"question": "And how is the second one rolled back?", "conversation_id": id}))
+        .await
+        .unwrap();
+    assert_eq!(second["turn"], 2);
+    assert_eq!(second["standalone_question"], REWRITTEN);
+    let condense = llm.prompts().into_iter().find(|p| p.contains("Follow-up question:")).unwrap();
+    assert!(condense.contains("User: What are the rollout options?\nAssistant: It is reverted."));
+    assert!(condense.contains("Follow-up question: And how is the second one rolled back?"));
+    let cited: Vec<&str> = second["citations"].as_array().unwrap().iter().filter_map(|c| c["filename"].as_str()).collect();
+    assert!(cited.contains(&"rollback.md"));
+    assert!(llm.prompts().last().unwrap().contains(REWRITTEN));
+
+    // Previews do not ask the model to rewrite the follow-up
+    let sent = llm.prompts().len();
+    let preview = call(&mcp, "preview_prompt", serde_json::json!({"kind": "answer", "question": "And the first one?", "conversation_id": id}))
+        .await
+        .unwrap();
+    assert_eq!(llm.prompts().len(), sent);
+    assert!(preview["prompt"].as_str().unwrap().contains(&format!("{} And the first one?", REWRITTEN)));
+
+    let conversation = call(&mcp, "get_conversation", serde_json::json!({"id": id})).await.unwrap();
+    assert_eq!(conversation["conversation"]["turn_count"], 2);
+    let turns = conversation["turns"].as_array().unwrap();
+    assert_eq!(turns[1]["question"], "And how is the second one rolled back?");
+    let stored: Vec<&str> = turns[1]["citations"].as_array().unwrap().iter().filter_map(|c| c["filename"].as_str()).collect();
+    assert_eq!(stored, cited);
+
+    let listed = call(&mcp, "list_conversations", serde_json::json!({})).await.unwrap();
+    assert_eq!(listed["conversations"][0]["title"], "Rollout");
+
+    call(&mcp, "delete_conversation", serde_json::json!({"id": id})).await.unwrap();
+    assert!(call(&mcp, "get_conversation", serde_json::json!({"id": id})).await.is_err());
+    assert!(call(&mcp, "answer_question", serde_json::json!({"question": "Again?", "conversation_id": id})).await.is_err());
+}
+
+#[tokio::test]
+async fn follow_ups_fall_back_to_the_previous_question_without_a_model() {
+    // Nothing listens here, so rewriting fails and unanswerable questions
+    // get the fixed no-context answer
+    let (mcp, _dir) = setup(OllamaClient::new("http://127.0.0.1:9".to_string(), common::MODEL.to_string())).await;
+    let started = call(&mcp, "start_conversation", serde_json::json!({})).await.unwrap();
+    let id = started["conversation"]["id"].as_str().unwrap().to_string();
+
+    call(&mcp, "answer_question", serde_json::json!({"question": "zebra migration?", "conversation_id": id})).await.unwrap();
+    let second = call(&mcp, "answer_question", serde_json::json!({"question": "and giraffes?", "conversation_id": id}))
+        .await
+        .unwrap();
+    assert_eq!(second["standalone_question"], "zebra migration? and giraffes?");
+
+    // Questions outside a conversation are not recorded
+    let plain = call(&mcp, "answer_question", serde_json::json!({"question": "zebra migration?"})).await.unwrap();
+    assert!(plain.get("conversation_id").is_none());
+    let conversation = call(&mcp, "get_conversation", serde_json::json!({"id": id})).await.unwrap();
+    assert_eq!(conversation["turns"].as_array().unwrap().len(), 2);
+}
@@ -21,6 +21,7 @@ fn make_document(path: &str, version: u32, is_latest: bool) -> Document {
         version,
         is_latest,
         project_id: None,
+        language: None,
     }
 }
 
@@ -87,6 +88,7 @@ async fn index_entries_round_trip() {
         chunk_id: 1,
         chunk_text: "Hello world".to_string(),
         positions: vec![0, 6],
+        start_offset: 0,
     };
 
     db.insert_index_entries(&[entry.clone()]).await.unwrap();
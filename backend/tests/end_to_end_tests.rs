@@ -0,0 +1,206 @@
+mod common;
+
+use common::{MockLlm, MockLlmServer};
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::llm::LlmClient;
+use knowledge_base_backend::mcp::MCPServer;
+use knowledge_base_backend::server;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// The API served on an ephemeral port, backed by an in-memory database
+/// and the mock LLM.
+struct TestApp {
+    url: String,
+    http: reqwest::Client,
+    llm: MockLlmServer,
+}
+
+impl TestApp {
+    async fn start(llm: MockLlm) -> Self {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let llm = llm.start().await;
+        let client = LlmClient::from(llm.client());
+        let mcp = MCPServer::new(db.clone(), client.clone());
+
+        let (address, app) = warp::serve(server::api_routes(db, client, mcp)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(app);
+        Self { url: format!("http://{}", address), http: reqwest::Client::new(), llm }
+    }
+
+    /// POST a tool call to `/api/request` and return the whole response.
+    async fn request(&self, tool: &str, arguments: serde_json::Value) -> serde_json::Value {
+        let body = serde_json::json!({"tool": tool, "arguments": arguments});
+        let response = self.http.post(format!("{}/api/request", self.url)).json(&body).send().await.unwrap();
+        assert!(response.status().is_success());
+        response.json().await.unwrap()
+    }
+
+    /// The data of a successful tool call.
+    async fn call(&self, tool: &str, arguments: serde_json::Value) -> serde_json::Value {
+        let response = self.request(tool, arguments).await;
+        assert_eq!(response["success"], true, "{} failed: {}", tool, response["error"]);
+        response["data"].clone()
+    }
+
+    async fn index(&self, folder: &Path) -> serde_json::Value {
+        let body = serde_json::json!({"folders": [folder], "project_id": null});
+        let response = self.http.post(format!("{}/api/index", self.url)).json(&body).send().await.unwrap();
+        let response: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(response["success"], true, "indexing failed: {}", response["message"]);
+        response["result"].clone()
+    }
+}
+
+fn notes_folder() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("deploy.md"), "# Deploys\n\nDeploys happen on Tuesdays after the standup.\nRollbacks need a second reviewer.").unwrap();
+    fs::write(dir.path().join("budget.md"), "# Budget\n\nThe infrastructure budget is reviewed every quarter.").unwrap();
+    dir
+}
+
+fn filenames(data: &serde_json::Value) -> Vec<String> {
+    data["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| result["document"]["filename"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn notes_are_indexed_searched_answered_and_diffed_over_http() {
+    let app = TestApp::start(MockLlm::new().reply_when("When do deploys happen?", "Deploys happen on Tuesdays [1].")).await;
+    let dir = notes_folder();
+    let folder = fs::canonicalize(dir.path()).unwrap();
+
+    let indexed = app.index(&folder).await;
+    assert_eq!(indexed["files_processed"], 2);
+    // Chunks were embedded through the mock
+    assert!(app.llm.embedded().iter().any(|text| text.contains("Tuesdays")));
+
+    let keyword = app.call("search_notes", serde_json::json!({"query": "rollbacks"})).await;
+    assert_eq!(filenames(&keyword), vec!["deploy.md"]);
+    let semantic = app.call("search_notes", serde_json::json!({"query": "infrastructure budget quarter", "mode": "semantic"})).await;
+    assert_eq!(filenames(&semantic)[0], "budget.md");
+
+    let answer = app.call("answer_question", serde_json::json!({"question": "When do deploys happen?"})).await;
+    assert_eq!(answer["status"], "answered");
+    assert_eq!(answer["answer"], "Deploys happen on Tuesdays [1].");
+    assert_eq!(answer["citations"][0]["filename"], "deploy.md");
+    assert_eq!(answer["citations"][0]["used"], true);
+    let prompt = app.llm.prompts().pop().unwrap();
+    assert!(prompt.contains("Deploys happen on Tuesdays after the standup."));
+
+    // Editing a note and indexing again keeps both versions
+    let path = folder.join("deploy.md");
+    fs::write(&path, "# Deploys\n\nDeploys happen on Thursdays after the standup.\nRollbacks need a second reviewer.").unwrap();
+    let reindexed = app.index(&folder).await;
+    assert_eq!(reindexed["files_failed"], 0);
+
+    let versions = app.call("get_document_versions", serde_json::json!({"path": path})).await;
+    assert_eq!(versions["versions"].as_array().unwrap().len(), 2);
+    let diff = app.call("compare_versions", serde_json::json!({"path": path, "version_a": 1, "version_b": 2})).await;
+    assert_eq!(diff["diff"]["summary"]["added"], 1);
+    assert_eq!(diff["diff"]["summary"]["removed"], 1);
+    let changed: Vec<&str> = diff["diff"]["lines"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|line| line["type"] != "unchanged")
+        .map(|line| line["content"].as_str().unwrap())
+        .collect();
+    assert_eq!(changed, vec!["Deploys happen on Tuesdays after the standup.", "Deploys happen on Thursdays after the standup."]);
+}
+
+#[tokio::test]
+async fn summaries_stream_and_health_over_http() {
+    let app = TestApp::start(MockLlm::new().reply("Budget reviews are quarterly.")).await;
+    let dir = notes_folder();
+    app.index(dir.path()).await;
+
+    let health: serde_json::Value = app.http.get(format!("{}/api/health", app.url)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(health["status"], "healthy");
+    assert_eq!(health["llm"]["reachable"], true);
+
+    let notes = app.call("list_notes", serde_json::json!({"limit": 10})).await;
+    let budget = notes["notes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|note| note["filename"] == "budget.md")
+        .unwrap()
+        .clone();
+
+    // Streamed summaries arrive as server-sent events, one token per word
+    let body = serde_json::json!({"tool": "summarize_note", "arguments": {"id": budget["id"], "length": "short"}});
+    let response = app.http.post(format!("{}/api/stream", app.url)).json(&body).send().await.unwrap();
+    let events = tokio::time::timeout(Duration::from_secs(10), response.text()).await.unwrap().unwrap();
+    let names: Vec<&str> = events.lines().filter_map(|line| line.strip_prefix("event:")).collect();
+    assert_eq!(names.first(), Some(&"citations"));
+    assert_eq!(names.last(), Some(&"done"));
+    assert_eq!(names.iter().filter(|name| **name == "token").count(), 4);
+    assert!(events.contains("quarterly."));
+
+    // The summary was cached by the stream
+    let summary = app.call("summarize_note", serde_json::json!({"id": budget["id"], "length": "short"})).await;
+    assert_eq!(summary["summary"], "Budget reviews are quarterly.");
+    assert_eq!(summary["cached"], true);
+    assert_eq!(app.llm.generate_requests().len(), 1);
+    assert_eq!(app.llm.generate_requests()[0]["stream"], true);
+}
+
+#[tokio::test]
+async fn failed_tool_calls_are_reported_in_the_response() {
+    let app = TestApp::start(MockLlm::new()).await;
+
+    let unknown = app.request("no_such_tool", serde_json::json!({})).await;
+    assert_eq!(unknown["success"], false);
+    assert!(unknown["error"].as_str().unwrap().contains("no_such_tool"));
+
+    let missing = app.request("answer_question", serde_json::json!({})).await;
+    assert_eq!(missing["success"], false);
+    assert_eq!(missing["error"], "Missing required field: question");
+
+    // Without notes the model is not asked
+    let answer = app.call("answer_question", serde_json::json!({"question": "Anything?"})).await;
+    assert_eq!(answer["status"], "no_context");
+    assert!(app.llm.prompts().is_empty());
+}
+
+#[tokio::test]
+async fn prompt_templates_are_replaced_and_bad_bodies_rejected() {
+    let app = TestApp::start(MockLlm::new()).await;
+    let url = format!("{}/api/prompts/answer", app.url);
+
+    let rejected = app.http.put(&url).json(&serde_json::json!(["not", "an", "object"])).send().await.unwrap();
+    assert_eq!(rejected.status(), 400);
+    let rejected: serde_json::Value = rejected.json().await.unwrap();
+    assert_eq!(rejected["success"], false);
+
+    // Setting the same override twice keeps one row
+    for template in ["First {context} {question}", "Second {context} {question}"] {
+        let response = app.http.put(&url).json(&serde_json::json!({"template": template})).send().await.unwrap();
+        let response: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(response["success"], true, "{}", response["error"]);
+    }
+    let list: serde_json::Value = app.http.get(format!("{}/api/prompts", app.url)).send().await.unwrap().json().await.unwrap();
+    let overrides = list["data"]["overrides"].as_array().unwrap();
+    assert_eq!(overrides.len(), 1);
+    assert_eq!(overrides[0]["template"], "Second {context} {question}");
+}
+
+#[tokio::test]
+async fn conversation_messages_must_be_json_objects() {
+    let app = TestApp::start(MockLlm::new()).await;
+    let started = app.call("start_conversation", serde_json::json!({})).await;
+    let url = format!("{}/api/conversations/{}/messages", app.url, started["conversation"]["id"].as_str().unwrap());
+
+    let rejected = app.http.post(&url).json(&serde_json::json!("When do deploys happen?")).send().await.unwrap();
+    assert_eq!(rejected.status(), 400);
+    let response = app.http.post(&url).json(&serde_json::json!({"question": "When do deploys happen?"})).send().await.unwrap();
+    let response: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(response["data"]["turn"], 1);
+}
@@ -0,0 +1,57 @@
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::search::{FacetCount, SearchEngine, SearchOptions};
+use std::fs;
+
+fn count(facets: &[FacetCount], value: &str) -> u32 {
+    facets.iter().find(|f| f.value == value).map(|f| f.count).unwrap_or(0)
+}
+
+#[tokio::test]
+async fn facets_cover_the_full_matched_set() {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let project = db.create_project("Infra", None).await.unwrap();
+
+    let infra_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        infra_dir.path().join("deploy.md"),
+        "---\ntags: [ops, release]\n---\n# Deploy\n\nRollout checklist for the release.",
+    )
+    .unwrap();
+    fs::write(
+        infra_dir.path().join("oncall.md"),
+        "---\ntags: [ops]\n---\n# Oncall\n\nThe release pager rotation.",
+    )
+    .unwrap();
+    let other_dir = tempfile::tempdir().unwrap();
+    fs::write(other_dir.path().join("journal.txt"), "Shipped the release today.").unwrap();
+    fs::write(other_dir.path().join("groceries.txt"), "Milk and bread.").unwrap();
+
+    let corpus = CorpusManager::new(db.clone(), vec![]);
+    corpus.index_folder(infra_dir.path(), Some(&project.id)).await.unwrap();
+    corpus.index_folder(other_dir.path(), None).await.unwrap();
+
+    let engine = SearchEngine::new(db.clone());
+    let (results, facets) = engine.search_with_facets("release", None, 1, 0, false).await.unwrap();
+
+    // Only one result on the page, but facets count all three matches
+    assert_eq!(results.len(), 1);
+    assert_eq!(count(&facets.file_types, "md"), 2);
+    assert_eq!(count(&facets.file_types, "txt"), 1);
+    assert_eq!(count(&facets.tags, "ops"), 2);
+    assert_eq!(count(&facets.tags, "release"), 1);
+    assert_eq!(facets.projects.len(), 1);
+    assert_eq!(facets.projects[0].count, 2);
+    assert_eq!(facets.projects[0].label.as_deref(), Some("Infra"));
+    assert_eq!(facets.folders.iter().map(|f| f.count).sum::<u32>(), 3);
+    assert_eq!(facets.years.iter().map(|f| f.count).sum::<u32>(), 3);
+
+    // Sorted by count, highest first
+    assert_eq!(facets.file_types[0].value, "md");
+
+    let response = engine.search_page("release", &SearchOptions::default()).await.unwrap();
+    assert!(response.facets.is_none());
+    let response = engine.search_page("release", &SearchOptions { include_facets: true, ..Default::default() }).await.unwrap();
+    assert_eq!(response.facets.unwrap().file_types.len(), 2);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::index_notes;
+use knowledge_base_backend::analysis::edit_distance;
+use knowledge_base_backend::search::{SearchEngine, SearchOptions};
+
+async fn indexed_engine() -> (SearchEngine, tempfile::TempDir) {
+    let notes = [
+        (
+            "budget.md",
+            "---\ntags: [finance, planning]\n---\n# Quarterly budget\n\nThe budget review covers infrastructure spending.",
+        ),
+        ("deploy.md", "# Deployment\n\nKubernetes deployment checklist for the staging cluster."),
+    ];
+    let (db, temp_dir) = index_notes(&notes, None).await;
+    (SearchEngine::new(db), temp_dir)
+}
+
+#[test]
+fn edit_distance_counts_transpositions_as_one_edit() {
+    assert_eq!(edit_distance("budget", "budget"), 0);
+    assert_eq!(edit_distance("budgte", "budget"), 1);
+    assert_eq!(edit_distance("kubernets", "kubernetes"), 1);
+    assert_eq!(edit_distance("cluster", "blister"), 2);
+}
+
+#[tokio::test]
+async fn typo_falls_back_to_suggestion() {
+    let (engine, _dir) = indexed_engine().await;
+
+    let response = engine.search_page("budgte", &SearchOptions::default()).await.unwrap();
+    assert_eq!(response.suggestion.as_deref(), Some("budget"));
+    assert!(response.used_suggestion);
+    assert_eq!(response.results.len(), 1);
+    assert_eq!(response.results[0].document.filename, "budget.md");
+}
+
+#[tokio::test]
+async fn only_unmatched_terms_are_corrected() {
+    let (engine, _dir) = indexed_engine().await;
+
+    let response = engine.search_page("kubernetes stagign", &SearchOptions::default()).await.unwrap();
+    assert_eq!(response.suggestion.as_deref(), Some("kubernetes staging"));
+    // "kubernetes" alone already matched, so the original results are kept
+    assert!(!response.used_suggestion);
+    assert!(!response.results.is_empty());
+}
+
+#[tokio::test]
+async fn exact_queries_have_no_suggestion() {
+    let (engine, _dir) = indexed_engine().await;
+
+    let response = engine.search_page("budget", &SearchOptions::default()).await.unwrap();
+    assert!(response.suggestion.is_none());
+    assert!(!response.used_suggestion);
+
+    // Tags are part of the vocabulary too
+    let response = engine.search_page("planing", &SearchOptions::default()).await.unwrap();
+    assert_eq!(response.suggestion.as_deref(), Some("planning"));
+}
+
+#[tokio::test]
+async fn well_matched_queries_are_not_checked_for_typos() {
+    let budget = "# Budget\n\nThe budget covers the cluster.";
+    let notes = [("one.md", budget), ("two.md", budget), ("three.md", budget), ("four.md", "# Staging\n\nThe staging cluster.")];
+    let (db, _dir) = index_notes(&notes, None).await;
+    let engine = SearchEngine::new(db);
+
+    let response = engine.search_page("budget stagng", &SearchOptions::default()).await.unwrap();
+    assert_eq!(response.total, 3);
+    assert!(response.suggestion.is_none());
+
+    // Typos in the first letter are not corrected
+    let response = engine.search_page("xtaging", &SearchOptions::default()).await.unwrap();
+    assert!(response.suggestion.is_none());
+}
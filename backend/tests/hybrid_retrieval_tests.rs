@@ -0,0 +1,115 @@
+mod common;
+
+use common::{MockLlm, CONCEPTS};
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::search::{retrieval_confidence, SearchEngine};
+use std::fs;
+
+async fn setup_db() -> Database {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    db
+}
+
+#[tokio::test]
+async fn lexical_retrieval_ranks_and_limits_chunks_per_document() {
+    let db = setup_db().await;
+    let temp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        temp_dir.path().join("deploy.md"),
+        "# Deploy\n\nThe staging deployment uses the blue cluster.",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("misc.md"), "# Misc\n\nStaging is sometimes slow.").unwrap();
+    // A long note whose chunks all mention staging
+    let paragraph = "Staging notes paragraph with filler words about nothing in particular. ";
+    fs::write(temp_dir.path().join("long.md"), paragraph.repeat(60)).unwrap();
+
+    CorpusManager::new(db.clone(), vec![]).index_folder(temp_dir.path(), None).await.unwrap();
+    let engine = SearchEngine::new(db.clone());
+
+    let chunks = engine
+        .get_relevant_chunks_for_qa_with_filters("Which cluster does the staging deployment use?", 5, None, 1, None)
+        .await
+        .unwrap();
+
+    assert_eq!(chunks[0].document.filename, "deploy.md");
+    assert_eq!(chunks[0].lexical_rank, Some(1));
+    assert!((chunks[0].score - 1.0).abs() < f32::EPSILON);
+    assert!(chunks.windows(2).all(|w| w[0].score >= w[1].score));
+
+    let long_chunks = chunks.iter().filter(|c| c.document.filename == "long.md").count();
+    assert_eq!(long_chunks, 1);
+    assert!(retrieval_confidence(&chunks) > 0.0);
+}
+
+#[tokio::test]
+async fn confidence_follows_how_well_chunks_match_not_their_rank() {
+    let db = setup_db().await;
+    let temp_dir = tempfile::tempdir().unwrap();
+    fs::write(temp_dir.path().join("deploy.md"), "# Deploy
+
+The staging deployment uses the blue cluster.").unwrap();
+    CorpusManager::new(db.clone(), vec![]).index_folder(temp_dir.path(), None).await.unwrap();
+    let engine = SearchEngine::new(db.clone());
+
+    let matched = engine.get_relevant_chunks_for_qa("Which cluster does the staging deployment use?", 5).await.unwrap();
+    assert_eq!(matched[0].keyword_coverage, Some(1.0));
+
+    // The only chunk ranks first, but shares just one of the keywords
+    let weak = engine.get_relevant_chunks_for_qa("Which budget spreadsheet tracks cluster invoices?", 5).await.unwrap();
+    assert!((weak[0].score - 1.0).abs() < f32::EPSILON);
+    assert!(weak[0].keyword_coverage.unwrap() < 0.5);
+    assert!(retrieval_confidence(&weak) < retrieval_confidence(&matched));
+    assert!(retrieval_confidence(&weak) < 0.1);
+}
+
+#[tokio::test]
+async fn duplicate_chunks_are_dropped() {
+    let db = setup_db().await;
+    let temp_dir = tempfile::tempdir().unwrap();
+    let content = "# Backup\n\nBackups run nightly and are kept for thirty days.";
+    fs::write(temp_dir.path().join("backup.md"), content).unwrap();
+    fs::write(temp_dir.path().join("backup-copy.md"), content).unwrap();
+
+    CorpusManager::new(db.clone(), vec![]).index_folder(temp_dir.path(), None).await.unwrap();
+    let engine = SearchEngine::new(db.clone());
+
+    let chunks = engine.get_relevant_chunks_for_qa("How long are backups kept?", 5).await.unwrap();
+    assert_eq!(chunks.len(), 1);
+}
+
+#[tokio::test]
+async fn hybrid_retrieval_fuses_semantic_candidates() {
+    let llm = MockLlm::new().concepts(CONCEPTS).start().await;
+    let db = setup_db().await;
+    let temp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        temp_dir.path().join("garage.md"),
+        "# Garage\n\nThe vehicle needs an engine service before winter.",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("kitchen.md"),
+        "# Kitchen\n\nA quick recipe for a weekday dinner.",
+    )
+    .unwrap();
+
+    let client = llm.client();
+    CorpusManager::new(db.clone(), vec![])
+        .with_embeddings(client.clone())
+        .index_folder(temp_dir.path(), None)
+        .await
+        .unwrap();
+
+    let lexical_only = SearchEngine::new(db.clone());
+    let chunks = lexical_only.get_relevant_chunks_for_qa("When should the automobile be serviced?", 1).await.unwrap();
+    assert!(chunks.iter().all(|c| c.document.filename != "garage.md"));
+
+    let hybrid = SearchEngine::new(db.clone()).with_embeddings(client);
+    let chunks = hybrid.get_relevant_chunks_for_qa("When should the automobile be serviced?", 1).await.unwrap();
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].document.filename, "garage.md");
+    assert_eq!(chunks[0].semantic_rank, Some(1));
+}
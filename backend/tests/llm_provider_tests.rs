@@ -0,0 +1,182 @@
+use knowledge_base_backend::config::Config;
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::llm::{self, GenerationOptions, LlmClient, LlmConfig, ProviderKind};
+use knowledge_base_backend::mcp::{MCPRequest, MCPServer};
+use knowledge_base_backend::openai::OpenAiClient;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use warp::http::StatusCode;
+use warp::Filter;
+
+const API_KEY: &str = "test-secret";
+
+fn unauthorized() -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({"error": {"message": "invalid api key"}})),
+        StatusCode::UNAUTHORIZED,
+    )
+}
+
+/// A stub OpenAI-compatible server requiring [`API_KEY`]: completions echo
+/// "Hello [1]." (streamed in two deltas when asked to), embeddings are
+/// fixed and model `m` has a 2048 token window. Request bodies are recorded.
+async fn stub_openai(requests: Arc<Mutex<Vec<serde_json::Value>>>) -> String {
+    let authorized = warp::header::optional::<String>("authorization")
+        .map(|header: Option<String>| header.as_deref() == Some(&format!("Bearer {}", API_KEY)));
+
+    let chat = warp::path!("v1" / "chat" / "completions")
+        .and(warp::post())
+        .and(authorized)
+        .and(warp::body::json())
+        .map(move |authorized: bool, body: serde_json::Value| {
+            if !authorized {
+                return warp::http::Response::builder()
+                    .status(StatusCode::UNAUTHORIZED)
+                    .body(serde_json::json!({"error": {"message": "invalid api key"}}).to_string())
+                    .unwrap();
+            }
+            let stream = body["stream"] == true;
+            requests.lock().unwrap().push(body);
+            if stream {
+                let events = ["Hello", " [1]."]
+                    .iter()
+                    .map(|token| format!("data: {}\n\n", serde_json::json!({"choices": [{"delta": {"content": token}}]})))
+                    .collect::<String>();
+                warp::http::Response::new(format!(": keep-alive\n\n{}data: [DONE]\n\n", events))
+            } else {
+                let completion = serde_json::json!({"choices": [{"message": {"role": "assistant", "content": "Hello [1]."}}]});
+                warp::http::Response::new(completion.to_string())
+            }
+        });
+    let embeddings = warp::path!("v1" / "embeddings")
+        .and(warp::post())
+        .and(authorized)
+        .map(|authorized: bool| {
+            if !authorized {
+                return unauthorized();
+            }
+            warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({"data": [{"embedding": [0.6, 0.8]}]})),
+                StatusCode::OK,
+            )
+        });
+    let models = warp::path!("v1" / "models")
+        .and(warp::get())
+        .and(authorized)
+        .map(|authorized: bool| {
+            if !authorized {
+                return unauthorized();
+            }
+            warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({"data": [{"id": "m", "max_model_len": 2048}]})),
+                StatusCode::OK,
+            )
+        });
+
+    let (address, server) = warp::serve(chat.or(embeddings).or(models)).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    format!("http://{}/v1", address)
+}
+
+#[tokio::test]
+async fn openai_provider_generates_streams_and_embeds() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let base_url = stub_openai(requests.clone()).await;
+    let client: LlmClient = OpenAiClient::new(base_url.clone(), "m".to_string())
+        .with_embedding_model("e".to_string())
+        .with_api_key(API_KEY.to_string())
+        .into();
+    assert_eq!(client.provider_name(), "openai");
+
+    let options = GenerationOptions { temperature: Some(0.2), max_tokens: Some(50), num_ctx: Some(4096), ..Default::default() };
+    assert_eq!(client.generate("Say hello", &options).await.unwrap(), "Hello [1].");
+    {
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0]["model"], "m");
+        assert_eq!(requests[0]["messages"], serde_json::json!([{"role": "user", "content": "Say hello"}]));
+        assert_eq!(requests[0]["max_tokens"], 50);
+        // Ollama-only settings are not sent
+        assert!(requests[0].get("num_ctx").is_none() && requests[0].get("top_p").is_none());
+    }
+
+    let mut tokens = Vec::new();
+    let text = client.generate_stream("Say hello", &options, |token| tokens.push(token.to_string())).await.unwrap();
+    assert_eq!(tokens, vec!["Hello", " [1]."]);
+    assert_eq!(text, "Hello [1].");
+
+    assert_eq!(client.embed("text").await.unwrap(), vec![0.6, 0.8]);
+    assert!(client.health_check().await.unwrap());
+    assert_eq!(client.context_window("m").await.unwrap(), Some(2048));
+
+    // Without the key the server's message is reported
+    let anonymous: LlmClient = OpenAiClient::new(base_url, "m".to_string()).into();
+    let error = anonymous.generate("Say hello", &options).await.unwrap_err();
+    assert!(error.to_string().contains("invalid api key"));
+    assert!(!anonymous.health_check().await.unwrap());
+}
+
+#[tokio::test]
+async fn questions_are_answered_through_an_openai_compatible_server() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let base_url = stub_openai(requests.clone()).await;
+
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let temp_dir = tempfile::tempdir().unwrap();
+    fs::write(temp_dir.path().join("greeting.md"), "# Greeting\n\nThe team says hello every morning.").unwrap();
+    let client = OpenAiClient::new(base_url, "m".to_string()).with_api_key(API_KEY.to_string());
+    CorpusManager::new(db.clone(), vec![]).with_embeddings(client.clone()).index_folder(temp_dir.path(), None).await.unwrap();
+
+    let mcp = MCPServer::new(db, client);
+    let request = MCPRequest {
+        tool: "answer_question".to_string(),
+        arguments: serde_json::json!({"question": "What does the team say every morning?"}),
+    };
+    let data = mcp.handle_request(request).await.unwrap().data.unwrap();
+    assert_eq!(data["answer"], "Hello [1].");
+    assert_eq!(data["citations"][0]["used"], true);
+    assert_eq!(data["context_budget"]["window_source"], "openai");
+    assert_eq!(data["context_budget"]["context_window"], 2048);
+
+    let requests = requests.lock().unwrap();
+    let prompt = requests.last().unwrap()["messages"][0]["content"].as_str().unwrap().to_string();
+    assert!(prompt.contains("says hello every morning"));
+}
+
+#[test]
+fn config_selects_the_provider_and_keeps_keys_out_of_the_file() {
+    let llm: LlmConfig = toml::from_str(
+        "provider = \"openai\"\nbase_url = \"http://localhost:8000/v1\"\nmodel = \"qwen\"\napi_key_env = \"KB_TEST_PROVIDER_KEY\"",
+    )
+    .unwrap();
+    assert_eq!(llm.provider, ProviderKind::OpenAi);
+
+    // The key comes from the named environment variable, else the key file
+    assert_eq!(llm.api_key().unwrap(), None);
+    std::env::set_var("KB_TEST_PROVIDER_KEY", "from-env");
+    assert_eq!(llm.api_key().unwrap().as_deref(), Some("from-env"));
+    std::env::remove_var("KB_TEST_PROVIDER_KEY");
+
+    let key_dir = tempfile::tempdir().unwrap();
+    let key_file = key_dir.path().join("llm.key");
+    fs::write(&key_file, "from-file\n").unwrap();
+    let with_file = LlmConfig { api_key_file: Some(key_file), ..llm.clone() };
+    assert_eq!(with_file.api_key().unwrap().as_deref(), Some("from-file"));
+    let missing = LlmConfig { api_key_file: Some(key_dir.path().join("missing.key")), ..llm.clone() };
+    assert!(missing.api_key().is_err());
+
+    let config = Config { llm: llm.clone(), ..Config::default() };
+    let client = llm::client_from_config(&config).unwrap();
+    assert_eq!(client.provider_name(), "openai");
+    assert_eq!(client.model(), "qwen");
+    assert_eq!(client.embedding_model(), config.embedding_model);
+
+    // Ollama stays the default and uses the top-level settings
+    let client = llm::client_from_config(&Config::default()).unwrap();
+    assert_eq!(client.provider_name(), "ollama");
+    assert_eq!(client.model(), Config::default().ollama_model);
+
+    let without_url = Config { llm: LlmConfig { base_url: None, ..llm }, ..Config::default() };
+    assert!(llm::client_from_config(&without_url).is_err());
+}
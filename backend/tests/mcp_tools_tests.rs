@@ -1,27 +1,7 @@
-use knowledge_base_backend::{database::{Database, Document}, mcp::{MCPServer, MCPRequest}};
-use chrono::{TimeZone, Utc};
-use uuid::Uuid;
-use std::path::PathBuf;
+mod common;
 
-fn make_document(path: &str, version: u32, is_latest: bool) -> Document {
-    Document {
-        id: Uuid::new_v4(),
-        path: PathBuf::from(path),
-        filename: PathBuf::from(path).file_name().unwrap().to_string_lossy().to_string(),
-        extension: "md".to_string(),
-        size: 10,
-        modified_at: Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
-        title: Some("Doc".to_string()),
-        tags: vec![],
-        headings: vec![],
-        content_excerpt: "excerpt".to_string(),
-        content_hash: "hash".to_string(),
-        indexed_at: Utc.timestamp_opt(1_700_000_100, 0).unwrap(),
-        version,
-        is_latest,
-        project_id: None,
-    }
-}
+use common::MockLlm;
+use knowledge_base_backend::{corpus::CorpusManager, database::Database, mcp::{MCPServer, MCPRequest}};
 
 #[tokio::test]
 async fn tools_list_contains_required_tools() {
@@ -48,31 +28,50 @@ async fn tools_list_contains_required_tools() {
 async fn answer_question_includes_version_fields_in_citations() {
     let db = Database::new("sqlite::memory:").await.unwrap();
     db.migrate().await.unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.md"), "# Deploys\n\nDeploys happen on Tuesdays after the standup.").unwrap();
+    CorpusManager::new(db.clone(), vec![]).index_folder(dir.path(), None).await.unwrap();
 
-    // Seed one document so search can return something. We won't exercise real search; tests validate structure.
-    let doc = make_document("/tmp/a.md", 1, true);
-    db.insert_document(&doc).await.unwrap();
-
-    let ollama = knowledge_base_backend::ollama::OllamaClient::new(
-        "http://localhost:11434".to_string(),
-        "gpt-oss:20b".to_string(),
-    );
-    let mcp = MCPServer::new(db.clone(), ollama);
+    let llm = MockLlm::new().reply("Deploys happen on Tuesdays [1].").start().await;
+    let mcp = MCPServer::new(db.clone(), llm.client());
 
-    // Build a fake request; since search depends on content, response may be empty. We accept empty.
-    let req = MCPRequest { tool: "answer_question".to_string(), arguments: serde_json::json!({"question": "test", "top_k": 1}) };
+    let req = MCPRequest { tool: "answer_question".to_string(), arguments: serde_json::json!({"question": "When do deploys happen?", "top_k": 1}) };
     let resp = mcp.handle_request(req).await.unwrap();
     assert!(resp.success);
-    if let Some(data) = resp.data {
-        if let Some(citations) = data.get("citations").and_then(|v| v.as_array()) {
-            for c in citations {
-                // Version fields must exist if citations exist
-                assert!(c.get("used_version").is_some());
-                assert!(c.get("latest_version").is_some());
-                assert!(c.get("is_latest").is_some());
-            }
-        }
+    let data = resp.data.unwrap();
+    assert_eq!(data["answer"], "Deploys happen on Tuesdays [1].");
+    assert_eq!(data["status"], "answered");
+    assert!(llm.prompts()[0].contains("When do deploys happen?"));
+
+    let citations = data["citations"].as_array().unwrap();
+    assert_eq!(citations.len(), 1);
+    for c in citations {
+        assert_eq!(c["used_version"], 1);
+        assert_eq!(c["latest_version"], 1);
+        assert_eq!(c["is_latest"], true);
+        assert_eq!(c["used"], true);
     }
 }
 
+#[tokio::test]
+async fn summarize_note_summarizes_with_the_model_and_caches() {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("retro.md"), "# Retro\n\nThe team agreed to shorten the release cycle.").unwrap();
+    CorpusManager::new(db.clone(), vec![]).index_folder(dir.path(), None).await.unwrap();
+    let id = db.list_documents_page(1, 0, None).await.unwrap()[0].id;
 
+    let llm = MockLlm::new().reply_when("shorten the release cycle", "- Shorter release cycle").start().await;
+    let mcp = MCPServer::new(db, llm.client());
+
+    let request = || MCPRequest { tool: "summarize_note".to_string(), arguments: serde_json::json!({"id": id, "length": "short"}) };
+    let data = mcp.handle_request(request()).await.unwrap().data.unwrap();
+    assert_eq!(data["summary"], "- Shorter release cycle");
+    assert_eq!(data["cached"], false);
+    assert!(llm.prompts()[0].contains("short summary"));
+
+    let again = mcp.handle_request(request()).await.unwrap().data.unwrap();
+    assert_eq!(again["cached"], true);
+    assert_eq!(llm.prompts().len(), 1);
+}
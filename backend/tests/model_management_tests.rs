@@ -0,0 +1,135 @@
+mod common;
+
+use common::{call, index_notes, MockLlm, MockLlmServer};
+use knowledge_base_backend::config::Config;
+use knowledge_base_backend::llm::{self, LlmClient, LlmConfig, ModelTask};
+use knowledge_base_backend::mcp::MCPServer;
+use knowledge_base_backend::ollama::OllamaClient;
+
+/// A mock Ollama with `llama3:latest` and `mistral:7b` installed. Generations
+/// answer "Answered by MODEL [1]." with the model they were asked for.
+async fn mock_llm() -> MockLlmServer {
+    MockLlm::new()
+        .with_models(&[])
+        .describe("llama3:latest", serde_json::json!({
+            "parameters": "stop \"<|eot_id|>\"\nnum_ctx 8192",
+            "template": "{{ .Prompt }}",
+            "details": {"family": "llama", "parameter_size": "8.0B", "quantization_level": "Q4_0"},
+            "model_info": {"llama.context_length": 131072}
+        }))
+        .describe("mistral:7b", serde_json::json!({
+            "details": {"family": "llama", "parameter_size": "7.2B"},
+            "model_info": {"llama.context_length": 32768}
+        }))
+        .reply_when("", "Answered by {model} [1].")
+        .start()
+        .await
+}
+
+/// Models of all generations so far.
+fn generated_with(llm: &MockLlmServer) -> Vec<String> {
+    llm.generate_requests().iter().map(|body| body["model"].as_str().unwrap_or_default().to_string()).collect()
+}
+
+async fn setup(llm: LlmClient) -> (MCPServer, tempfile::TempDir) {
+    let (db, temp_dir) = index_notes(&[("backups.md", "# Backups\n\nBackups run every night at two.")], None).await;
+    (MCPServer::new(db, llm), temp_dir)
+}
+
+#[tokio::test]
+async fn installed_models_are_listed_shown_and_checked() {
+    let mock = mock_llm().await;
+    let llm: LlmClient = OllamaClient::new(mock.url.clone(), "llama3".to_string()).into();
+    let (mcp, _dir) = setup(llm.with_task_models(None, Some("mistral:7b".to_string()))).await;
+
+    let list = call(&mcp, "list_models", serde_json::json!({})).await.unwrap();
+    assert_eq!(list["provider"], "ollama");
+    assert_eq!(list["models"][0]["name"], "llama3:latest");
+    assert_eq!(list["models"][0]["quantization"], "Q4_0");
+    assert_eq!(list["models"][1]["parameter_size"], "7.2B");
+    assert_eq!(list["defaults"], serde_json::json!({"answer": "llama3", "summary": "mistral:7b", "embedding": "nomic-embed-text"}));
+
+    // The Modelfile's num_ctx wins over the trained context length
+    let show = call(&mcp, "show_model", serde_json::json!({"name": "llama3"})).await.unwrap();
+    assert_eq!(show["model"]["context_window"], 8192);
+    assert_eq!(show["model"]["family"], "llama");
+    assert_eq!(show["used_for"], serde_json::json!(["answer"]));
+    let show = call(&mcp, "show_model", serde_json::json!({"name": "mistral:7b"})).await.unwrap();
+    // Without num_ctx, capped at Ollama's default window
+    assert_eq!(show["model"]["context_window"], 4096);
+    assert_eq!(show["used_for"], serde_json::json!(["summary"]));
+    let missing = call(&mcp, "show_model", serde_json::json!({"name": "phi3"})).await.unwrap_err();
+    assert!(missing.to_string().contains("Model not found"));
+
+    // "llama3" is installed as "llama3:latest"; the embedding model is missing
+    let check = call(&mcp, "check_models", serde_json::json!({})).await.unwrap();
+    assert_eq!(check["ok"], false);
+    assert_eq!(check["check"]["reachable"], true);
+    let available: Vec<(String, bool)> = check["check"]["models"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|status| (status["task"].as_str().unwrap().to_string(), status["available"].as_bool().unwrap()))
+        .collect();
+    assert_eq!(
+        available,
+        vec![("answer".to_string(), true), ("summary".to_string(), true), ("embedding".to_string(), false)]
+    );
+
+    // An unreachable server is reported rather than failing the check
+    let down: LlmClient = OllamaClient::new("http://127.0.0.1:9".to_string(), "llama3".to_string()).into();
+    let check = down.check_models().await;
+    assert!(!check.reachable && !check.ok());
+    assert!(check.error.is_some());
+}
+
+#[tokio::test]
+async fn requests_can_choose_their_model() {
+    let mock = mock_llm().await;
+    let llm: LlmClient = OllamaClient::new(mock.url.clone(), "llama3".to_string()).into();
+    let (mcp, _dir) = setup(llm.with_task_models(None, Some("mistral:7b".to_string()))).await;
+
+    let answer = call(&mcp, "answer_question", serde_json::json!({"question": "When do backups run?"})).await.unwrap();
+    assert_eq!(answer["model"], "llama3");
+    assert_eq!(answer["answer"], "Answered by llama3 [1].");
+
+    let answer = call(&mcp, "answer_question", serde_json::json!({"question": "When do backups run?", "model": "mistral:7b"}))
+        .await
+        .unwrap();
+    assert_eq!(answer["model"], "mistral:7b");
+    assert_eq!(generated_with(&mock), vec!["llama3", "mistral:7b"]);
+
+    // Summaries default to the summary model and are cached per model
+    let id = answer["citations"][0]["document_id"].as_str().unwrap().to_string();
+    let summary = call(&mcp, "summarize_note", serde_json::json!({"id": id})).await.unwrap();
+    assert_eq!(summary["model"], "mistral:7b");
+    assert_eq!(summary["cached"], false);
+    let other = call(&mcp, "summarize_note", serde_json::json!({"id": id, "model": "llama3"})).await.unwrap();
+    assert_eq!(other["model"], "llama3");
+    assert_eq!(other["cached"], false);
+    let again = call(&mcp, "summarize_note", serde_json::json!({"id": id})).await.unwrap();
+    assert_eq!(again["cached"], true);
+    assert_eq!(generated_with(&mock).len(), 4);
+}
+
+#[test]
+fn config_sets_a_default_model_per_task() {
+    let config = Config {
+        ollama_model: "llama3".to_string(),
+        llm: toml::from_str("answer_model = \"qwen2\"\nsummary_model = \"mistral:7b\"").unwrap(),
+        ..Config::default()
+    };
+    let client = llm::client_from_config(&config).unwrap();
+    assert_eq!(client.model_for(ModelTask::Answer), "qwen2");
+    assert_eq!(client.model_for(ModelTask::Summary), "mistral:7b");
+    assert_eq!(client.model_for(ModelTask::Embedding), config.embedding_model);
+
+    // Without task models every task uses the main model
+    let config = Config { ollama_model: "llama3".to_string(), llm: LlmConfig::default(), ..Config::default() };
+    let client = llm::client_from_config(&config).unwrap();
+    assert_eq!(client.model_for(ModelTask::Answer), "llama3");
+    assert_eq!(client.model_for(ModelTask::Summary), "llama3");
+
+    assert!(llm::same_model("llama3", "llama3:latest"));
+    assert!(!llm::same_model("llama3", "llama3:8b"));
+}
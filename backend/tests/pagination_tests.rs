@@ -0,0 +1,173 @@
+mod common;
+
+use common::call;
+use knowledge_base_backend::database::{Database, Document};
+use knowledge_base_backend::mcp::MCPServer;
+use knowledge_base_backend::ollama::OllamaClient;
+use knowledge_base_backend::pagination::Cursor;
+use chrono::{TimeZone, Utc};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+fn make_document(index: i64) -> Document {
+    let path = format!("/tmp/notes/note-{index}.md");
+    Document {
+        id: Uuid::new_v4(),
+        path: PathBuf::from(&path),
+        filename: format!("note-{index}.md"),
+        extension: "md".to_string(),
+        size: 10,
+        modified_at: Utc.timestamp_opt(1_700_000_000 + index, 0).unwrap(),
+        title: Some(format!("Note {index}")),
+        tags: vec![],
+        headings: vec![],
+        content_excerpt: "alpha release notes".to_string(),
+        content_hash: format!("hash-{index}"),
+        indexed_at: Utc.timestamp_opt(1_700_100_000, 0).unwrap(),
+        version: 1,
+        is_latest: true,
+        project_id: None,
+        language: None,
+    }
+}
+
+async fn setup(count: i64) -> (Database, MCPServer) {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    for index in 0..count {
+        db.insert_document(&make_document(index)).await.unwrap();
+    }
+    let ollama = OllamaClient::new("http://localhost:11434".to_string(), "gpt-oss:20b".to_string());
+    let mcp = MCPServer::new(db.clone(), ollama);
+    (db, mcp)
+}
+
+fn ids(data: &serde_json::Value, field: &str) -> Vec<String> {
+    data[field]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| {
+            let document = item.get("document").unwrap_or(item);
+            document["id"].as_str().unwrap().to_string()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn list_notes_reports_totals_and_pages_with_stable_cursors() {
+    let (db, mcp) = setup(25).await;
+
+    let first = call(&mcp, "list_notes", serde_json::json!({"limit": 10})).await.unwrap();
+    assert_eq!(first["total"], 25);
+    let mut seen: HashSet<String> = ids(&first, "notes").into_iter().collect();
+    assert_eq!(seen.len(), 10);
+
+    // A newer note appears before the cursor position and must not shift later pages
+    db.insert_document(&make_document(100)).await.unwrap();
+
+    let mut cursor = first["next_cursor"].as_str().unwrap().to_string();
+    loop {
+        let page = call(&mcp, "list_notes", serde_json::json!({"limit": 10, "cursor": cursor})).await.unwrap();
+        for id in ids(&page, "notes") {
+            assert!(seen.insert(id), "note repeated across pages");
+        }
+        match page["next_cursor"].as_str() {
+            Some(next) => cursor = next.to_string(),
+            None => break,
+        }
+    }
+    assert_eq!(seen.len(), 25);
+}
+
+#[tokio::test]
+async fn search_notes_reports_totals_and_pages_with_cursors() {
+    let (_db, mcp) = setup(25).await;
+
+    let first = call(&mcp, "search_notes", serde_json::json!({"query": "alpha", "limit": 10})).await.unwrap();
+    assert_eq!(first["total"], 25);
+    let mut seen: HashSet<String> = ids(&first, "results").into_iter().collect();
+
+    let mut cursor = first["next_cursor"].as_str().unwrap().to_string();
+    loop {
+        let page = call(&mcp, "search_notes", serde_json::json!({"query": "alpha", "limit": 10, "cursor": cursor}))
+            .await
+            .unwrap();
+        assert_eq!(page["total"], 25);
+        for id in ids(&page, "results") {
+            assert!(seen.insert(id), "result repeated across pages");
+        }
+        match page["next_cursor"].as_str() {
+            Some(next) => cursor = next.to_string(),
+            None => break,
+        }
+    }
+    assert_eq!(seen.len(), 25);
+
+    // Cursors are tied to their query
+    let other = call(&mcp, "search_notes", serde_json::json!({"query": "release", "cursor": cursor})).await;
+    assert!(other.is_err());
+}
+
+#[tokio::test]
+async fn search_cursors_are_tied_to_mode_filters_and_versions() {
+    let (_db, mcp) = setup(25).await;
+    let first = call(&mcp, "search_notes", serde_json::json!({"query": "alpha", "limit": 10})).await.unwrap();
+    let cursor = first["next_cursor"].as_str().unwrap();
+
+    for changed in [
+        serde_json::json!({"filters": {"file_types": ["md"]}}),
+        serde_json::json!({"include_historical": true}),
+        serde_json::json!({"as_of": "2024-01-01T00:00:00Z"}),
+    ] {
+        let mut arguments = serde_json::json!({"query": "alpha", "limit": 10, "cursor": cursor});
+        arguments.as_object_mut().unwrap().extend(changed.as_object().unwrap().clone());
+        assert!(call(&mcp, "search_notes", arguments).await.is_err());
+    }
+
+    let same = call(&mcp, "search_notes", serde_json::json!({"query": "alpha", "limit": 10, "cursor": cursor})).await;
+    assert_eq!(same.unwrap()["total"], 25);
+}
+
+#[tokio::test]
+async fn out_of_range_arguments_are_rejected() {
+    let (_db, mcp) = setup(1).await;
+
+    assert!(call(&mcp, "list_notes", serde_json::json!({"limit": 101})).await.is_err());
+    assert!(call(&mcp, "list_notes", serde_json::json!({"limit": 0})).await.is_err());
+    assert!(call(&mcp, "search_notes", serde_json::json!({"query": "alpha", "limit": 500})).await.is_err());
+    assert!(call(&mcp, "answer_question", serde_json::json!({"question": "alpha?", "top_k": 50})).await.is_err());
+    assert!(call(&mcp, "list_notes", serde_json::json!({"cursor": "not-a-cursor"})).await.is_err());
+
+    let first = call(&mcp, "list_notes", serde_json::json!({"limit": 100})).await.unwrap();
+    assert!(first["next_cursor"].is_null());
+
+    let tools = mcp.get_available_tools();
+    let list_notes = tools.iter().find(|t| t.name == "list_notes").unwrap();
+    assert_eq!(list_notes.input_schema["properties"]["limit"]["maximum"], 100);
+}
+
+#[tokio::test]
+async fn later_search_pages_are_scored_when_the_first_page_was() {
+    let (db, mcp) = setup(0).await;
+    for index in 0..2 {
+        let mut document = make_document(index);
+        document.modified_at = Utc::now() - chrono::Duration::days(31);
+        db.insert_document(&document).await.unwrap();
+    }
+
+    // A cursor handed out two days ago, when the notes were still recent
+    let first = call(&mcp, "search_notes", serde_json::json!({"query": "alpha", "limit": 1})).await.unwrap();
+    let Cursor::Search { query_hash, .. } = Cursor::decode(first["next_cursor"].as_str().unwrap()).unwrap() else {
+        panic!("not a search cursor");
+    };
+    let cursor = Cursor::for_search(query_hash, f32::MAX, Uuid::nil(), Utc::now() - chrono::Duration::days(2)).encode();
+    let page = call(&mcp, "search_notes", serde_json::json!({"query": "alpha", "cursor": cursor, "explain": true}))
+        .await
+        .unwrap();
+    let components = page["results"][0]["explanation"]["components"].as_array().unwrap();
+    let recency = components.iter().find(|c| c["signal"] == "recency").unwrap();
+    assert_eq!(recency["detail"], "modified 29 day(s) ago");
+    assert_eq!(recency["score"], 0.5);
+}
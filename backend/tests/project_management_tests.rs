@@ -1,4 +1,4 @@
-use knowledge_base_backend::database::Database;
+use knowledge_base_backend::database::{Database, Versions};
 use std::path::PathBuf;
 use uuid::Uuid;
 
@@ -211,12 +211,13 @@ async fn test_project_with_documents() -> anyhow::Result<()> {
         version: 1,
         is_latest: true,
         project_id: Some(project.id),
+        language: None,
     };
     
     db.insert_document(&document).await?;
     
     // Verify the document is associated with the project
-    let documents = db.search_documents_with_filters("test", 10, 0, true, Some(&[project.id])).await?;
+    let documents = db.search_documents_with_filters("test", 10, 0, Versions::All, Some(&[project.id])).await?;
     assert_eq!(documents.len(), 1);
     assert_eq!(documents[0].project_id, Some(project.id));
 
@@ -0,0 +1,111 @@
+mod common;
+
+use common::{call, index_notes_into};
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::mcp::MCPServer;
+use knowledge_base_backend::ollama::OllamaClient;
+use knowledge_base_backend::prompts::{self, PromptConfig, PromptKind, PromptVariables};
+use uuid::Uuid;
+
+async fn setup(config: PromptConfig) -> (MCPServer, Uuid, Uuid, tempfile::TempDir) {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let project = db.create_project("Ops", None).await.unwrap();
+
+    let temp_dir = index_notes_into(&db, &[("rollout.md", "# Rollout\n\nThe canary rollout starts on Monday.")], Some(project.id)).await;
+    let document_id = db.list_documents_page(1, 0, None).await.unwrap()[0].id;
+
+    let ollama = OllamaClient::new("http://localhost:11434".to_string(), "gpt-oss:20b".to_string());
+    let mcp = MCPServer::new(db, ollama).with_prompt_config(config);
+    (mcp, project.id, document_id, temp_dir)
+}
+
+#[test]
+fn templates_render_known_variables_and_validate_placeholders() {
+    let variables = PromptVariables {
+        question: "When?".to_string(),
+        context: "[a.md] Monday".to_string(),
+        language: "English".to_string(),
+        length: String::new(),
+    };
+    let rendered = prompts::render("Q: {question}\nC: {context}\nIn {language}. JSON: {\"a\": 1} {unknown", &variables);
+    assert_eq!(rendered, "Q: When?\nC: [a.md] Monday\nIn English. JSON: {\"a\": 1} {unknown");
+
+    assert!(prompts::validate(PromptKind::Answer, "{question} {context}").is_ok());
+    assert!(prompts::validate(PromptKind::Answer, "{question} {context} {tone}").is_err());
+    assert!(prompts::validate(PromptKind::Answer, "{question} only").is_err());
+    assert!(prompts::validate(PromptKind::Summary, "Summarize {context} as {length}").is_ok());
+    for kind in PromptKind::ALL {
+        assert!(prompts::validate(kind, kind.default_template()).is_ok());
+    }
+
+    assert_eq!(prompts::answer_language("auto", "What is the rollout plan for the team?"), "English");
+    assert_eq!(prompts::answer_language("auto", "Mikor kezdődik a bevezetés és hogy van a csapat?"), "Hungarian");
+    assert_eq!(prompts::answer_language("German", "What is the plan?"), "German");
+}
+
+#[tokio::test]
+async fn overrides_resolve_project_then_global_then_config_then_default() {
+    let (mcp, project, _document, _dir) = setup(PromptConfig::default()).await;
+    let preview = |arguments: serde_json::Value| call(&mcp, "preview_prompt", arguments);
+
+    let data = preview(serde_json::json!({"kind": "answer", "question": "When does the rollout start?"})).await.unwrap();
+    assert_eq!(data["template"]["source"], "default");
+    assert_eq!(data["language"], "English");
+    let prompt = data["prompt"].as_str().unwrap();
+    assert!(prompt.contains("in English"));
+    assert!(prompt.contains("Question: When does the rollout start?"));
+    assert!(prompt.contains("[1] (rollout.md) "));
+
+    // A language in the request wins over auto-detection
+    let data = preview(serde_json::json!({"kind": "answer", "question": "When does the rollout start?", "language": "Hungarian"}))
+        .await
+        .unwrap();
+    assert!(data["prompt"].as_str().unwrap().contains("in Hungarian"));
+
+    call(&mcp, "set_prompt_template", serde_json::json!({"kind": "answer", "template": "GLOBAL {question} {context}"})).await.unwrap();
+    call(
+        &mcp,
+        "set_prompt_template",
+        serde_json::json!({"kind": "answer", "template": "OPS {language}: {question} {context}", "project_id": project}),
+    )
+    .await
+    .unwrap();
+    assert!(call(&mcp, "set_prompt_template", serde_json::json!({"kind": "answer", "template": "{question}"})).await.is_err());
+
+    let unscoped = preview(serde_json::json!({"kind": "answer", "question": "rollout?"})).await.unwrap();
+    assert_eq!(unscoped["template"]["source"], "global");
+    assert!(unscoped["prompt"].as_str().unwrap().starts_with("GLOBAL rollout?"));
+    let scoped = preview(serde_json::json!({"kind": "answer", "question": "rollout?", "project_ids": [project]})).await.unwrap();
+    assert_eq!(scoped["template"]["source"], "project");
+    assert!(scoped["prompt"].as_str().unwrap().starts_with("OPS "));
+
+    let listed = call(&mcp, "list_prompt_templates", serde_json::json!({"project_id": project})).await.unwrap();
+    assert_eq!(listed["effective"][0]["source"], "project");
+    assert_eq!(listed["overrides"].as_array().unwrap().len(), 2);
+
+    let reset = call(&mcp, "reset_prompt_template", serde_json::json!({"kind": "answer"})).await.unwrap();
+    assert_eq!(reset["removed"], true);
+    assert_eq!(reset["effective"]["source"], "default");
+}
+
+#[tokio::test]
+async fn summary_prompts_use_the_configured_template_and_length() {
+    let config = PromptConfig {
+        summary: Some("Summarize as a {length} in {language}:\n{context}".to_string()),
+        language: "German".to_string(),
+        ..Default::default()
+    };
+    let (mcp, _project, document, _dir) = setup(config).await;
+
+    let data = call(&mcp, "preview_prompt", serde_json::json!({"kind": "summary", "id": document, "length": "short"}))
+        .await
+        .unwrap();
+    assert_eq!(data["template"]["source"], "config");
+    let prompt = data["prompt"].as_str().unwrap();
+    assert!(prompt.starts_with("Summarize as a short summary (3-5 bullet points) in German:"));
+    assert!(prompt.contains("The canary rollout starts on Monday."));
+
+    assert!(call(&mcp, "preview_prompt", serde_json::json!({"kind": "summary", "id": Uuid::new_v4()})).await.is_err());
+    assert!(call(&mcp, "preview_prompt", serde_json::json!({"kind": "haiku"})).await.is_err());
+}
@@ -0,0 +1,57 @@
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::evaluation::{evaluate, Baseline, GoldenSet, Metrics};
+use knowledge_base_backend::search::SearchEngine;
+use std::path::PathBuf;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/relevance").join(name)
+}
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+#[test]
+fn metrics_follow_their_definitions() {
+    let ranked = names(&["a.md", "b.md", "c.md", "d.md"]);
+    let expected = names(&["b.md", "d.md"]);
+
+    let metrics = Metrics::for_ranking(&ranked, &expected, 2);
+    assert_eq!(metrics.precision_at_k, 0.5);
+    assert_eq!(metrics.recall_at_k, 0.5);
+    assert_eq!(metrics.mrr, 0.5);
+    // One hit at rank 2 against an ideal of hits at ranks 1 and 2
+    let ideal = 1.0 + 1.0 / 3f64.log2();
+    assert!((metrics.ndcg_at_k - (1.0 / 3f64.log2()) / ideal).abs() < 1e-9);
+
+    let none = Metrics::for_ranking(&[], &expected, 2);
+    assert_eq!(none, Metrics::default());
+}
+
+#[tokio::test]
+async fn golden_queries_do_not_regress_against_the_baseline() {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    CorpusManager::new(db.clone(), vec![]).index_folder(&fixture("corpus"), None).await.unwrap();
+
+    let golden = GoldenSet::load(&fixture("golden_queries.json")).unwrap();
+    let report = evaluate(&SearchEngine::new(db), &golden).await.unwrap();
+    assert_eq!(report.queries.len(), golden.queries.len());
+
+    let baseline = Baseline::load(&fixture("baseline.json")).unwrap();
+    let regressions: Vec<_> = baseline.compare(&report).into_iter().filter(|c| c.is_regression()).collect();
+    assert!(
+        regressions.is_empty(),
+        "relevance regressed (update baseline.json with relevance_eval --write-baseline if intended): {:?}",
+        regressions
+    );
+
+    // A worse run is reported metric by metric
+    let mut worse = report.clone();
+    worse.search.mrr -= 0.5;
+    let changes = baseline.compare(&worse);
+    assert_eq!(changes.len(), 1);
+    assert_eq!((changes[0].retriever.as_str(), changes[0].metric.as_str()), ("search", "mrr"));
+    assert!(changes[0].is_regression());
+}
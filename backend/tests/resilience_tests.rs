@@ -0,0 +1,239 @@
+mod common;
+
+use common::{index_notes, MockLlm};
+use knowledge_base_backend::llm::{GenerationOptions, LlmClient, LlmConfig};
+use knowledge_base_backend::mcp::{MCPRequest, MCPServer, StreamEvent};
+use knowledge_base_backend::ollama::OllamaClient;
+use knowledge_base_backend::resilience::{self, CircuitBreaker, ModelUnavailable, ResilienceConfig};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use warp::http::StatusCode;
+use warp::Filter;
+
+/// Nothing listens on the discard port.
+const UNREACHABLE: &str = "http://127.0.0.1:9";
+
+fn fast_retries(max_retries: u32) -> ResilienceConfig {
+    ResilienceConfig { max_retries, backoff_ms: 1, max_backoff_ms: 5, ..ResilienceConfig::default() }
+}
+
+/// A mock Ollama whose `/api/generate` answers the first `failures` calls
+/// with `status` and later ones with "Recovered." after `delay`.
+async fn flaky_ollama(failures: usize, status: StatusCode, delay: Duration, calls: Arc<AtomicUsize>) -> String {
+    let route = warp::path!("api" / "generate").and(warp::post()).then(move || {
+        let calls = calls.clone();
+        async move {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(delay).await;
+            let (status, body) = if call < failures {
+                (status, serde_json::json!({"error": "model is loading"}))
+            } else {
+                (StatusCode::OK, serde_json::json!({"model": "m", "created_at": "t", "response": "Recovered.", "done": true}))
+            };
+            warp::reply::with_status(warp::reply::json(&body), status)
+        }
+    });
+    let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    format!("http://{}", address)
+}
+
+#[tokio::test]
+async fn transient_failures_are_retried_but_client_errors_are_not() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let base_url = flaky_ollama(2, StatusCode::SERVICE_UNAVAILABLE, Duration::ZERO, calls.clone()).await;
+    let client = LlmClient::from(OllamaClient::new(base_url, "m".to_string())).with_resilience(fast_retries(2));
+    assert_eq!(client.generate("hi", &GenerationOptions::default()).await.unwrap(), "Recovered.");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    assert_eq!(client.circuit_status().state, "closed");
+
+    // Out of retries the server counts as unavailable
+    let calls = Arc::new(AtomicUsize::new(0));
+    let base_url = flaky_ollama(5, StatusCode::BAD_GATEWAY, Duration::ZERO, calls.clone()).await;
+    let client = LlmClient::from(OllamaClient::new(base_url, "m".to_string())).with_resilience(fast_retries(1));
+    let error = client.generate("hi", &GenerationOptions::default()).await.unwrap_err();
+    assert!(resilience::is_model_unavailable(&error));
+    assert!(error.to_string().contains("model is loading (after 2 attempts)"));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    // A missing model is reported at once, as the server's own error
+    let calls = Arc::new(AtomicUsize::new(0));
+    let base_url = flaky_ollama(5, StatusCode::NOT_FOUND, Duration::ZERO, calls.clone()).await;
+    let client = LlmClient::from(OllamaClient::new(base_url, "m".to_string())).with_resilience(fast_retries(3));
+    let error = client.generate("hi", &GenerationOptions::default()).await.unwrap_err();
+    assert!(!resilience::is_model_unavailable(&error));
+    assert_eq!(error.to_string(), "Ollama error: model is loading");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn slow_responses_time_out() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let base_url = flaky_ollama(0, StatusCode::OK, Duration::from_secs(5), calls.clone()).await;
+    let ollama = OllamaClient::new(base_url, "m".to_string())
+        .with_timeouts(Duration::from_secs(1), Duration::from_millis(200), Duration::from_millis(300));
+    let client = LlmClient::from(ollama).with_resilience(fast_retries(2));
+
+    // Timed out generations count as failures but are not retried
+    let started = Instant::now();
+    let error = client.generate("hi", &GenerationOptions::default()).await.unwrap_err();
+    assert!(resilience::is_model_unavailable(&error));
+    assert!(error.to_string().contains("(after 1 attempt)"));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(client.circuit_status().consecutive_failures, 1);
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    // Streamed generations have their own limit
+    let started = Instant::now();
+    assert!(client.generate_stream("hi", &GenerationOptions::default(), |_| {}).await.is_err());
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn the_circuit_opens_after_repeated_failures() {
+    let config = ResilienceConfig { failure_threshold: 2, cooldown_secs: 60, ..fast_retries(1) };
+    let client = LlmClient::from(OllamaClient::new(UNREACHABLE.to_string(), "m".to_string())).with_resilience(config);
+    let options = GenerationOptions::default();
+
+    for _ in 0..2 {
+        let error = client.generate("hi", &options).await.unwrap_err();
+        assert!(error.downcast_ref::<ModelUnavailable>().unwrap().reason.contains("after 2 attempts"));
+    }
+    // Clones share the breaker; calls now fail without trying the server
+    let status = client.clone().circuit_status();
+    assert_eq!((status.state.as_str(), status.consecutive_failures), ("open", 2));
+    let error = client.clone().embed("text").await.unwrap_err();
+    assert!(error.to_string().contains("2 consecutive calls failed; trying again in 60 s"));
+
+    // Health checks still ask the server and report the circuit
+    let check = client.check_models().await;
+    assert!(!check.reachable);
+    assert_eq!(check.circuit.state, "open");
+
+}
+
+#[test]
+fn a_half_open_circuit_lets_one_trial_call_through() {
+    let breaker = CircuitBreaker::new(1, Duration::from_millis(50));
+    breaker.record_failure();
+    assert!(breaker.check().is_err());
+    std::thread::sleep(Duration::from_millis(60));
+    assert_eq!(breaker.status().state, "half_open");
+
+    // Others fail fast while the trial is in flight
+    assert!(breaker.check().is_ok());
+    assert_eq!(breaker.check(), Err(Duration::ZERO));
+    breaker.record_failure();
+    assert_eq!(breaker.status().state, "open");
+
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(breaker.check().is_ok());
+    breaker.record_success();
+    assert_eq!(breaker.status().state, "closed");
+    assert!(breaker.check().is_ok() && breaker.check().is_ok());
+
+    // A trial that never reports back is replaced after the cooldown
+    breaker.record_failure();
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(breaker.check().is_ok());
+    assert!(breaker.check().is_err());
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(breaker.check().is_ok());
+}
+
+async fn indexed_server(llm: LlmClient) -> (MCPServer, tempfile::TempDir) {
+    let (db, temp_dir) = index_notes(&[("backups.md", "# Backups\n\nBackups run every night at two.")], None).await;
+    (MCPServer::new(db, llm), temp_dir)
+}
+
+#[tokio::test]
+async fn answers_fall_back_to_citations_when_the_model_is_down() {
+    let client = LlmClient::from(OllamaClient::new(UNREACHABLE.to_string(), "m".to_string())).with_resilience(fast_retries(0));
+    let (mcp, _dir) = indexed_server(client).await;
+    let arguments = serde_json::json!({"question": "When do backups run?"});
+
+    let request = MCPRequest { tool: "answer_question".to_string(), arguments: arguments.clone() };
+    let data = mcp.handle_request(request).await.unwrap().data.unwrap();
+    assert_eq!(data["status"], "model_unavailable");
+    assert!(data["answer"].as_str().unwrap().contains("unavailable"));
+    assert!(data["model_error"].as_str().unwrap().contains("The ollama model server is unavailable"));
+    assert_eq!(data["citations"][0]["filename"], "backups.md");
+    assert!(data["citation_check"].is_null());
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let request = MCPRequest { tool: "answer_question".to_string(), arguments };
+    mcp.handle_stream_request(request, sender).await.unwrap();
+    let mut events = Vec::new();
+    while let Some(event) = receiver.recv().await {
+        events.push(event);
+    }
+    let names: Vec<&str> = events.iter().map(|e| e.name()).collect();
+    assert_eq!(names, vec!["citations", "token", "done"]);
+    match events.last().unwrap() {
+        StreamEvent::Done { metadata } => {
+            assert_eq!(metadata["status"], "model_unavailable");
+            assert_eq!(metadata["citations"].as_array().unwrap().len(), 1);
+        }
+        other => panic!("unexpected event: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn errors_in_the_middle_of_a_stream_count_as_failures() {
+    let llm = MockLlm::new().reply("Backups run").stream_error("model crashed").start().await;
+    let client = LlmClient::from(llm.client()).with_resilience(fast_retries(2));
+    let error = client.generate_stream("hi", &GenerationOptions::default(), |_| {}).await.unwrap_err();
+    assert!(resilience::is_model_unavailable(&error));
+    assert!(error.to_string().contains("model crashed"));
+    assert_eq!(client.circuit_status().consecutive_failures, 1);
+
+    // Answers degrade like they do when the buffered call fails
+    let (mcp, _dir) = indexed_server(client).await;
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let request = MCPRequest { tool: "answer_question".to_string(), arguments: serde_json::json!({"question": "When do backups run?"}) };
+    mcp.handle_stream_request(request, sender).await.unwrap();
+    let mut last = None;
+    while let Some(event) = receiver.recv().await {
+        last = Some(event);
+    }
+    match last.unwrap() {
+        StreamEvent::Done { metadata } => {
+            assert_eq!(metadata["status"], "model_unavailable");
+            assert_eq!(metadata["citations"][0]["filename"], "backups.md");
+        }
+        other => panic!("unexpected event: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn answered_questions_report_their_status() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let base_url = flaky_ollama(1, StatusCode::SERVICE_UNAVAILABLE, Duration::ZERO, calls).await;
+    let client = LlmClient::from(OllamaClient::new(base_url, "m".to_string())).with_resilience(fast_retries(2));
+    let (mcp, _dir) = indexed_server(client).await;
+
+    let request = MCPRequest { tool: "answer_question".to_string(), arguments: serde_json::json!({"question": "When do backups run?"}) };
+    let data = mcp.handle_request(request).await.unwrap().data.unwrap();
+    assert_eq!(data["status"], "answered");
+    assert_eq!(data["answer"], "Recovered.");
+    assert!(data.get("model_error").is_none());
+}
+
+#[test]
+fn resilience_settings_are_read_from_the_llm_section() {
+    let llm: LlmConfig =
+        toml::from_str("[resilience]\ntimeout_secs = 30\nstream_timeout_secs = 900\nmax_retries = 0\nfailure_threshold = 3").unwrap();
+    assert_eq!(llm.resilience.timeout(), Duration::from_secs(30));
+    assert_eq!(llm.resilience.stream_timeout(), Duration::from_secs(900));
+    assert_eq!(llm.resilience.max_retries, 0);
+    assert_eq!(llm.resilience.failure_threshold, 3);
+    // Unset values keep their defaults
+    assert_eq!(llm.resilience.connect_timeout(), ResilienceConfig::default().connect_timeout());
+
+    let config = ResilienceConfig { backoff_ms: 100, max_backoff_ms: 300, ..ResilienceConfig::default() };
+    for (retry, ceiling) in [(0, 100), (1, 200), (2, 300), (10, 300)] {
+        let delay = config.backoff(retry).as_millis() as u64;
+        assert!(delay >= ceiling / 2 && delay <= ceiling, "retry {}: {} ms", retry, delay);
+    }
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use common::{call, index_notes};
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::mcp::MCPServer;
+use knowledge_base_backend::ollama::OllamaClient;
+use std::fs;
+use std::time::Duration;
+
+async fn setup() -> (Database, MCPServer, CorpusManager, tempfile::TempDir) {
+    let notes = [
+        ("incident-1.md", "---\ntags: [incident]\n---\n# Outage\n\nDatabase outage postmortem."),
+        ("incident-2.md", "---\ntags: [incident]\n---\n# Latency\n\nAPI latency postmortem."),
+        ("draft.md", "# Draft\n\nPostmortem template without tags."),
+    ];
+    let (db, temp_dir) = index_notes(&notes, None).await;
+    let corpus = CorpusManager::new(db.clone(), vec![]);
+
+    let ollama = OllamaClient::new("http://localhost:11434".to_string(), "gpt-oss:20b".to_string());
+    let mcp = MCPServer::new(db.clone(), ollama);
+    (db, mcp, corpus, temp_dir)
+}
+
+fn filenames(data: &serde_json::Value) -> Vec<String> {
+    let mut names: Vec<String> = data["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["document"]["filename"].as_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn saved_searches_keep_their_filters_and_act_as_folders() {
+    let (_db, mcp, _corpus, _dir) = setup().await;
+
+    let saved = call(
+        &mcp,
+        "save_search",
+        serde_json::json!({"name": "incidents", "query": "postmortem", "filters": {"tags": ["incident"]}}),
+    )
+    .await
+    .unwrap();
+    let id = saved["saved_search"]["id"].as_str().unwrap().to_string();
+
+    // Names are unique
+    assert!(call(&mcp, "save_search", serde_json::json!({"name": "incidents", "query": "outage"})).await.is_err());
+
+    let listed = call(&mcp, "list_saved_searches", serde_json::json!({})).await.unwrap();
+    let folders = listed["saved_searches"].as_array().unwrap();
+    assert_eq!(folders.len(), 1);
+    assert_eq!(folders[0]["name"], "incidents");
+    assert!(folders[0].get("total").is_none());
+    assert!(folders[0]["last_run_at"].is_null());
+
+    // Counting the matches runs the search
+    let listed = call(&mcp, "list_saved_searches", serde_json::json!({"counts": true})).await.unwrap();
+    assert_eq!(listed["saved_searches"][0]["total"], 2);
+    assert!(listed["saved_searches"][0]["last_run_at"].is_null());
+
+    // By name or by id
+    let by_name = call(&mcp, "run_saved_search", serde_json::json!({"id": "incidents"})).await.unwrap();
+    assert_eq!(filenames(&by_name), vec!["incident-1.md", "incident-2.md"]);
+    assert!(by_name["saved_search"]["last_run_at"].is_string());
+    let by_id = call(&mcp, "run_saved_search", serde_json::json!({"id": id})).await.unwrap();
+    assert_eq!(by_id["total"], 2);
+
+    call(&mcp, "delete_saved_search", serde_json::json!({"id": "incidents"})).await.unwrap();
+    assert!(call(&mcp, "run_saved_search", serde_json::json!({"id": "incidents"})).await.is_err());
+}
+
+#[tokio::test]
+async fn new_only_runs_return_documents_indexed_since_the_previous_run() {
+    let (_db, mcp, corpus, dir) = setup().await;
+    call(&mcp, "save_search", serde_json::json!({"name": "postmortems", "query": "postmortem"})).await.unwrap();
+
+    // The first run has nothing to compare against
+    let first = call(&mcp, "run_saved_search", serde_json::json!({"id": "postmortems", "new_only": true})).await.unwrap();
+    assert_eq!(first["total"], 3);
+    assert!(first["new_since"].is_null());
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    fs::write(dir.path().join("incident-3.md"), "# Disk\n\nDisk full postmortem.").unwrap();
+    corpus.index_folder(dir.path(), None).await.unwrap();
+
+    let second = call(&mcp, "run_saved_search", serde_json::json!({"id": "postmortems", "new_only": true})).await.unwrap();
+    assert_eq!(filenames(&second), vec!["incident-3.md"]);
+    let timestamp = |value: &serde_json::Value| chrono::DateTime::parse_from_rfc3339(value.as_str().unwrap()).unwrap();
+    assert_eq!(timestamp(&second["new_since"]), timestamp(&first["saved_search"]["last_run_at"]));
+
+    let third = call(&mcp, "run_saved_search", serde_json::json!({"id": "postmortems", "new_only": true})).await.unwrap();
+    assert_eq!(third["total"], 0);
+
+    // A plain run still sees everything
+    let all = call(&mcp, "run_saved_search", serde_json::json!({"id": "postmortems"})).await.unwrap();
+    assert_eq!(all["total"], 4);
+}
+
+#[tokio::test]
+async fn later_pages_of_a_new_only_run_keep_the_first_page_cutoff() {
+    let (_db, mcp, corpus, dir) = setup().await;
+    call(&mcp, "save_search", serde_json::json!({"name": "postmortems", "query": "postmortem"})).await.unwrap();
+    call(&mcp, "run_saved_search", serde_json::json!({"id": "postmortems"})).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    for (name, body) in [("incident-3.md", "Disk full postmortem."), ("incident-4.md", "Certificate expiry postmortem.")] {
+        fs::write(dir.path().join(name), format!("# Incident\n\n{}", body)).unwrap();
+    }
+    corpus.index_folder(dir.path(), None).await.unwrap();
+
+    // Running the first page moves last_run_at past both new documents
+    let first = call(&mcp, "run_saved_search", serde_json::json!({"id": "postmortems", "new_only": true, "limit": 1})).await.unwrap();
+    assert_eq!(first["total"], 2);
+    let cursor = first["next_cursor"].as_str().unwrap();
+
+    let second = call(
+        &mcp,
+        "run_saved_search",
+        serde_json::json!({"id": "postmortems", "new_only": true, "limit": 1, "cursor": cursor}),
+    )
+    .await
+    .unwrap();
+    assert_eq!(second["total"], 2);
+    assert_eq!(second["new_since"], first["new_since"]);
+    assert!(second["next_cursor"].is_null());
+    let mut seen = filenames(&first);
+    seen.extend(filenames(&second));
+    seen.sort();
+    assert_eq!(seen, vec!["incident-3.md", "incident-4.md"]);
+
+    // Offsets cannot carry the cutoff, so it must be passed back
+    let by_offset = serde_json::json!({"id": "postmortems", "new_only": true, "limit": 1, "offset": 1});
+    assert!(call(&mcp, "run_saved_search", by_offset).await.is_err());
+    let by_offset = serde_json::json!({
+        "id": "postmortems", "new_only": true, "limit": 1, "offset": 1, "since": first["new_since"]
+    });
+    let second = call(&mcp, "run_saved_search", by_offset).await.unwrap();
+    assert_eq!(second["total"], 2);
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::index_notes;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::mcp::{MCPRequest, MCPServer};
+use knowledge_base_backend::ollama::OllamaClient;
+use knowledge_base_backend::search::{SearchEngine, SearchFilters, SearchOptions};
+
+async fn setup() -> (Database, tempfile::TempDir) {
+    let notes = [
+        (
+            "rollout.md",
+            "---\ntags: [ops]\n---\n# Rollout plan\n\nThe canary rollout starts on Monday. Rollout metrics are watched daily.",
+        ),
+        ("notes.txt", "Misc notes mentioning a rollout once."),
+    ];
+    index_notes(&notes, None).await
+}
+
+#[tokio::test]
+async fn explanations_add_up_to_the_score() {
+    let (db, _dir) = setup().await;
+    let engine = SearchEngine::new(db);
+
+    let options = SearchOptions { explain: true, ..Default::default() };
+    let response = engine.search_page("rollout \"canary rollout\"", &options).await.unwrap();
+    assert_eq!(response.results.len(), 2);
+
+    for result in &response.results {
+        let explanation = result.explanation.as_ref().unwrap();
+        let sum: f32 = explanation.components.iter().map(|c| c.score).sum();
+        assert!((sum - result.score).abs() < 1e-4);
+        assert_eq!(explanation.score, result.score);
+        assert!(!explanation.matched_chunks.is_empty());
+    }
+
+    let top = response.results[0].explanation.as_ref().unwrap();
+    let signals: Vec<&str> = top.components.iter().map(|c| c.signal.as_str()).collect();
+    for signal in ["filename", "title", "content", "phrase", "recency"] {
+        assert!(signals.contains(&signal), "missing {signal}");
+    }
+    assert!(top.filters.iter().any(|f| f.filter == "version"));
+
+    // Explanations are opt-in
+    let response = engine.search_page("rollout", &SearchOptions::default()).await.unwrap();
+    assert!(response.results.iter().all(|r| r.explanation.is_none()));
+}
+
+#[tokio::test]
+async fn explanations_report_filter_decisions() {
+    let (db, _dir) = setup().await;
+    let engine = SearchEngine::new(db);
+
+    let filters = SearchFilters {
+        file_types: Some(vec!["md".to_string()]),
+        folders: None,
+        date_from: None,
+        date_to: None,
+        tags: Some(vec!["ops".to_string()]),
+        project_ids: None,
+        indexed_after: None,
+    };
+    let options = SearchOptions { explain: true, filters: Some(filters), ..Default::default() };
+    let response = engine.search_page("rollout", &options).await.unwrap();
+    assert_eq!(response.results.len(), 1);
+
+    let explanation = response.results[0].explanation.as_ref().unwrap();
+    let filters: Vec<&str> = explanation.filters.iter().map(|f| f.filter.as_str()).collect();
+    assert_eq!(filters, vec!["version", "file_types", "tags"]);
+    assert!(explanation.filters[2].detail.contains("ops"));
+}
+
+#[tokio::test]
+async fn search_notes_serializes_explanations_only_when_asked() {
+    let (db, _dir) = setup().await;
+    let ollama = OllamaClient::new("http://localhost:11434".to_string(), "gpt-oss:20b".to_string());
+    let mcp = MCPServer::new(db, ollama);
+
+    let request = MCPRequest {
+        tool: "search_notes".to_string(),
+        arguments: serde_json::json!({"query": "rollout", "explain": true}),
+    };
+    let data = mcp.handle_request(request).await.unwrap().data.unwrap();
+    assert!(data["results"][0]["explanation"]["components"].is_array());
+
+    let request = MCPRequest {
+        tool: "search_notes".to_string(),
+        arguments: serde_json::json!({"query": "rollout"}),
+    };
+    let data = mcp.handle_request(request).await.unwrap().data.unwrap();
+    assert!(data["results"][0].get("explanation").is_none());
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::{write_notes, CONCEPTS, MockLlm, MockLlmServer};
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::{Database, Versions};
+use knowledge_base_backend::search::SearchEngine;
+use std::fs;
+
+/// The mock with synonym-aligned embeddings.
+async fn start_mock() -> MockLlmServer {
+    MockLlm::new().concepts(CONCEPTS).start().await
+}
+
+async fn setup() -> (Database, tempfile::TempDir) {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    // Indexed by each test, with embeddings
+    let temp_dir = write_notes(&[
+        ("garage.md", "# Garage\n\nThe vehicle needs an engine service before winter."),
+        ("kitchen.md", "# Kitchen\n\nA quick recipe for a weekday dinner."),
+    ]);
+    (db, temp_dir)
+}
+
+#[tokio::test]
+async fn semantic_search_finds_paraphrased_matches() {
+    let llm = start_mock().await;
+    let (db, temp_dir) = setup().await;
+    let client = llm.client();
+
+    let corpus = CorpusManager::new(db.clone(), vec![]).with_embeddings(client.clone());
+    corpus.index_folder(temp_dir.path(), None).await.unwrap();
+
+    let engine = SearchEngine::new(db.clone()).with_embeddings(client);
+
+    // No keyword overlap with the garage note
+    let keyword_results = engine.search("automobile", None, 10, 0, false).await.unwrap();
+    assert!(keyword_results.is_empty());
+
+    let results = engine.semantic_search("automobile", None, 10, 0, false).await.unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].document.filename, "garage.md");
+    assert!(results[0].score > results[1].score);
+    assert!(results[0].snippets[0].text.contains("vehicle"));
+
+    let chunks = engine.semantic_chunks("cooking", 1, None, None).await.unwrap();
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].0.filename, "kitchen.md");
+}
+
+#[tokio::test]
+async fn embeddings_are_only_recomputed_for_changed_chunks() {
+    let llm = start_mock().await;
+    let (db, temp_dir) = setup().await;
+    let client = llm.client();
+    let corpus = CorpusManager::new(db.clone(), vec![]).with_embeddings(client.clone());
+
+    corpus.index_folder(temp_dir.path(), None).await.unwrap();
+    let initial_calls = llm.embedded().len();
+    assert!(initial_calls >= 2);
+
+    // Nothing changed: no new embedding requests
+    corpus.index_folder(temp_dir.path(), None).await.unwrap();
+    assert_eq!(llm.embedded().len(), initial_calls);
+
+    // One file changed: only its new chunk is embedded
+    fs::write(
+        temp_dir.path().join("kitchen.md"),
+        "# Kitchen\n\nA slow recipe for a Sunday meal.",
+    )
+    .unwrap();
+    corpus.index_folder(temp_dir.path(), None).await.unwrap();
+    assert_eq!(llm.embedded().len(), initial_calls + 1);
+
+    let embeddings = db.get_chunk_embeddings(client.embedding_model(), Versions::Latest, None).await.unwrap();
+    assert_eq!(embeddings.len(), 2);
+}
+
+#[tokio::test]
+async fn changing_the_embedding_model_recomputes_vectors() {
+    let llm = start_mock().await;
+    let (db, temp_dir) = setup().await;
+
+    let client = llm.client();
+    CorpusManager::new(db.clone(), vec![])
+        .with_embeddings(client)
+        .index_folder(temp_dir.path(), None)
+        .await
+        .unwrap();
+    let initial_calls = llm.embedded().len();
+
+    let other_client = llm.client().with_embedding_model("other-embed".to_string());
+    CorpusManager::new(db.clone(), vec![])
+        .with_embeddings(other_client)
+        .index_folder(temp_dir.path(), None)
+        .await
+        .unwrap();
+
+    assert_eq!(llm.embedded().len(), initial_calls * 2);
+    let embeddings = db.get_chunk_embeddings("other-embed", Versions::Latest, None).await.unwrap();
+    assert_eq!(embeddings.len(), initial_calls);
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use common::index_notes;
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::mcp::{MCPRequest, MCPServer};
+use knowledge_base_backend::ollama::OllamaClient;
+use knowledge_base_backend::search::SearchEngine;
+use std::fs;
+use std::path::Path;
+
+async fn setup() -> (Database, tempfile::TempDir) {
+    let notes = [
+        (
+            "async-rust.md",
+            "---\ntags: [rust, async]\n---\n# Async Rust\n\nThe tokio runtime drives futures on an executor. \
+             Spawning tasks on the tokio executor is cheap. See [[rust-basics]] first.",
+        ),
+        ("tokio.md", "---\ntags: [rust]\n---\n# Tokio\n\nNotes on the tokio runtime, its executor and spawning tasks."),
+        ("rust-basics.md", "# Rust basics\n\nOwnership and borrowing rules."),
+        ("groceries.md", "# Groceries\n\nMilk, bread and apples."),
+    ];
+    index_notes(&notes, None).await
+}
+
+async fn document_id(db: &Database, dir: &Path, filename: &str) -> uuid::Uuid {
+    db.get_latest_document_version(&dir.join(filename)).await.unwrap().unwrap().id
+}
+
+#[tokio::test]
+async fn similar_documents_are_scored_with_reasons() {
+    let (db, dir) = setup().await;
+    let source = document_id(&db, dir.path(), "async-rust.md").await;
+    let engine = SearchEngine::new(db.clone());
+
+    let similar = engine.find_similar(&source, None, 10).await.unwrap();
+    let names: Vec<&str> = similar.iter().map(|s| s.document.filename.as_str()).collect();
+
+    assert_eq!(names[0], "tokio.md");
+    assert!(similar[0].shared_tags.contains(&"rust".to_string()));
+    assert!(similar[0].shared_terms.contains(&"tokio".to_string()));
+    assert!(similar[0].reasons.iter().any(|r| r.starts_with("shared tags: rust")));
+    assert!(similar[0].reasons.iter().any(|r| r.starts_with("shared terms:")));
+
+    let basics = similar.iter().find(|s| s.document.filename == "rust-basics.md").unwrap();
+    assert!(basics.reasons.contains(&"linked from this note".to_string()));
+
+    assert!(!names.contains(&"groceries.md"));
+    assert!(!names.contains(&"async-rust.md"));
+    assert!(similar.windows(2).all(|w| w[0].score >= w[1].score));
+}
+
+#[tokio::test]
+async fn find_similar_tool_respects_limit_and_project_scope() {
+    let (db, dir) = setup().await;
+    let source = document_id(&db, dir.path(), "async-rust.md").await;
+    let ollama = OllamaClient::new("http://localhost:11434".to_string(), "gpt-oss:20b".to_string());
+    let mcp = MCPServer::new(db.clone(), ollama);
+
+    let request = MCPRequest {
+        tool: "find_similar".to_string(),
+        arguments: serde_json::json!({"id": source, "limit": 1}),
+    };
+    let response = mcp.handle_request(request).await.unwrap();
+    assert!(response.success);
+    let similar = response.data.unwrap()["similar"].as_array().unwrap().clone();
+    assert_eq!(similar.len(), 1);
+    assert_eq!(similar[0]["document"]["filename"], "tokio.md");
+
+    // No indexed document belongs to this project
+    let project = db.create_project("Empty", None).await.unwrap();
+    let request = MCPRequest {
+        tool: "find_similar".to_string(),
+        arguments: serde_json::json!({"id": source, "project_ids": [project.id]}),
+    };
+    let response = mcp.handle_request(request).await.unwrap();
+    assert!(response.data.unwrap()["similar"].as_array().unwrap().is_empty());
+
+    let request = MCPRequest {
+        tool: "find_similar".to_string(),
+        arguments: serde_json::json!({"id": uuid::Uuid::new_v4()}),
+    };
+    assert!(mcp.handle_request(request).await.is_err());
+}
+
+#[tokio::test]
+async fn notes_linking_to_the_source_are_candidates() {
+    let (db, dir) = setup().await;
+    fs::write(dir.path().join("shopping.md"), "# Shopping\n\nWeekly list: [groceries](groceries.md).").unwrap();
+    CorpusManager::new(db.clone(), vec![]).index_folder(dir.path(), None).await.unwrap();
+    let source = document_id(&db, dir.path(), "groceries.md").await;
+
+    let similar = SearchEngine::new(db.clone()).find_similar(&source, None, 10).await.unwrap();
+    let names: Vec<&str> = similar.iter().map(|s| s.document.filename.as_str()).collect();
+
+    assert_eq!(names, vec!["shopping.md"]);
+    assert!(similar[0].reasons.contains(&"links to this note".to_string()));
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::index_notes;
+use knowledge_base_backend::search::{HighlightFormat, SearchEngine, SearchOptions};
+
+async fn indexed_engine(content: &str) -> (SearchEngine, tempfile::TempDir) {
+    let (db, temp_dir) = index_notes(&[("notes.md", content)], None).await;
+    (SearchEngine::new(db), temp_dir)
+}
+
+fn long_document() -> String {
+    // The match sits far beyond the 500 character excerpt
+    let filler = "Filler sentence about unrelated matters. ".repeat(60);
+    format!("# Notes\n\n{filler}The Kubernetes cluster <prod> runs Kubernetes 1.29.\n")
+}
+
+#[tokio::test]
+async fn snippets_come_from_matching_chunks_with_document_offsets() {
+    let content = long_document();
+    let (engine, _dir) = indexed_engine(&content).await;
+
+    let results = engine.search("kubernetes", None, 10, 0, false).await.unwrap();
+    assert_eq!(results.len(), 1);
+    let snippet = &results[0].snippets[0];
+    assert!(snippet.text.contains("Kubernetes cluster"));
+
+    let chars: Vec<char> = content.chars().collect();
+    let document_text: String = chars[snippet.start_pos..snippet.end_pos].iter().collect();
+    assert_eq!(document_text, snippet.text);
+
+    assert_eq!(snippet.matches.len(), 2);
+    for range in &snippet.matches {
+        let matched: String = chars[range.start..range.end].iter().collect();
+        assert_eq!(matched, "Kubernetes");
+    }
+}
+
+#[tokio::test]
+async fn highlighting_keeps_original_text_in_every_format() {
+    let (engine, _dir) = indexed_engine(&long_document()).await;
+
+    let search = |highlight| {
+        let engine = engine.clone();
+        async move {
+            let options = SearchOptions { highlight, ..Default::default() };
+            let response = engine.search_page("kubernetes", &options).await.unwrap();
+            response.results[0].snippets[0].clone()
+        }
+    };
+
+    let markdown = search(HighlightFormat::Markdown).await;
+    assert!(markdown.highlighted.contains("**Kubernetes** cluster <prod>"));
+
+    let html = search(HighlightFormat::Html).await;
+    assert!(html.highlighted.contains("<mark>Kubernetes</mark> cluster &lt;prod&gt;"));
+
+    let raw = search(HighlightFormat::Raw).await;
+    assert_eq!(raw.highlighted, raw.text);
+    assert_eq!(raw.matches, markdown.matches);
+}
+
+#[tokio::test]
+async fn inflected_forms_and_phrases_are_highlighted() {
+    let (engine, _dir) = indexed_engine("# Plans\n\nBoth Projects ship the release notes on Friday.").await;
+
+    let results = engine.search("project \"release notes\"", None, 10, 0, false).await.unwrap();
+    let highlighted = &results[0].snippets[0].highlighted;
+    assert!(highlighted.contains("**Projects**"));
+    assert!(highlighted.contains("**release notes**"));
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::MockLlm;
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::mcp::{MCPRequest, MCPServer, StreamEvent};
+use std::fs;
+
+const ANSWER: &str = "The rollout is Monday.";
+
+#[tokio::test]
+async fn generate_stream_hands_over_tokens_as_they_arrive() {
+    let llm = MockLlm::new().reply(ANSWER).start().await;
+
+    let mut tokens = Vec::new();
+    let text = llm.client().generate_stream("prompt", None, |token| tokens.push(token.to_string())).await.unwrap();
+    assert_eq!(tokens, vec!["The ", "rollout ", "is ", "Monday."]);
+    assert_eq!(text, ANSWER);
+
+    let llm = MockLlm::new().reply("Par").stream_error("model unloaded").start().await;
+    let error = llm.client().generate_stream("prompt", None, |_| {}).await.unwrap_err();
+    assert!(error.to_string().contains("model unloaded"));
+}
+
+#[tokio::test]
+async fn streamed_answers_send_citations_then_tokens_then_metadata() {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let temp_dir = tempfile::tempdir().unwrap();
+    fs::write(temp_dir.path().join("rollout.md"), "# Rollout\n\nThe canary rollout starts on Monday.").unwrap();
+    CorpusManager::new(db.clone(), vec![]).index_folder(temp_dir.path(), None).await.unwrap();
+
+    let llm = MockLlm::new().reply_when("When does the rollout start?", ANSWER).start().await;
+    let mcp = MCPServer::new(db, llm.client());
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let request = MCPRequest {
+        tool: "answer_question".to_string(),
+        arguments: serde_json::json!({"question": "When does the rollout start?"}),
+    };
+    mcp.handle_stream_request(request, sender).await.unwrap();
+
+    let mut events = Vec::new();
+    while let Some(event) = receiver.recv().await {
+        events.push(event);
+    }
+    let names: Vec<&str> = events.iter().map(|e| e.name()).collect();
+    assert_eq!(names, vec!["citations", "token", "token", "token", "token", "done"]);
+
+    match &events[0] {
+        StreamEvent::Citations { citations } => assert_eq!(citations[0]["filename"], "rollout.md"),
+        other => panic!("expected citations, got {:?}", other),
+    }
+    let answer: String = events
+        .iter()
+        .filter_map(|e| match e {
+            StreamEvent::Token { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(answer, ANSWER);
+    match &events[5] {
+        StreamEvent::Done { metadata } => {
+            assert_eq!(metadata["context_chunks"], 1);
+            assert!(metadata["confidence"].is_string());
+        }
+        other => panic!("expected done, got {:?}", other),
+    }
+
+    // Serialized events carry their name for SSE clients
+    let json = serde_json::to_value(&events[1]).unwrap();
+    assert_eq!(json, serde_json::json!({"event": "token", "text": "The "}));
+
+    let (sender, _receiver) = tokio::sync::mpsc::unbounded_channel();
+    let request = MCPRequest { tool: "list_notes".to_string(), arguments: serde_json::json!({}) };
+    assert!(mcp.handle_stream_request(request, sender).await.is_err());
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::{index_notes, MockLlm, MockLlmServer};
+use knowledge_base_backend::budget::{estimate_tokens, split_to_budget, ContextConfig};
+use knowledge_base_backend::mcp::{MCPRequest, MCPServer, StreamEvent};
+use std::collections::HashMap;
+use std::fs;
+
+fn long_note(topic: &str) -> String {
+    (1..=24)
+        .map(|i| format!("Paragraph {} about the {}. {}", i, topic, "The team reviewed metrics, risks and owners in detail. ".repeat(5)))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Summarize every prompt as "Summary N.", N counting the calls.
+async fn setup() -> (MCPServer, MockLlmServer, uuid::Uuid, tempfile::TempDir) {
+    let (db, temp_dir) = index_notes(&[("review.md", &long_note("billing migration"))], None).await;
+    let document_id = db.list_documents_page(1, 0, None).await.unwrap()[0].id;
+
+    let config = ContextConfig { windows: HashMap::from([(common::MODEL.to_string(), 900)]), answer_reserve: 200 };
+    let llm = (1..=200).fold(MockLlm::new(), |llm, n| llm.reply(&format!("Summary {}.", n))).start().await;
+    (MCPServer::new(db, llm.client()).with_context_config(config), llm, document_id, temp_dir)
+}
+
+async fn summarize(mcp: &MCPServer, arguments: serde_json::Value) -> serde_json::Value {
+    let request = MCPRequest { tool: "summarize_note".to_string(), arguments };
+    mcp.handle_request(request).await.unwrap().data.unwrap()
+}
+
+#[test]
+fn content_is_split_into_sections_within_budget() {
+    let text = long_note("rollout");
+    let sections = split_to_budget(&text, 150);
+    assert!(sections.len() > 1);
+    assert!(sections.iter().all(|section| estimate_tokens(section) <= 150));
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let rejoined: Vec<&str> = sections.iter().flat_map(|s| s.split_whitespace()).collect();
+    assert_eq!(words, rejoined);
+
+    // A single paragraph longer than a section is cut between words
+    let paragraph = "word ".repeat(400);
+    let sections = split_to_budget(&paragraph, 100);
+    assert_eq!(sections.len(), 5);
+    assert!(sections.iter().all(|section| estimate_tokens(section) <= 100));
+}
+
+#[tokio::test]
+async fn long_notes_are_map_reduced_and_cached() {
+    let (mcp, llm, id, dir) = setup().await;
+
+    let data = summarize(&mcp, serde_json::json!({"id": id, "length": "short"})).await;
+    let sections = data["sections"].as_u64().unwrap() as usize;
+    assert!(sections > 1);
+    assert_eq!(data["cached"], false);
+    assert_eq!(data["context_budget"]["truncated"], false);
+    {
+        let prompts = llm.prompts();
+        assert_eq!(prompts.len(), sections + 1);
+        assert!(prompts[0].contains("concise summary of one section"));
+        // The final prompt merges the section summaries instead of the content
+        let last = prompts.last().unwrap();
+        assert!(last.contains("Summary 1.") && last.contains(&format!("Summary {}.", sections)));
+        assert!(!last.contains("Paragraph 1 about"));
+        assert!(estimate_tokens(last) <= 700);
+        assert_eq!(data["summary"], format!("Summary {}.", prompts.len()));
+    }
+
+    // Unchanged content is served from the cache
+    let cached = summarize(&mcp, serde_json::json!({"id": id, "length": "short"})).await;
+    assert_eq!(cached["cached"], true);
+    assert_eq!(cached["summary"], data["summary"]);
+    assert_eq!(cached["sections"], sections);
+    assert_eq!(llm.prompts().len(), sections + 1);
+
+    // Other lengths, refreshes and edited content are generated again
+    let calls = llm.prompts().len();
+    assert_eq!(summarize(&mcp, serde_json::json!({"id": id, "length": "long"})).await["cached"], false);
+    assert_eq!(summarize(&mcp, serde_json::json!({"id": id, "length": "short", "refresh": true})).await["cached"], false);
+    fs::write(dir.path().join("review.md"), long_note("payroll migration")).unwrap();
+    assert_eq!(summarize(&mcp, serde_json::json!({"id": id, "length": "short"})).await["cached"], false);
+    assert_eq!(llm.prompts().len(), calls + 3 * (sections + 1));
+}
+
+#[tokio::test]
+async fn streamed_summaries_report_progress_through_sections() {
+    let (mcp, llm, id, _dir) = setup().await;
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let request = MCPRequest { tool: "summarize_note".to_string(), arguments: serde_json::json!({"id": id}) };
+    mcp.handle_stream_request(request, sender).await.unwrap();
+    let mut events = Vec::new();
+    while let Some(event) = receiver.recv().await {
+        events.push(event);
+    }
+
+    let sections = llm.prompts().len() - 1;
+    let progress: Vec<(String, usize, usize)> = events
+        .iter()
+        .filter_map(|e| match e {
+            StreamEvent::Progress { stage, completed, total } => Some((stage.clone(), *completed, *total)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(progress.len(), sections);
+    assert_eq!(progress.last().unwrap(), &("map".to_string(), sections, sections));
+
+    let names: Vec<&str> = events.iter().map(|e| e.name()).collect();
+    assert_eq!(names.first(), Some(&"citations"));
+    assert_eq!(&names[names.len() - 2..], &["token", "done"]);
+    match events.last().unwrap() {
+        StreamEvent::Done { metadata } => assert_eq!(metadata["sections"], sections),
+        other => panic!("expected done, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn summaries_stop_when_the_client_disconnects() {
+    let (mcp, _llm, id, _dir) = setup().await;
+
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    drop(receiver);
+    let request = MCPRequest { tool: "summarize_note".to_string(), arguments: serde_json::json!({"id": id}) };
+    assert!(mcp.handle_stream_request(request, sender).await.is_err());
+
+    // Nothing was cached for the client that went away
+    assert_eq!(summarize(&mcp, serde_json::json!({"id": id})).await["cached"], false);
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use common::index_notes_into;
+use knowledge_base_backend::database::Database;
+use knowledge_base_backend::mcp::{MCPRequest, MCPServer};
+use knowledge_base_backend::ollama::OllamaClient;
+use knowledge_base_backend::search::{SearchEngine, SearchFilters, SearchOptions};
+use uuid::Uuid;
+
+async fn setup() -> (Database, Uuid, Uuid, tempfile::TempDir, tempfile::TempDir) {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let work = db.create_project("Work", None).await.unwrap();
+    let home = db.create_project("Home", None).await.unwrap();
+
+    let work_notes = [
+        ("cluster.md", "# Cluster\n\nThe kubernetes cluster runs on three nodes."),
+        ("upgrade.md", "# Upgrade\n\nUpgrade k8s before the freeze."),
+        ("review.md", "# Review\n\nEvery pull request needs two approvals."),
+    ];
+    let work_dir = index_notes_into(&db, &work_notes, Some(work.id)).await;
+    let home_dir = index_notes_into(&db, &[("garden.md", "# Garden\n\nThe kubernetes of tomatoes is a joke.")], Some(home.id)).await;
+    (db, work.id, home.id, work_dir, home_dir)
+}
+
+fn filenames(results: &[knowledge_base_backend::search::SearchResult]) -> Vec<&str> {
+    results.iter().map(|r| r.document.filename.as_str()).collect()
+}
+
+fn in_project(project_id: Uuid) -> SearchOptions {
+    let filters = SearchFilters { project_ids: Some(vec![project_id]), ..Default::default() };
+    SearchOptions { filters: Some(filters), explain: true, ..Default::default() }
+}
+
+#[tokio::test]
+async fn synonyms_expand_search_terms_with_a_lower_weight() {
+    let (db, work, home, _work_dir, _home_dir) = setup().await;
+    db.upsert_synonym(Some(&work), "k8s", &["kubernetes".to_string()]).await.unwrap();
+    let engine = SearchEngine::new(db);
+
+    let response = engine.search_page("k8s", &in_project(work)).await.unwrap();
+    // The typed term outranks its synonym
+    assert_eq!(filenames(&response.results), vec!["upgrade.md", "cluster.md"]);
+    assert!(response.results[0].score > response.results[1].score);
+    let explanation = response.results[1].explanation.as_ref().unwrap();
+    assert!(explanation.components.iter().any(|c| c.detail.contains("synonym of \"k8s\"")));
+    assert!(response.results[1].snippets[0].text.to_lowercase().contains("kubernetes"));
+
+    // Entries work in both directions
+    let response = engine.search_page("kubernetes", &in_project(work)).await.unwrap();
+    assert_eq!(filenames(&response.results), vec!["cluster.md", "upgrade.md"]);
+
+    // Other projects keep their own vocabulary
+    let response = engine.search_page("k8s", &in_project(home)).await.unwrap();
+    assert!(response.results.is_empty());
+}
+
+#[tokio::test]
+async fn multi_word_synonyms_match_as_phrases_in_search_and_qa() {
+    let (db, work, _home, _work_dir, _home_dir) = setup().await;
+    // Global entries apply to every project
+    db.upsert_synonym(None, "PR", &["pull request".to_string()]).await.unwrap();
+    let engine = SearchEngine::new(db);
+
+    let response = engine.search_page("pr approvals", &in_project(work)).await.unwrap();
+    assert_eq!(response.results[0].document.filename, "review.md");
+
+    let chunks = engine
+        .get_relevant_chunks_for_qa_with_filters("Who approves a PR?", 5, Some(&[work]), 2, None)
+        .await
+        .unwrap();
+    assert_eq!(chunks[0].document.filename, "review.md");
+
+    // Typed multi-word terms expand to their abbreviation too
+    let chunks = engine.get_relevant_chunks_for_qa("What do we do before pull request review?", 5).await.unwrap();
+    assert!(chunks.iter().any(|c| c.document.filename == "review.md"));
+}
+
+#[tokio::test]
+async fn dictionary_is_managed_through_mcp_tools() {
+    let (db, work, _home, _work_dir, _home_dir) = setup().await;
+    let ollama = OllamaClient::new("http://localhost:11434".to_string(), "gpt-oss:20b".to_string());
+    let mcp = MCPServer::new(db, ollama);
+    let call = |tool: &str, arguments: serde_json::Value| {
+        let request = MCPRequest { tool: tool.to_string(), arguments };
+        mcp.handle_request(request)
+    };
+
+    let created = call("set_synonyms", serde_json::json!({"term": "OKR", "synonyms": ["objectives"], "project_id": work}))
+        .await
+        .unwrap()
+        .data
+        .unwrap();
+    // Setting a term again replaces its entry
+    let replaced = call("set_synonyms", serde_json::json!({"term": "okr", "synonyms": ["célkitűzés", "objectives"], "project_id": work}))
+        .await
+        .unwrap()
+        .data
+        .unwrap();
+    assert_eq!(created["synonym"]["id"], replaced["synonym"]["id"]);
+    call("set_synonyms", serde_json::json!({"term": "k8s", "synonyms": ["kubernetes"]})).await.unwrap();
+
+    let listed = call("list_synonyms", serde_json::json!({"project_id": work})).await.unwrap().data.unwrap();
+    let entries = listed["synonyms"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1]["synonyms"], serde_json::json!(["célkitűzés", "objectives"]));
+
+    assert!(call("set_synonyms", serde_json::json!({"term": "pr", "synonyms": ["PR"]})).await.is_err());
+    assert!(call("set_synonyms", serde_json::json!({"term": "pr", "synonyms": ["x"], "project_id": Uuid::new_v4()})).await.is_err());
+
+    let id = replaced["synonym"]["id"].clone();
+    call("delete_synonym", serde_json::json!({"id": id})).await.unwrap();
+    assert!(call("delete_synonym", serde_json::json!({"id": id})).await.is_err());
+    let listed = call("list_synonyms", serde_json::json!({})).await.unwrap().data.unwrap();
+    assert_eq!(listed["synonyms"].as_array().unwrap().len(), 1);
+}
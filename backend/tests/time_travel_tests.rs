@@ -0,0 +1,133 @@
+use knowledge_base_backend::corpus::CorpusManager;
+use knowledge_base_backend::database::{Database, Versions};
+use knowledge_base_backend::mcp::{MCPRequest, MCPServer};
+use knowledge_base_backend::ollama::OllamaClient;
+use knowledge_base_backend::search::{SearchEngine, SearchOptions};
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::time::Duration;
+
+struct History {
+    db: Database,
+    before: DateTime<Utc>,
+    between: DateTime<Utc>,
+    _dir: tempfile::TempDir,
+}
+
+/// Index `deploy.md` twice: first on Jenkins, then rewritten for GitHub Actions.
+async fn setup() -> History {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let temp_dir = tempfile::tempdir().unwrap();
+    let corpus = CorpusManager::new(db.clone(), vec![]);
+    let path = temp_dir.path().join("deploy.md");
+
+    let before = Utc::now();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    fs::write(&path, "# Deploy\n\nReleases are built by the Jenkins pipeline.").unwrap();
+    corpus.index_folder(temp_dir.path(), None).await.unwrap();
+    fs::write(temp_dir.path().join("oncall.md"), "# Oncall\n\nThe pipeline pages the oncall engineer.").unwrap();
+    corpus.index_folder(temp_dir.path(), None).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let between = Utc::now();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    fs::write(&path, "# Deploy\n\nReleases are built by the GitHub Actions pipeline.").unwrap();
+    corpus.index_folder(temp_dir.path(), None).await.unwrap();
+
+    History { db, before, between, _dir: temp_dir }
+}
+
+#[tokio::test]
+async fn as_of_resolves_the_version_latest_at_that_moment() {
+    let history = setup().await;
+    let engine = SearchEngine::new(history.db.clone());
+
+    let search = |query: &'static str, as_of| {
+        let engine = engine.clone();
+        async move {
+            let options = SearchOptions { as_of, ..Default::default() };
+            engine.search_page(query, &options).await.unwrap().results
+        }
+    };
+
+    let results = search("pipeline", Some(history.between)).await;
+    assert_eq!(results.len(), 2);
+    let deploy = results.iter().find(|r| r.document.filename == "deploy.md").unwrap();
+    assert_eq!(deploy.document.version, 1);
+    assert!(!deploy.document.is_latest);
+    assert_eq!(search("jenkins", Some(history.between)).await.len(), 1);
+    assert!(search("actions", Some(history.between)).await.is_empty());
+
+    // Without as_of only the current version is searched
+    assert!(search("jenkins", None).await.is_empty());
+    assert_eq!(search("actions", None).await[0].document.version, 2);
+
+    // Nothing had been indexed yet
+    assert!(search("pipeline", Some(history.before)).await.is_empty());
+}
+
+#[tokio::test]
+async fn queries_scoped_to_a_moment_see_one_version_per_path() {
+    let history = setup().await;
+    let terms = vec!["pipelin".to_string(), "pipeline".to_string()];
+
+    let documents = history.db
+        .search_documents_by_terms(&terms, false, 1, 0, Versions::AsOf(history.between), None)
+        .await
+        .unwrap();
+    assert_eq!(documents.len(), 1);
+    let documents = history.db
+        .search_documents_by_terms(&terms, false, 10, 0, Versions::AsOf(history.between), None)
+        .await
+        .unwrap();
+    let mut versions: Vec<(String, u32)> = documents.iter().map(|d| (d.filename.clone(), d.version)).collect();
+    versions.sort();
+    assert_eq!(versions, vec![("deploy.md".to_string(), 1), ("oncall.md".to_string(), 1)]);
+
+    let all = history.db.search_documents_by_terms(&terms, false, 10, 0, Versions::All, None).await.unwrap();
+    assert_eq!(all.len(), 3);
+}
+
+#[tokio::test]
+async fn answer_retrieval_cites_versions_from_the_past() {
+    let history = setup().await;
+    let engine = SearchEngine::new(history.db.clone());
+
+    let chunks = engine
+        .get_relevant_chunks_for_qa_with_filters("Which pipeline builds releases?", 5, None, 2, Some(history.between))
+        .await
+        .unwrap();
+    let deploy = chunks.iter().find(|c| c.document.filename == "deploy.md").unwrap();
+    assert_eq!(deploy.document.version, 1);
+    assert!(deploy.entry.chunk_text.contains("Jenkins"));
+
+    let chunks = engine
+        .get_relevant_chunks_for_qa_with_filters("Which pipeline builds releases?", 5, None, 2, None)
+        .await
+        .unwrap();
+    let deploy = chunks.iter().find(|c| c.document.filename == "deploy.md").unwrap();
+    assert!(deploy.entry.chunk_text.contains("GitHub Actions"));
+}
+
+#[tokio::test]
+async fn search_notes_accepts_rfc3339_as_of() {
+    let history = setup().await;
+    let ollama = OllamaClient::new("http://localhost:11434".to_string(), "gpt-oss:20b".to_string());
+    let mcp = MCPServer::new(history.db.clone(), ollama);
+
+    let request = MCPRequest {
+        tool: "search_notes".to_string(),
+        arguments: serde_json::json!({"query": "jenkins", "as_of": history.between.to_rfc3339()}),
+    };
+    let response = mcp.handle_request(request).await.unwrap();
+    let data = response.data.unwrap();
+    assert_eq!(data["total"], 1);
+    assert_eq!(data["results"][0]["document"]["version"], 1);
+
+    let request = MCPRequest {
+        tool: "search_notes".to_string(),
+        arguments: serde_json::json!({"query": "jenkins", "as_of": "last march"}),
+    };
+    assert!(mcp.handle_request(request).await.is_err());
+}
@@ -1,6 +1,5 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use serde_json;
 use std::path::PathBuf;
 use std::io::{self, Write};
 
@@ -30,6 +29,12 @@ enum Commands {
         /// Output format (text, json)
         #[arg(short, long, default_value = "text")]
         format: String,
+        /// Search mode (keyword, semantic)
+        #[arg(short, long, default_value = "keyword")]
+        mode: String,
+        /// Show why each result ranked where it did
+        #[arg(long)]
+        explain: bool,
     },
     /// Read a specific document
     Read {
@@ -39,6 +44,17 @@ enum Commands {
         #[arg(short, long, default_value = "text")]
         format: String,
     },
+    /// Find notes similar to a document
+    Similar {
+        /// Document ID
+        id: String,
+        /// Maximum number of similar notes
+        #[arg(short, long, default_value = "10")]
+        limit: u32,
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
     /// Summarize a document
     Summarize {
         /// Document ID
@@ -46,6 +62,43 @@ enum Commands {
         /// Summary length (short, medium, long)
         #[arg(short, long, default_value = "medium")]
         length: String,
+        /// Regenerate instead of reusing a cached summary
+        #[arg(long)]
+        refresh: bool,
+        /// Model to summarize with instead of the configured one
+        #[arg(short, long)]
+        model: Option<String>,
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+    /// Summarize a whole project, folder, tag or saved search
+    #[command(alias = "digest")]
+    SummarizeCollection {
+        /// Project ID
+        #[arg(long, group = "collection")]
+        project: Option<String>,
+        /// Folder path
+        #[arg(long, group = "collection")]
+        folder: Option<String>,
+        /// Tag
+        #[arg(long, group = "collection")]
+        tag: Option<String>,
+        /// Saved search ID or name
+        #[arg(long = "saved", group = "collection")]
+        saved_search: Option<String>,
+        /// Overview length (short, medium, long)
+        #[arg(short, long, default_value = "medium")]
+        length: String,
+        /// Most recently modified documents to include
+        #[arg(long, default_value = "20")]
+        limit: u32,
+        /// Regenerate document summaries instead of reusing cached ones
+        #[arg(long)]
+        refresh: bool,
+        /// Model to summarize with instead of the configured one
+        #[arg(short, long)]
+        model: Option<String>,
         /// Output format (text, json)
         #[arg(short, long, default_value = "text")]
         format: String,
@@ -57,6 +110,12 @@ enum Commands {
         /// Number of context chunks to use
         #[arg(short, long, default_value = "5")]
         top_k: u32,
+        /// Answer language (e.g. English), or "auto" to follow the question
+        #[arg(long)]
+        language: Option<String>,
+        /// Model to answer with instead of the configured one
+        #[arg(short, long)]
+        model: Option<String>,
         /// Output format (text, json)
         #[arg(short, long, default_value = "text")]
         format: String,
@@ -87,6 +146,175 @@ enum Commands {
         #[command(subcommand)]
         action: ProjectAction,
     },
+    /// Saved searches (smart collections)
+    Saved {
+        #[command(subcommand)]
+        action: SavedAction,
+    },
+    /// Synonym and abbreviation dictionary used to expand searches
+    Synonym {
+        #[command(subcommand)]
+        action: SynonymAction,
+    },
+    /// Prompt templates for answers and summaries
+    Prompt {
+        #[command(subcommand)]
+        action: PromptAction,
+    },
+    /// Models on the configured LLM server
+    Models {
+        #[command(subcommand)]
+        action: ModelAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum ModelAction {
+    /// List installed models and the model used for each task
+    List {
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+    /// Show the details and context window of a model
+    Show {
+        /// Model name
+        name: String,
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+    /// Check that the configured models are installed
+    Check {
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum PromptAction {
+    /// Show the templates in effect and all overrides
+    Show {
+        /// Resolve the templates for this project ID
+        #[arg(short, long)]
+        project: Option<String>,
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+    /// Override a template; it may use {question}, {context}, {language} and {length}
+    Set {
+        /// Template kind (answer, summary)
+        kind: String,
+        /// File containing the template
+        file: PathBuf,
+        /// Project ID the override applies to (global if omitted)
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+    /// Remove an override
+    Reset {
+        /// Template kind (answer, summary)
+        kind: String,
+        /// Project ID of the override (global if omitted)
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+    /// Print the prompt a question would be answered with
+    Preview {
+        /// Question to render the answer prompt for
+        question: String,
+        /// Only use context from this project ID
+        #[arg(short, long)]
+        project: Option<String>,
+        /// Answer language, or "auto"
+        #[arg(long)]
+        language: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SynonymAction {
+    /// List dictionary entries
+    List {
+        /// Only entries applying to this project ID (global ones included)
+        #[arg(short, long)]
+        project: Option<String>,
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+    /// Define the synonyms of a term, replacing any previous entry
+    Add {
+        /// Term as typed in queries, e.g. k8s
+        term: String,
+        /// Equivalent terms or phrases, e.g. kubernetes
+        #[arg(required = true)]
+        synonyms: Vec<String>,
+        /// Project ID the entry belongs to (global if omitted)
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+    /// Delete a dictionary entry
+    Delete {
+        /// Entry ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum SavedAction {
+    /// List saved searches
+    List {
+        /// Run every saved search to show its number of matches
+        #[arg(long)]
+        counts: bool,
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+    /// Save a search under a name
+    Add {
+        /// Name of the saved search
+        name: String,
+        /// Search query
+        query: String,
+        /// Search mode (keyword, semantic)
+        #[arg(short, long, default_value = "keyword")]
+        mode: String,
+        /// Only match documents with this tag (repeatable)
+        #[arg(long)]
+        tag: Vec<String>,
+        /// Only match documents of this file type (repeatable)
+        #[arg(long = "type")]
+        file_type: Vec<String>,
+        /// Only match documents of this project ID (repeatable)
+        #[arg(short, long)]
+        project: Vec<String>,
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+    /// Run a saved search
+    Run {
+        /// Saved search name or ID
+        name: String,
+        /// Only show documents indexed since the previous run
+        #[arg(long)]
+        new: bool,
+        /// Maximum number of results
+        #[arg(short, long, default_value = "20")]
+        limit: u32,
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+    /// Delete a saved search
+    Delete {
+        /// Saved search name or ID
+        name: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -181,7 +409,7 @@ impl KnowledgeBaseClient {
         });
 
         let response = self.client
-            .post(&format!("{}/api/request", self.base_url))
+            .post(format!("{}/api/request", self.base_url))
             .json(&request)
             .send()
             .await?;
@@ -195,13 +423,58 @@ impl KnowledgeBaseClient {
         }
     }
 
+    /// Call a tool through the streaming endpoint, handing every
+    /// Server-Sent Event (name and JSON data) to `on_event` as it arrives.
+    async fn stream_request<F>(&self, tool: &str, arguments: serde_json::Value, mut on_event: F) -> Result<()>
+    where
+        F: FnMut(&str, serde_json::Value) -> Result<()>,
+    {
+        let request = serde_json::json!({
+            "tool": tool,
+            "arguments": arguments
+        });
+
+        let mut response = self.client
+            .post(format!("{}/api/stream", self.base_url))
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        // Bytes are buffered until an event is complete, so characters split
+        // across chunks are decoded whole
+        let mut buffer: Vec<u8> = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            buffer.extend_from_slice(&chunk);
+            // Events end with a blank line
+            while let Some(end) = buffer.windows(2).position(|window| window == b"\n\n") {
+                let event: Vec<u8> = buffer.drain(..end + 2).collect();
+                let event = std::str::from_utf8(&event)?;
+                let mut name = "message";
+                let mut data = String::new();
+                for line in event.lines() {
+                    if let Some(value) = line.strip_prefix("event:") {
+                        name = value.trim();
+                    } else if let Some(value) = line.strip_prefix("data:") {
+                        data.push_str(value.strip_prefix(' ').unwrap_or(value));
+                    }
+                }
+                // Keep-alive comments carry no data
+                if !data.is_empty() {
+                    on_event(name, serde_json::from_str(&data)?)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     async fn index_folders(&self, folders: Vec<PathBuf>) -> Result<serde_json::Value> {
         let request = serde_json::json!({
             "folders": folders
         });
 
         let response = self.client
-            .post(&format!("{}/api/index", self.base_url))
+            .post(format!("{}/api/index", self.base_url))
             .json(&request)
             .send()
             .await?;
@@ -214,6 +487,9 @@ impl KnowledgeBaseClient {
         println!("Knowledge Base Interactive Q&A Mode");
         println!("Type 'exit' or 'quit' to leave, 'help' for commands\n");
 
+        // Questions are asked in a conversation so follow-ups keep their context
+        let mut conversation_id: Option<String> = None;
+
         loop {
             print!("ask> ");
             io::stdout().flush()?;
@@ -236,13 +512,16 @@ impl KnowledgeBaseClient {
                     println!("  ask <question>  - Ask a question about your knowledge base");
                     println!("  search <query>  - Search your knowledge base");
                     println!("  list            - List all indexed documents");
+                    println!("  :new            - Start a new conversation");
+                    println!("  :sessions       - List saved conversations");
+                    println!("  :resume <id>    - Continue a saved conversation");
+                    println!("  :delete <id>    - Delete a saved conversation");
                     println!("  help            - Show this help message");
                     println!("  exit/quit       - Exit the interactive mode");
                     continue;
                 }
                 _ => {
-                    if question.starts_with("search ") {
-                        let query = &question[7..];
+                    if let Some(query) = question.strip_prefix("search ") {
                         if query.is_empty() {
                             println!("Please provide a search query");
                             continue;
@@ -250,9 +529,37 @@ impl KnowledgeBaseClient {
                         self.handle_search_command(query, 20, "text").await?;
                     } else if question == "list" {
                         self.handle_list_command(20, "text").await?;
+                    } else if question == ":new" {
+                        conversation_id = None;
+                        println!("Started a new conversation");
+                    } else if question == ":sessions" {
+                        match self.make_request("list_conversations", serde_json::json!({})).await {
+                            Ok(data) => print_conversations(&data),
+                            Err(e) => eprintln!("Failed to list conversations: {}", e),
+                        }
+                    } else if let Some(id) = question.strip_prefix(":resume ") {
+                        match self.make_request("get_conversation", serde_json::json!({ "id": id.trim() })).await {
+                            Ok(data) => {
+                                print_conversation(&data);
+                                conversation_id = data["conversation"]["id"].as_str().map(str::to_string);
+                            }
+                            Err(e) => eprintln!("Failed to resume conversation: {}", e),
+                        }
+                    } else if let Some(id) = question.strip_prefix(":delete ") {
+                        let id = id.trim();
+                        match self.make_request("delete_conversation", serde_json::json!({ "id": id })).await {
+                            Ok(_) => {
+                                if conversation_id.as_deref() == Some(id) {
+                                    conversation_id = None;
+                                }
+                                println!("Deleted conversation {}", id);
+                            }
+                            Err(e) => eprintln!("Failed to delete conversation: {}", e),
+                        }
                     } else {
                         // Treat as a question
-                        self.handle_ask_command(question, 5, "text").await?;
+                        let question = question.strip_prefix("ask ").unwrap_or(question);
+                        self.handle_conversation_question(question, &mut conversation_id).await?;
                     }
                 }
             }
@@ -262,23 +569,28 @@ impl KnowledgeBaseClient {
         Ok(())
     }
 
-    async fn handle_ask_command(&self, question: &str, top_k: u32, format: &str) -> Result<()> {
+    /// Answer `question` as the next turn of the current conversation,
+    /// starting one first if there is none.
+    async fn handle_conversation_question(&self, question: &str, conversation_id: &mut Option<String>) -> Result<()> {
+        if conversation_id.is_none() {
+            let title: String = question.chars().take(60).collect();
+            match self.make_request("start_conversation", serde_json::json!({ "title": title })).await {
+                Ok(data) => *conversation_id = data["conversation"]["id"].as_str().map(str::to_string),
+                Err(e) => {
+                    eprintln!("Failed to start conversation: {}", e);
+                    return Ok(());
+                }
+            }
+        }
+
         let arguments = serde_json::json!({
             "question": question,
-            "top_k": top_k
+            "top_k": 5,
+            "conversation_id": conversation_id
         });
-
         match self.make_request("answer_question", arguments).await {
-            Ok(data) => {
-                if format == "json" {
-                    println!("{}", serde_json::to_string_pretty(&data)?);
-                } else {
-                    print_answer(&data);
-                }
-            }
-            Err(e) => {
-                eprintln!("Failed to get answer: {}", e);
-            }
+            Ok(data) => print_answer(&data),
+            Err(e) => eprintln!("Failed to get answer: {}", e),
         }
         Ok(())
     }
@@ -336,7 +648,7 @@ impl KnowledgeBaseClient {
         }
 
         let response = self.client
-            .post(&format!("{}/api/index", self.base_url))
+            .post(format!("{}/api/index", self.base_url))
             .json(&request_body)
             .send()
             .await?;
@@ -430,11 +742,13 @@ async fn main() -> Result<()> {
                     }
                 }
             }
-            Commands::Search { query, limit, format } => {
+            Commands::Search { query, limit, format, mode, explain } => {
                 let arguments = serde_json::json!({
                     "query": query,
                     "limit": limit,
-                    "offset": 0
+                    "offset": 0,
+                    "mode": mode,
+                    "explain": explain
                 });
 
                 match client.make_request("search_notes", arguments).await {
@@ -470,44 +784,187 @@ async fn main() -> Result<()> {
                     }
                 }
             }
-            Commands::Summarize { id, length, format } => {
+            Commands::Similar { id, limit, format } => {
                 let arguments = serde_json::json!({
                     "id": id,
-                    "length": length
+                    "limit": limit
                 });
 
-                match client.make_request("summarize_note", arguments).await {
+                match client.make_request("find_similar", arguments).await {
                     Ok(data) => {
                         if format == "json" {
                             println!("{}", serde_json::to_string_pretty(&data)?);
                         } else {
-                            print_summary(&data);
+                            print_similar(&data);
                         }
                     }
                     Err(e) => {
+                        eprintln!("Failed to find similar notes: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            Commands::Summarize { id, length, refresh, model, format } => {
+                let mut arguments = serde_json::json!({
+                    "id": id,
+                    "length": length,
+                    "refresh": refresh
+                });
+                if let Some(model) = model {
+                    arguments["model"] = serde_json::json!(model);
+                }
+
+                if format == "json" {
+                    match client.make_request("summarize_note", arguments).await {
+                        Ok(data) => println!("{}", serde_json::to_string_pretty(&data)?),
+                        Err(e) => {
+                            eprintln!("Failed to summarize document: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                } else {
+                    // Stream so long documents can report progress through their sections
+                    let mut summary = String::new();
+                    let mut metadata = serde_json::Value::Null;
+                    let streamed = client
+                        .stream_request("summarize_note", arguments, |event, data| {
+                            match event {
+                                "progress" => {
+                                    let stage = if data["stage"] == "map" { "Summarizing sections" } else { "Merging summaries" };
+                                    eprint!("\r{} {}/{}", stage, data["completed"], data["total"]);
+                                    io::stderr().flush()?;
+                                }
+                                "token" => summary.push_str(data["text"].as_str().unwrap_or("")),
+                                "done" => metadata = data["metadata"].clone(),
+                                "error" => return Err(anyhow::anyhow!("{}", data["message"].as_str().unwrap_or("Unknown error"))),
+                                _ => {}
+                            }
+                            Ok(())
+                        })
+                        .await;
+
+                    if let Err(e) = streamed {
                         eprintln!("Failed to summarize document: {}", e);
                         std::process::exit(1);
                     }
+                    if metadata["sections"].as_u64().unwrap_or(1) > 1 {
+                        eprintln!();
+                    }
+                    metadata["summary"] = serde_json::json!(summary);
+                    print_summary(&metadata);
                 }
             }
-            Commands::Ask { question, top_k, format } => {
-                let arguments = serde_json::json!({
+            Commands::SummarizeCollection { project, folder, tag, saved_search, length, limit, refresh, model, format } => {
+                let mut arguments = serde_json::json!({
+                    "length": length,
+                    "limit": limit,
+                    "refresh": refresh
+                });
+                if let Some(model) = model {
+                    arguments["model"] = serde_json::json!(model);
+                }
+                match (project, folder, tag, saved_search) {
+                    (Some(project), _, _, _) => arguments["project_id"] = serde_json::json!(project),
+                    (_, Some(folder), _, _) => arguments["folder"] = serde_json::json!(folder),
+                    (_, _, Some(tag), _) => arguments["tag"] = serde_json::json!(tag),
+                    (_, _, _, Some(saved_search)) => arguments["saved_search"] = serde_json::json!(saved_search),
+                    _ => {
+                        eprintln!("Specify one of --project, --folder, --tag or --saved");
+                        std::process::exit(1);
+                    }
+                }
+
+                if format == "json" {
+                    match client.make_request("summarize_collection", arguments).await {
+                        Ok(data) => println!("{}", serde_json::to_string_pretty(&data)?),
+                        Err(e) => {
+                            eprintln!("Failed to summarize collection: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                } else {
+                    // Stream so progress through the documents shows while they are summarized
+                    let mut overview = String::new();
+                    let mut metadata = serde_json::Value::Null;
+                    let mut reported_progress = false;
+                    let streamed = client
+                        .stream_request("summarize_collection", arguments, |event, data| {
+                            match event {
+                                "progress" => {
+                                    eprint!("\rSummarizing documents {}/{}", data["completed"], data["total"]);
+                                    io::stderr().flush()?;
+                                    reported_progress = true;
+                                }
+                                "token" => overview.push_str(data["text"].as_str().unwrap_or("")),
+                                "done" => metadata = data["metadata"].clone(),
+                                "error" => return Err(anyhow::anyhow!("{}", data["message"].as_str().unwrap_or("Unknown error"))),
+                                _ => {}
+                            }
+                            Ok(())
+                        })
+                        .await;
+
+                    if reported_progress {
+                        eprintln!();
+                    }
+                    if let Err(e) = streamed {
+                        eprintln!("Failed to summarize collection: {}", e);
+                        std::process::exit(1);
+                    }
+                    print_collection_summary(&metadata);
+                }
+            }
+            Commands::Ask { question, top_k, language, model, format } => {
+                let mut arguments = serde_json::json!({
                     "question": question,
                     "top_k": top_k
                 });
+                if let Some(language) = language {
+                    arguments["language"] = serde_json::json!(language);
+                }
+                if let Some(model) = model {
+                    arguments["model"] = serde_json::json!(model);
+                }
 
-                match client.make_request("answer_question", arguments).await {
-                    Ok(data) => {
-                        if format == "json" {
-                            println!("{}", serde_json::to_string_pretty(&data)?);
-                        } else {
-                            print_answer(&data);
+                if format == "json" {
+                    match client.make_request("answer_question", arguments).await {
+                        Ok(data) => println!("{}", serde_json::to_string_pretty(&data)?),
+                        Err(e) => {
+                            eprintln!("Failed to get answer: {}", e);
+                            std::process::exit(1);
                         }
                     }
-                    Err(e) => {
+                } else {
+                    // Print the answer token by token as it is generated
+                    let mut citations = serde_json::Value::Null;
+                    let mut metadata = serde_json::Value::Null;
+                    println!("Answer:");
+                    let streamed = client
+                        .stream_request("answer_question", arguments, |event, data| {
+                            match event {
+                                "citations" => citations = data["citations"].clone(),
+                                "token" => {
+                                    print!("{}", data["text"].as_str().unwrap_or(""));
+                                    io::stdout().flush()?;
+                                }
+                                "done" => metadata = data["metadata"].clone(),
+                                "error" => return Err(anyhow::anyhow!("{}", data["message"].as_str().unwrap_or("Unknown error"))),
+                                _ => {}
+                            }
+                            Ok(())
+                        })
+                        .await;
+                    println!("\n");
+
+                    if let Err(e) = streamed {
                         eprintln!("Failed to get answer: {}", e);
                         std::process::exit(1);
                     }
+                    // The done event carries the citations flagged as used or not
+                    if metadata.get("citations").is_none() {
+                        metadata["citations"] = citations;
+                    }
+                    print_answer_sources(&metadata);
                 }
             }
             Commands::List { limit, format } => {
@@ -551,6 +1008,244 @@ async fn main() -> Result<()> {
                     }
                 }
             }
+            Commands::Saved { action } => {
+                match action {
+                    SavedAction::List { counts, format } => {
+                        match client.make_request("list_saved_searches", serde_json::json!({ "counts": counts })).await {
+                            Ok(data) => {
+                                if format == "json" {
+                                    println!("{}", serde_json::to_string_pretty(&data)?);
+                                } else {
+                                    print_saved_searches(&data);
+                                }
+                            }
+                            Err(e) => {
+                                eprintln!("Failed to list saved searches: {}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                    SavedAction::Add { name, query, mode, tag, file_type, project, format } => {
+                        let mut filters = serde_json::Map::new();
+                        if !tag.is_empty() {
+                            filters.insert("tags".to_string(), serde_json::json!(tag));
+                        }
+                        if !file_type.is_empty() {
+                            filters.insert("file_types".to_string(), serde_json::json!(file_type));
+                        }
+                        if !project.is_empty() {
+                            filters.insert("project_ids".to_string(), serde_json::json!(project));
+                        }
+                        let mut arguments = serde_json::json!({
+                            "name": name,
+                            "query": query,
+                            "mode": mode
+                        });
+                        if !filters.is_empty() {
+                            arguments["filters"] = serde_json::Value::Object(filters);
+                        }
+
+                        match client.make_request("save_search", arguments).await {
+                            Ok(data) => {
+                                if format == "json" {
+                                    println!("{}", serde_json::to_string_pretty(&data)?);
+                                } else {
+                                    println!("Saved search \"{}\" created.", data["saved_search"]["name"].as_str().unwrap_or(""));
+                                }
+                            }
+                            Err(e) => {
+                                eprintln!("Failed to save search: {}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                    SavedAction::Run { name, new, limit, format } => {
+                        let arguments = serde_json::json!({
+                            "id": name,
+                            "new_only": new,
+                            "limit": limit
+                        });
+
+                        match client.make_request("run_saved_search", arguments).await {
+                            Ok(data) => {
+                                if format == "json" {
+                                    println!("{}", serde_json::to_string_pretty(&data)?);
+                                } else {
+                                    if new {
+                                        match data["new_since"].as_str() {
+                                            Some(since) => println!("New since {}", since),
+                                            None => println!("First run: every match is new"),
+                                        }
+                                    }
+                                    print_search_results(&data);
+                                }
+                            }
+                            Err(e) => {
+                                eprintln!("Failed to run saved search: {}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                    SavedAction::Delete { name } => {
+                        match client.make_request("delete_saved_search", serde_json::json!({ "id": name })).await {
+                            Ok(_) => println!("Saved search \"{}\" deleted.", name),
+                            Err(e) => {
+                                eprintln!("Failed to delete saved search: {}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                }
+            }
+            Commands::Prompt { action } => {
+                let with_project = |mut arguments: serde_json::Value, project: Option<String>| {
+                    if let Some(project) = project {
+                        arguments["project_id"] = serde_json::json!(project);
+                    }
+                    arguments
+                };
+                let (tool, arguments) = match action {
+                    PromptAction::Show { project, format } => {
+                        match client.make_request("list_prompt_templates", with_project(serde_json::json!({}), project)).await {
+                            Ok(data) => {
+                                if format == "json" {
+                                    println!("{}", serde_json::to_string_pretty(&data)?);
+                                } else {
+                                    print_prompt_templates(&data);
+                                }
+                            }
+                            Err(e) => {
+                                eprintln!("Failed to list prompt templates: {}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                        return Ok(());
+                    }
+                    PromptAction::Set { kind, file, project } => {
+                        let template = std::fs::read_to_string(&file)?;
+                        ("set_prompt_template", with_project(serde_json::json!({ "kind": kind, "template": template }), project))
+                    }
+                    PromptAction::Reset { kind, project } => {
+                        ("reset_prompt_template", with_project(serde_json::json!({ "kind": kind }), project))
+                    }
+                    PromptAction::Preview { question, project, language } => {
+                        let mut arguments = serde_json::json!({ "kind": "answer", "question": question });
+                        if let Some(project) = project {
+                            arguments["project_ids"] = serde_json::json!([project]);
+                        }
+                        if let Some(language) = language {
+                            arguments["language"] = serde_json::json!(language);
+                        }
+                        ("preview_prompt", arguments)
+                    }
+                };
+
+                match client.make_request(tool, arguments).await {
+                    Ok(data) => match tool {
+                        "preview_prompt" => {
+                            println!(
+                                "Template: {} ({}), language: {}\n",
+                                data["template"]["kind"].as_str().unwrap_or(""),
+                                data["template"]["source"].as_str().unwrap_or(""),
+                                data["language"].as_str().unwrap_or("")
+                            );
+                            println!("{}", data["prompt"].as_str().unwrap_or(""));
+                        }
+                        "set_prompt_template" => println!("Template saved."),
+                        _ => println!(
+                            "Override removed; now using the {} template.",
+                            data["effective"]["source"].as_str().unwrap_or("default")
+                        ),
+                    },
+                    Err(e) => {
+                        eprintln!("Prompt template request failed: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            Commands::Models { action } => {
+                let (tool, arguments, format) = match action {
+                    ModelAction::List { format } => ("list_models", serde_json::json!({}), format),
+                    ModelAction::Show { name, format } => ("show_model", serde_json::json!({ "name": name }), format),
+                    ModelAction::Check { format } => ("check_models", serde_json::json!({}), format),
+                };
+
+                match client.make_request(tool, arguments).await {
+                    Ok(data) => {
+                        if format == "json" {
+                            println!("{}", serde_json::to_string_pretty(&data)?);
+                        } else {
+                            match tool {
+                                "list_models" => print_models(&data),
+                                "show_model" => print_model_details(&data),
+                                _ => print_model_check(&data["check"]),
+                            }
+                        }
+                        // A failed check fails the command, for scripts and health probes
+                        if tool == "check_models" && data["ok"] != true {
+                            std::process::exit(1);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Model request failed: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            Commands::Synonym { action } => {
+                match action {
+                    SynonymAction::List { project, format } => {
+                        let mut arguments = serde_json::json!({});
+                        if let Some(project) = project {
+                            arguments["project_id"] = serde_json::json!(project);
+                        }
+
+                        match client.make_request("list_synonyms", arguments).await {
+                            Ok(data) => {
+                                if format == "json" {
+                                    println!("{}", serde_json::to_string_pretty(&data)?);
+                                } else {
+                                    print_synonyms(&data);
+                                }
+                            }
+                            Err(e) => {
+                                eprintln!("Failed to list synonyms: {}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                    SynonymAction::Add { term, synonyms, project } => {
+                        let mut arguments = serde_json::json!({
+                            "term": term,
+                            "synonyms": synonyms
+                        });
+                        if let Some(project) = project {
+                            arguments["project_id"] = serde_json::json!(project);
+                        }
+
+                        match client.make_request("set_synonyms", arguments).await {
+                            Ok(data) => println!(
+                                "Synonyms of \"{}\" saved ({}).",
+                                term,
+                                data["synonym"]["id"].as_str().unwrap_or("")
+                            ),
+                            Err(e) => {
+                                eprintln!("Failed to save synonyms: {}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                    SynonymAction::Delete { id } => {
+                        match client.make_request("delete_synonym", serde_json::json!({ "id": id })).await {
+                            Ok(_) => println!("Synonym entry {} deleted.", id),
+                            Err(e) => {
+                                eprintln!("Failed to delete synonym entry: {}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                }
+            }
             Commands::Project { action } => {
                 match action {
                     ProjectAction::List { format } => {
@@ -643,8 +1338,17 @@ async fn main() -> Result<()> {
 }
 
 fn print_search_results(data: &serde_json::Value) {
+    if let Some(suggestion) = data.get("suggestion").and_then(|s| s.as_str()) {
+        if data["used_suggestion"].as_bool().unwrap_or(false) {
+            println!("No exact matches. Showing results for \"{}\"\n", suggestion);
+        } else {
+            println!("Did you mean: {}?\n", suggestion);
+        }
+    }
+
     if let Some(results) = data.get("results").and_then(|r| r.as_array()) {
-        println!("Found {} results:\n", results.len());
+        let total = data["total"].as_u64().unwrap_or(results.len() as u64);
+        println!("Found {} results (showing {}):\n", total, results.len());
         
         for (i, result) in results.iter().enumerate() {
             if let Some(document) = result.get("document") {
@@ -664,12 +1368,44 @@ fn print_search_results(data: &serde_json::Value) {
                         );
                     }
                 }
+                if let Some(explanation) = result.get("explanation") {
+                    print_explanation(explanation);
+                }
                 println!();
             }
         }
     }
 }
 
+fn print_explanation(explanation: &serde_json::Value) {
+    println!("   Score: {:.2}", explanation["score"].as_f64().unwrap_or(0.0));
+    let components = explanation["components"].as_array().cloned().unwrap_or_default();
+    let filters = explanation["filters"].as_array().cloned().unwrap_or_default();
+    let chunks: Vec<String> = explanation["matched_chunks"]
+        .as_array()
+        .map(|ids| ids.iter().map(|id| id.to_string()).collect())
+        .unwrap_or_default();
+
+    for component in &components {
+        println!(
+            "   ├─ {:<9} +{:.2}  {}",
+            component["signal"].as_str().unwrap_or(""),
+            component["score"].as_f64().unwrap_or(0.0),
+            component["detail"].as_str().unwrap_or("")
+        );
+    }
+    println!("   ├─ filters");
+    for filter in &filters {
+        println!(
+            "   │  ├─ {}: {}",
+            filter["filter"].as_str().unwrap_or(""),
+            filter["detail"].as_str().unwrap_or("")
+        );
+    }
+    let chunks = if chunks.is_empty() { "none".to_string() } else { chunks.join(", ") };
+    println!("   └─ matched chunks: {}", chunks);
+}
+
 fn print_document(data: &serde_json::Value) {
     if let Some(document) = data.get("document") {
         println!("Document: {}", document["filename"].as_str().unwrap_or("Unknown"));
@@ -691,18 +1427,73 @@ fn print_summary(data: &serde_json::Value) {
     }
     
     if let Some(summary) = data.get("summary") {
-        println!("Summary:");
+        if data["cached"] == true {
+            println!("Summary (cached):");
+        } else {
+            println!("Summary:");
+        }
         println!("{}", summary.as_str().unwrap_or(""));
     }
 }
 
+fn print_collection_summary(data: &serde_json::Value) {
+    let collection = &data["collection"];
+    println!(
+        "Collection: {} {}",
+        collection["kind"].as_str().unwrap_or("").replace('_', " "),
+        collection["value"].as_str().unwrap_or("")
+    );
+    println!();
+
+    println!("Overview:");
+    println!("{}", data["overview"].as_str().unwrap_or(""));
+
+    if let Some(documents) = data["documents"].as_array().filter(|d| !d.is_empty()) {
+        println!("\nDocuments:");
+        for item in documents {
+            let marker = match item["marker"].as_u64() {
+                Some(marker) => format!("[{}]", marker),
+                None => "[-]".to_string(),
+            };
+            let cited = data["citations"]
+                .as_array()
+                .and_then(|citations| citations.iter().find(|c| c["marker"] == item["marker"]))
+                .is_some_and(|c| c["used"] != false);
+            let mut notes = Vec::new();
+            if item["cached"] == true {
+                notes.push("cached");
+            }
+            if item["marker"].is_null() {
+                notes.push("left out for length");
+            } else if !cited {
+                notes.push("not cited");
+            }
+            let notes = if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) };
+            println!("  {} {}{}", marker, item["document"]["filename"].as_str().unwrap_or("Unknown"), notes);
+        }
+    }
+
+    if let Some(skipped) = data["skipped"].as_array().filter(|s| !s.is_empty()) {
+        println!("\nSkipped {} unreadable document(s):", skipped.len());
+        for item in skipped {
+            println!("  - {}: {}", item["path"].as_str().unwrap_or("Unknown"), item["error"].as_str().unwrap_or(""));
+        }
+    }
+}
+
 fn print_answer(data: &serde_json::Value) {
     if let Some(answer) = data.get("answer") {
         println!("Answer:");
         println!("{}", answer.as_str().unwrap_or(""));
         println!();
     }
-    
+    print_answer_sources(data);
+}
+
+fn print_answer_sources(data: &serde_json::Value) {
+    if let Some(error) = data["model_error"].as_str() {
+        eprintln!("{}", error);
+    }
     if let Some(confidence) = data.get("confidence") {
         println!("Confidence: {}", confidence.as_str().unwrap_or("Unknown"));
     }
@@ -711,15 +1502,235 @@ fn print_answer(data: &serde_json::Value) {
         if !citations.is_empty() {
             println!("\nSources:");
             for (i, citation) in citations.iter().enumerate() {
-                println!("  {}. {}", i + 1, citation["filename"].as_str().unwrap_or("Unknown"));
+                let marker = citation["marker"].as_u64().unwrap_or(i as u64 + 1);
+                let unused = if citation["used"] == false { " (not cited)" } else { "" };
+                println!("  [{}] {}{}", marker, citation["filename"].as_str().unwrap_or("Unknown"), unused);
+            }
+        }
+    }
+
+    if let Some(check) = data.get("citation_check").filter(|c| c.is_object()) {
+        if check["uncited"] == true {
+            println!("\nWarning: the answer does not cite any source");
+        } else if let Some(sentences) = check["uncited_sentences"].as_array().filter(|s| !s.is_empty()) {
+            println!("\nWarning: {} sentence(s) cite no source:", sentences.len());
+            for sentence in sentences {
+                println!("  - {}", sentence.as_str().unwrap_or(""));
+            }
+        }
+    }
+}
+
+fn print_similar(data: &serde_json::Value) {
+    if let Some(similar) = data.get("similar").and_then(|s| s.as_array()) {
+        if similar.is_empty() {
+            println!("No similar notes found.");
+            return;
+        }
+        println!("Similar notes:\n");
+
+        for (i, item) in similar.iter().enumerate() {
+            let document = &item["document"];
+            println!("{}. {} (score: {:.2})", i + 1, document["filename"].as_str().unwrap_or("Unknown"), item["score"].as_f64().unwrap_or(0.0));
+            println!("   ID: {}", document["id"].as_str().unwrap_or("Unknown"));
+            if let Some(reasons) = item["reasons"].as_array() {
+                let reasons: Vec<&str> = reasons.iter().filter_map(|r| r.as_str()).collect();
+                if !reasons.is_empty() {
+                    println!("   Why: {}", reasons.join("; "));
+                }
             }
+            println!();
+        }
+    }
+}
+
+fn print_saved_searches(data: &serde_json::Value) {
+    if let Some(saved_searches) = data.get("saved_searches").and_then(|s| s.as_array()) {
+        if saved_searches.is_empty() {
+            println!("No saved searches.");
+            return;
+        }
+        println!("Saved searches:\n");
+
+        for saved in saved_searches {
+            let name = saved["name"].as_str().unwrap_or("Unknown");
+            match saved.get("total") {
+                Some(total) => {
+                    let total = total.as_u64().map(|t| t.to_string()).unwrap_or_else(|| "?".to_string());
+                    println!("{} ({} matches)", name, total);
+                }
+                None => println!("{}", name),
+            }
+            println!("   Query: {} [{}]", saved["query"].as_str().unwrap_or(""), saved["mode"].as_str().unwrap_or("keyword"));
+            if let Some(filters) = saved["filters"].as_object() {
+                let active: Vec<String> = filters
+                    .iter()
+                    .filter(|(_, value)| !value.is_null())
+                    .map(|(name, value)| format!("{}={}", name, value))
+                    .collect();
+                if !active.is_empty() {
+                    println!("   Filters: {}", active.join(", "));
+                }
+            }
+            println!("   Last run: {}", saved["last_run_at"].as_str().unwrap_or("never"));
+            println!();
+        }
+    }
+}
+
+fn print_prompt_templates(data: &serde_json::Value) {
+    println!("Answer language: {}\n", data["language"].as_str().unwrap_or("auto"));
+    if let Some(effective) = data["effective"].as_array() {
+        for template in effective {
+            println!(
+                "{} template ({}):",
+                template["kind"].as_str().unwrap_or(""),
+                template["source"].as_str().unwrap_or("")
+            );
+            println!("{}\n", template["template"].as_str().unwrap_or(""));
+        }
+    }
+    if let Some(overrides) = data["overrides"].as_array() {
+        if !overrides.is_empty() {
+            println!("Overrides:");
+            for template in overrides {
+                println!(
+                    "  {} - {}",
+                    template["kind"].as_str().unwrap_or(""),
+                    template["project_id"].as_str().unwrap_or("global")
+                );
+            }
+        }
+    }
+}
+
+fn print_models(data: &serde_json::Value) {
+    let defaults = &data["defaults"];
+    println!(
+        "Provider: {}\nAnswer model: {}\nSummary model: {}\nEmbedding model: {}\n",
+        data["provider"].as_str().unwrap_or(""),
+        defaults["answer"].as_str().unwrap_or(""),
+        defaults["summary"].as_str().unwrap_or(""),
+        defaults["embedding"].as_str().unwrap_or("")
+    );
+    match data["models"].as_array() {
+        Some(models) if !models.is_empty() => {
+            println!("Installed models:");
+            for model in models {
+                let details: Vec<&str> = ["parameter_size", "quantization"]
+                    .iter()
+                    .filter_map(|field| model[*field].as_str())
+                    .collect();
+                if details.is_empty() {
+                    println!("  {}", model["name"].as_str().unwrap_or(""));
+                } else {
+                    println!("  {} ({})", model["name"].as_str().unwrap_or(""), details.join(", "));
+                }
+            }
+        }
+        _ => println!("No models installed."),
+    }
+}
+
+fn print_model_details(data: &serde_json::Value) {
+    let model = &data["model"];
+    println!("Model: {}", model["name"].as_str().unwrap_or(""));
+    for (label, field) in [("Family", "family"), ("Parameters", "parameter_size"), ("Quantization", "quantization"), ("Modified", "modified_at")] {
+        if let Some(value) = model[field].as_str() {
+            println!("{}: {}", label, value);
+        }
+    }
+    match model["context_window"].as_u64() {
+        Some(window) => println!("Context window: {} tokens", window),
+        None => println!("Context window: unknown"),
+    }
+    if let Some(tasks) = data["used_for"].as_array() {
+        if !tasks.is_empty() {
+            let tasks: Vec<&str> = tasks.iter().filter_map(|task| task.as_str()).collect();
+            println!("Used for: {}", tasks.join(", "));
+        }
+    }
+}
+
+fn print_model_check(check: &serde_json::Value) {
+    let provider = check["provider"].as_str().unwrap_or("");
+    if check["reachable"] != true {
+        println!("The {} server is not reachable: {}", provider, check["error"].as_str().unwrap_or("unknown error"));
+        return;
+    }
+    println!("The {} server is reachable.", provider);
+    if check["circuit"]["state"] == "open" {
+        println!(
+            "Calls are failing fast after {} consecutive failures; retrying in {} s.",
+            check["circuit"]["consecutive_failures"],
+            check["circuit"]["retry_in_secs"]
+        );
+    }
+    if let Some(models) = check["models"].as_array() {
+        for status in models {
+            println!(
+                "  {} model {}: {}",
+                status["task"].as_str().unwrap_or(""),
+                status["model"].as_str().unwrap_or(""),
+                if status["available"] == true { "installed" } else { "missing" }
+            );
+        }
+    }
+}
+
+fn print_synonyms(data: &serde_json::Value) {
+    if let Some(synonyms) = data.get("synonyms").and_then(|s| s.as_array()) {
+        if synonyms.is_empty() {
+            println!("No synonyms defined.");
+            return;
+        }
+        println!("Synonyms:\n");
+
+        for entry in synonyms {
+            let expansions: Vec<&str> = entry["synonyms"]
+                .as_array()
+                .map(|s| s.iter().filter_map(|v| v.as_str()).collect())
+                .unwrap_or_default();
+            println!("{} => {}", entry["term"].as_str().unwrap_or(""), expansions.join(", "));
+            println!("   Project: {}", entry["project_id"].as_str().unwrap_or("all projects"));
+            println!("   ID: {}", entry["id"].as_str().unwrap_or(""));
+            println!();
+        }
+    }
+}
+
+fn print_conversations(data: &serde_json::Value) {
+    if let Some(conversations) = data.get("conversations").and_then(|c| c.as_array()) {
+        if conversations.is_empty() {
+            println!("No conversations yet.");
+            return;
+        }
+        println!("Conversations:\n");
+
+        for conversation in conversations {
+            println!("{} ({} turns)", conversation["title"].as_str().unwrap_or("Untitled"), conversation["turn_count"].as_u64().unwrap_or(0));
+            println!("   Last active: {}", conversation["updated_at"].as_str().unwrap_or(""));
+            println!("   ID: {}", conversation["id"].as_str().unwrap_or(""));
+            println!();
+        }
+    }
+}
+
+fn print_conversation(data: &serde_json::Value) {
+    let conversation = &data["conversation"];
+    println!("Conversation: {}", conversation["title"].as_str().unwrap_or("Untitled"));
+    if let Some(turns) = data.get("turns").and_then(|t| t.as_array()) {
+        for turn in turns {
+            println!("\nask> {}", turn["question"].as_str().unwrap_or(""));
+            println!("{}", turn["answer"].as_str().unwrap_or(""));
         }
     }
 }
 
 fn print_notes_list(data: &serde_json::Value) {
     if let Some(notes) = data.get("notes").and_then(|n| n.as_array()) {
-        println!("Indexed documents ({}):\n", notes.len());
+        let total = data["total"].as_u64().unwrap_or(notes.len() as u64);
+        println!("Indexed documents ({} of {}):\n", notes.len(), total);
         
         for (i, note) in notes.iter().enumerate() {
             println!("{}. {}", i + 1, note["filename"].as_str().unwrap_or("Unknown"));
@@ -11,6 +11,8 @@ import {
 import {
   MCPTool,
   SearchResult,
+  SearchResponse,
+  SearchFacets,
   SearchFilters,
   Document,
   Project,
@@ -36,6 +38,11 @@ const SearchInterface: React.FC<SearchInterfaceProps> = ({
 }) => {
   const { state, updateSearchState } = useStatePersistence();
   const [isLoading, setIsLoading] = useState(false);
+  const [suggestion, setSuggestion] = useState<{
+    text: string;
+    used: boolean;
+  } | null>(null);
+  const [facets, setFacets] = useState<SearchFacets | null>(null);
   const [showVersionHistory, setShowVersionHistory] = useState(false);
   const [versionHistoryDocument, setVersionHistoryDocument] =
     useState<Document | null>(null);
@@ -59,8 +66,9 @@ const SearchInterface: React.FC<SearchInterfaceProps> = ({
     documentContent,
   } = state.search;
 
-  const searchNotes = async () => {
-    if (!query.trim()) return;
+  const searchNotes = async (queryOverride?: string) => {
+    const searchQuery = (queryOverride ?? query).trim();
+    if (!searchQuery) return;
 
     setIsLoading(true);
     try {
@@ -69,10 +77,11 @@ const SearchInterface: React.FC<SearchInterfaceProps> = ({
       const request: any = {
         tool: 'search_notes',
         arguments: {
-          query: query.trim(),
+          query: searchQuery,
           limit: 20,
           offset: 0,
           include_historical: includeHistorical,
+          include_facets: true,
         },
       };
 
@@ -90,14 +99,27 @@ const SearchInterface: React.FC<SearchInterfaceProps> = ({
 
       const data = await response.json();
       if (data.success && data.data) {
-        updateSearchState({ results: data.data.results || [] });
+        const searchResponse = data.data as SearchResponse;
+        updateSearchState({ results: searchResponse.results || [] });
+        setFacets(searchResponse.facets ?? null);
+        setSuggestion(
+          searchResponse.suggestion
+            ? {
+                text: searchResponse.suggestion,
+                used: !!searchResponse.used_suggestion,
+              }
+            : null
+        );
       } else {
         console.error('Search failed:', data.error);
         updateSearchState({ results: [] });
+        setFacets(null);
+        setSuggestion(null);
       }
     } catch (error) {
       console.error('Search error:', error);
       updateSearchState({ results: [] });
+      setSuggestion(null);
     } finally {
       setIsLoading(false);
     }
@@ -224,7 +246,7 @@ const SearchInterface: React.FC<SearchInterfaceProps> = ({
             Filters
           </Button>
           <Button
-            onClick={searchNotes}
+            onClick={() => searchNotes()}
             disabled={isLoading || !query.trim()}
             loading={isLoading}
             variant='primary'
@@ -300,7 +322,14 @@ const SearchInterface: React.FC<SearchInterfaceProps> = ({
                         }}
                         className={styles.filterOptionInput}
                       />
-                      <span className={styles.filterOptionText}>.{type}</span>
+                      <span className={styles.filterOptionText}>
+                        .{type}
+                        {facets &&
+                          ` (${
+                            facets.file_types.find(f => f.value === type)
+                              ?.count ?? 0
+                          })`}
+                      </span>
                     </label>
                   ))}
                 </div>
@@ -365,6 +394,23 @@ const SearchInterface: React.FC<SearchInterfaceProps> = ({
             </div>
           )}
 
+          {!isLoading && suggestion && (
+            <div className={styles.noResults}>
+              {suggestion.used ? 'Showing results for ' : 'Did you mean '}
+              <button
+                type="button"
+                className={styles.suggestionLink}
+                onClick={() => {
+                  updateSearchState({ query: suggestion.text });
+                  searchNotes(suggestion.text);
+                }}
+              >
+                {suggestion.text}
+              </button>
+              {suggestion.used ? '' : '?'}
+            </div>
+          )}
+
           {!isLoading && results.length === 0 && query && (
             <div className={styles.noResults}>
               No results found for "{query}"
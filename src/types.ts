@@ -12,6 +12,19 @@ export interface Project {
   updated_at: string;
 }
 
+export interface SavedSearch {
+  id: string;
+  name: string;
+  query: string;
+  filters: Partial<SearchFilters> | null;
+  mode: 'keyword' | 'semantic';
+  created_at: string;
+  updated_at: string;
+  last_run_at: string | null;
+  /** Current number of matches; only set when listing */
+  total?: number | null;
+}
+
 export interface IndexedFolder {
   path: string;
   project_id?: string;
@@ -35,6 +48,7 @@ export interface Document {
   version: number;
   is_latest: boolean;
   project_id?: string;
+  language?: string;
 }
 
 export interface Snippet {
@@ -42,12 +56,64 @@ export interface Snippet {
   start_pos: number;
   end_pos: number;
   highlighted: string;
+  matches: MatchRange[];
+  chunk_id: number;
+}
+
+export interface MatchRange {
+  start: number;
+  end: number;
 }
 
 export interface SearchResult {
   document: Document;
   score: number;
   snippets: Snippet[];
+  explanation?: ScoreExplanation;
+}
+
+export interface ScoreComponent {
+  signal: 'filename' | 'title' | 'content' | 'phrase' | 'recency' | 'semantic';
+  detail: string;
+  score: number;
+}
+
+export interface FilterDecision {
+  filter: string;
+  detail: string;
+}
+
+export interface ScoreExplanation {
+  score: number;
+  components: ScoreComponent[];
+  filters: FilterDecision[];
+  matched_chunks: number[];
+}
+
+export interface SearchResponse {
+  results: SearchResult[];
+  total: number;
+  limit: number;
+  offset: number | null;
+  next_cursor?: string | null;
+  suggestion?: string | null;
+  used_suggestion?: boolean;
+  as_of?: string | null;
+  facets?: SearchFacets | null;
+}
+
+export interface FacetCount {
+  value: string;
+  label?: string | null;
+  count: number;
+}
+
+export interface SearchFacets {
+  file_types: FacetCount[];
+  tags: FacetCount[];
+  projects: FacetCount[];
+  folders: FacetCount[];
+  years: FacetCount[];
 }
 
 export interface SearchFilters {
@@ -70,7 +136,11 @@ export interface MCPResponse {
 
 export interface QAAnswer {
   answer: string;
+  // 'model_unavailable' answers carry the citations and the reason in `model_error`
+  status: 'answered' | 'no_context' | 'model_unavailable';
+  model_error?: string;
   confidence: 'low' | 'medium' | 'high';
+  confidence_score: number;
   citations: Citation[];
   context_chunks: number;
 }
@@ -83,7 +153,11 @@ export interface Citation {
   excerpt: string;
   used_version: number;
   latest_version: number;
+  indexed_at?: string;
   is_latest: boolean;
+  score: number;
+  lexical_rank: number | null;
+  semantic_rank: number | null;
 }
 
 export interface IndexingResult {
@@ -127,3 +201,159 @@ export interface ExclusionPattern {
   is_glob: boolean;
   created_at: string;
 }
+
+export interface SimilarDocument {
+  document: Document;
+  score: number;
+  reasons: string[];
+  shared_tags: string[];
+  shared_terms: string[];
+}
+
+export interface Suggestion {
+  text: string;
+  kind: 'title' | 'heading' | 'tag' | 'term';
+  document_frequency: number;
+}
+
+export interface Synonym {
+  id: string;
+  project_id?: string;
+  term: string;
+  synonyms: string[];
+  created_at: string;
+  updated_at: string;
+}
+
+// Server-Sent Events of POST /api/stream (answer_question, summarize_note)
+export type StreamEvent =
+  | { event: 'citations'; citations: Record<string, unknown>[] }
+  | { event: 'progress'; stage: 'map' | 'reduce'; completed: number; total: number }
+  | { event: 'token'; text: string }
+  | { event: 'done'; metadata: Record<string, unknown> }
+  | { event: 'error'; message: string };
+
+export type PromptKind = 'answer' | 'summary';
+
+export interface PromptTemplate {
+  project_id?: string;
+  kind: PromptKind;
+  template: string;
+  updated_at: string;
+}
+
+export interface ResolvedTemplate {
+  kind: PromptKind;
+  template: string;
+  source: 'project' | 'global' | 'config' | 'default';
+  project_id?: string;
+}
+
+export interface RenderedPrompt {
+  prompt: string;
+  language: string;
+  template: ResolvedTemplate;
+}
+
+export interface Conversation {
+  id: string;
+  title?: string;
+  project_ids: string[];
+  turn_count: number;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface ConversationTurn {
+  id: string;
+  conversation_id: string;
+  position: number;
+  question: string;
+  standalone_question: string;
+  answer: string;
+  citations: Record<string, unknown>[];
+  created_at: string;
+}
+
+// Result of verifying an answer's [n] markers (answer_question `citation_check`)
+export interface CitationCheck {
+  used: number[];
+  invalid_markers: number[];
+  uncited_sentences: string[];
+  uncited: boolean;
+}
+
+// How a prompt was fitted into the model's context window (`context_budget`)
+export interface BudgetReport {
+  context_window: number;
+  window_source: 'config' | 'ollama' | 'openai' | 'default';
+  answer_reserve: number;
+  prompt_tokens: number;
+  context_tokens: number;
+  truncated: boolean;
+  dropped_chunks: number;
+  trimmed_chunks: number;
+}
+
+// A document of a summarize_collection digest; `marker` is null when its
+// summary did not fit the overview prompt
+export interface CollectionDocument {
+  document: Document;
+  summary: string;
+  cached: boolean;
+  marker: number | null;
+}
+
+export interface CollectionSummary {
+  collection: { kind: 'project' | 'folder' | 'tag' | 'saved_search'; value: string };
+  length: 'short' | 'medium' | 'long';
+  model: string;
+  language: string;
+  overview: string;
+  documents: CollectionDocument[];
+  skipped: { document_id: string; path: string; error: string }[];
+  citations: Record<string, unknown>[];
+  citation_check: CitationCheck | null;
+  context_budget: BudgetReport;
+}
+
+// A model installed on the LLM server (list_models)
+export interface ModelInfo {
+  name: string;
+  size?: number;
+  modified_at?: string;
+  family?: string;
+  parameter_size?: string;
+  quantization?: string;
+}
+
+// show_model `model`; fields the server does not report are left out
+export interface ModelDetails extends ModelInfo {
+  context_window?: number;
+  parameters?: string;
+  template?: string;
+}
+
+export type ModelTask = 'answer' | 'summary' | 'embedding';
+
+export interface ModelStatus {
+  task: ModelTask;
+  model: string;
+  available: boolean;
+}
+
+// check_models `check`, also the `llm` field of GET /health
+export interface ModelCheck {
+  provider: string;
+  reachable: boolean;
+  models: ModelStatus[];
+  error?: string;
+  circuit: CircuitStatus;
+}
+
+// State of the circuit breaker in front of the model server
+export interface CircuitStatus {
+  state: 'closed' | 'open' | 'half_open';
+  consecutive_failures: number;
+  retry_in_secs?: number;
+}